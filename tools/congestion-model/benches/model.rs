@@ -0,0 +1,162 @@
+//! Benchmarks of the simulator itself, not of the simulated strategies.
+//!
+//! Workloads and seeds are fixed so that numbers are comparable across
+//! commits. Run them with
+//!
+//! ```bash
+//! cargo bench -p congestion-model
+//! ```
+
+use congestion_model::strategy::{NepStrategy, NoQueueShard, PriorityAging};
+use congestion_model::workload::{BalancedProducer, Producer};
+use congestion_model::{
+    CongestionStrategy, Model, ReceiptDefinition, ReceiptPriority, ShardId, TransactionBuilder,
+    TGAS,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::RngCore;
+
+const SEED: u64 = 42;
+
+/// Every round, each shard submits a fixed number of transactions whose
+/// receipts execute on the sender shard. With `priorities`, every other
+/// transaction has high priority.
+struct LocalChains {
+    tx_per_shard: usize,
+    chain_length: usize,
+    priorities: bool,
+}
+
+impl Producer for LocalChains {
+    fn init(&mut self, _shards: &[ShardId]) {}
+
+    fn produce_transactions(
+        &mut self,
+        _round: u64,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut out = vec![];
+        for &shard in shards {
+            for i in 0..self.tx_per_shard {
+                let priority = if self.priorities && i % 2 == 0 {
+                    ReceiptPriority::High
+                } else {
+                    ReceiptPriority::Normal
+                };
+                let mut tx = tx_factory(shard).with_priority(priority);
+                let receipt = ReceiptDefinition {
+                    receiver: shard,
+                    size: 100,
+                    attached_gas: TGAS,
+                    execution_gas: TGAS,
+                };
+                let mut prev = tx.add_first_receipt(receipt.clone(), 0);
+                for _ in 1..self.chain_length {
+                    prev = tx.new_outgoing_receipt(prev, receipt.clone());
+                }
+                out.push(tx);
+            }
+        }
+        out
+    }
+}
+
+fn model(
+    num_shards: usize,
+    strategy: impl Fn() -> Box<dyn CongestionStrategy>,
+    producer: Box<dyn Producer>,
+) -> Model {
+    let shards = (0..num_shards).map(|_| strategy()).collect();
+    Model::new(shards, producer).with_seed(SEED)
+}
+
+fn run_rounds(model: &mut Model, rounds: usize) {
+    for _ in 0..rounds {
+        model.step();
+    }
+}
+
+/// The full model loop with a realistic workload and strategy.
+fn balanced_rounds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("balanced_nep_20_rounds");
+    group.sample_size(10);
+    for num_shards in [4, 16, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(num_shards), &num_shards, |b, &n| {
+            b.iter_batched(
+                || model(n, || Box::<NepStrategy>::default(), Box::<BalancedProducer>::default()),
+                |mut model| run_rounds(&mut model, 20),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Many short runs one after another, like a parameter sweep does them. Either
+/// with a new model for each run or with one model reset before each run, see
+/// [`Model::reset`].
+fn repeated_runs(c: &mut Criterion) {
+    const RUNS: u64 = 1000;
+    let new_model =
+        || model(4, || Box::<NepStrategy>::default(), Box::<BalancedProducer>::default());
+    let mut group = c.benchmark_group("balanced_nep_1000_runs_of_5_rounds");
+    group.sample_size(10);
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            for seed in 0..RUNS {
+                let mut model = new_model().with_seed(seed);
+                run_rounds(&mut model, 5);
+            }
+        })
+    });
+    group.bench_function("reset", |b| {
+        b.iter_batched(
+            new_model,
+            |mut model| {
+                for seed in 0..RUNS {
+                    model.reset(seed);
+                    run_rounds(&mut model, 5);
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// Transaction conversion and receipt execution, with a strategy that spends
+/// no time on decisions and cheap receipts that never leave their shard.
+fn receipt_execution(c: &mut Criterion) {
+    c.bench_function("receipt_execution_1_shard_20_rounds", |b| {
+        b.iter_batched(
+            || {
+                let producer =
+                    LocalChains { tx_per_shard: 100, chain_length: 5, priorities: false };
+                model(1, || Box::new(NoQueueShard {}), Box::new(producer))
+            },
+            |mut model| run_rounds(&mut model, 20),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+/// Reordering a long incoming queue by priority, every round.
+fn priority_queue(c: &mut Criterion) {
+    c.bench_function("priority_aging_1_shard_20_rounds", |b| {
+        b.iter_batched(
+            || {
+                // twice the work a chunk can execute, so the queue keeps growing
+                let producer =
+                    LocalChains { tx_per_shard: 2000, chain_length: 1, priorities: true };
+                model(1, || Box::<PriorityAging>::default(), Box::new(producer))
+            },
+            |mut model| run_rounds(&mut model, 20),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, balanced_rounds, repeated_runs, receipt_execution, priority_queue);
+criterion_main!(benches);
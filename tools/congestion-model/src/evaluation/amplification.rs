@@ -0,0 +1,131 @@
+use super::{Histogram, TransactionStatus, TransactionView};
+use crate::Model;
+
+/// Receipts per transaction, over all finished and failed transactions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Amplification {
+    /// Executed receipts per transaction, see
+    /// [`crate::TransactionView::receipt_count`].
+    pub executed: Histogram,
+    /// Dropped receipts per transaction, 0 for all successful ones.
+    pub dropped: Histogram,
+    /// Receipts cut from each transaction before submission, see
+    /// [`crate::ModelConfig::max_outstanding_receipts_per_tx`].
+    pub truncated: Histogram,
+}
+
+impl Model {
+    /// Distribution of work amplification, how many receipts each completed
+    /// transaction executed.
+    ///
+    /// Transactions with many receipts are a congestion risk, they keep
+    /// adding work to the shards long after they were accepted. For a fixed
+    /// workload, the executed counts also check that the configured fan out
+    /// and depth are what really executed. Transactions that are still in
+    /// flight are not included.
+    pub fn amplification_histogram(&self) -> Amplification {
+        let mut amplification = self.retired.amplification.clone();
+        for view in self.transaction_views() {
+            amplification.add(&view);
+        }
+        amplification
+    }
+}
+
+impl Amplification {
+    pub(super) fn add(&mut self, view: &TransactionView) {
+        match view.status() {
+            TransactionStatus::FinishedSuccess | TransactionStatus::Failed => {
+                self.executed.add(view.receipt_count() as u64);
+                self.dropped.add(view.dropped_receipt_count() as u64);
+                self.truncated.add(view.truncated_receipt_count() as u64);
+            }
+            TransactionStatus::Init | TransactionStatus::Pending => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NoQueueShard;
+    use crate::workload::BalancedProducer;
+    use crate::{CongestionStrategy, Model, ModelConfig, ReceiptCapPolicy, RejectionReason};
+
+    fn run(drop_probability: f64) -> Model {
+        run_with(ModelConfig { drop_probability, ..ModelConfig::default() })
+    }
+
+    fn run_with(config: ModelConfig) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        // 1 + 3 receipts, each followed by a refund
+        let workload = BalancedProducer::with_sizes_and_fan_out(vec![100, 1000], 3);
+        let mut model = Model::new(shards, Box::new(workload)).with_config(config);
+        for _ in 0..20 {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn amplification_matches_workload_shape() {
+        let amplification = run(0.0).amplification_histogram();
+        assert!(amplification.executed.count() > 0);
+        assert_eq!(amplification.executed.min(), Some(8));
+        assert_eq!(amplification.executed.max(), Some(8));
+        assert_eq!(amplification.dropped.max(), Some(0));
+    }
+
+    fn capped(max_receipts: usize, receipt_cap_policy: ReceiptCapPolicy) -> Model {
+        run_with(ModelConfig {
+            max_outstanding_receipts_per_tx: Some(max_receipts),
+            receipt_cap_policy,
+            ..ModelConfig::default()
+        })
+    }
+
+    #[test]
+    fn truncation_cuts_every_transaction_to_the_cap() {
+        let model = capped(5, ReceiptCapPolicy::Truncate);
+        let amplification = model.amplification_histogram();
+        assert!(amplification.executed.count() > 0);
+        assert_eq!(amplification.executed.min(), Some(5));
+        assert_eq!(amplification.executed.max(), Some(5));
+        assert_eq!(amplification.truncated.min(), Some(3));
+        assert_eq!(amplification.truncated.max(), Some(3));
+        for view in model.transaction_views() {
+            // all receipts are still reachable from the first one
+            assert_eq!(view.receipts().len(), 5);
+        }
+        assert!(model.gas_ledger().is_balanced());
+    }
+
+    #[test]
+    fn rejection_applies_above_the_cap_only() {
+        let at_cap = capped(8, ReceiptCapPolicy::Reject);
+        assert_eq!(at_cap.tx_rejection_rate(), 0.0);
+        assert_eq!(at_cap.amplification_histogram().executed.min(), Some(8));
+
+        let below_cap = capped(7, ReceiptCapPolicy::Reject);
+        assert_eq!(below_cap.tx_rejection_rate(), 1.0);
+        assert_eq!(below_cap.progress().finished_transactions, 0);
+        let rejection = below_cap.transaction_views().next().unwrap().rejection().unwrap();
+        assert_eq!(
+            rejection.reason,
+            RejectionReason::TooManyReceipts { receipts: 8, max_receipts: 7 }
+        );
+        assert!(below_cap.gas_ledger().is_balanced());
+    }
+
+    #[test]
+    fn dropped_receipts_are_counted_separately() {
+        let model = run(0.05);
+        let amplification = model.amplification_histogram();
+        assert!(amplification.dropped.max().unwrap() > 0, "{amplification:?}");
+        assert!(amplification.executed.min().unwrap() < 8, "{amplification:?}");
+        for view in model.transaction_views() {
+            let total = view.receipts().len();
+            assert!(view.receipt_count() + view.dropped_receipt_count() <= total);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    ExecutionMode, GGas, Model, ModelConfig, Receipt, ReceiptId, Round, ShardId, Transaction,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Gas a shard has to burn for a transaction, and the earliest round it can.
+struct Work {
+    shard: ShardId,
+    earliest: Round,
+    gas: GGas,
+}
+
+/// Work of the transactions considered for the bound, see
+/// [`Model::optimal_completion_bound`].
+#[derive(Clone, Debug, Default)]
+pub(super) struct BoundWork {
+    /// Latest end of a critical path.
+    critical_path: Round,
+    /// Work by shard.
+    arrivals: BTreeMap<ShardId, Arrivals>,
+}
+
+/// Work that becomes available on one shard.
+#[derive(Clone, Debug, Default)]
+struct Arrivals {
+    /// Gas by the earliest round it can be burnt in.
+    gas: BTreeMap<Round, GGas>,
+    /// Largest single piece of work.
+    max_item: GGas,
+}
+
+impl Model {
+    /// Lower bound for the round in which all transactions submitted so far
+    /// could complete, under an optimal scheduler.
+    ///
+    /// The bound is the maximum of two simpler bounds:
+    ///
+    /// - Critical paths: A transaction is converted no earlier than the round
+    ///   it is submitted in. Each receipt executes no earlier than the round
+    ///   after its predecessor, because receipts are delivered in the next
+    ///   round, even to the own shard. The longest receipt chain of a
+    ///   transaction therefore sets the earliest round it can complete.
+    /// - Shard capacity: Each shard has to burn the conversion gas of the
+    ///   transactions it sends, the execution gas of the receipts it receives
+    ///   and the forwarding gas for receipts it sends to other shards. Each
+    ///   piece of work becomes available at the earliest round from the
+    ///   critical paths. Per round, a shard burns at most its gas limit plus one
+    ///   receipt, since the gas limit is a soft limit. The bound assumes the
+    ///   shard burns exactly that much whenever work is available, splitting
+    ///   receipts across rounds if needed. With
+    ///   [`crate::ModelConfig::gas_limit_jitter`], the largest possible gas
+    ///   limit is used.
+    ///
+    /// For transactions with [`ExecutionMode::Sequential`], the critical path
+    /// includes all receipts, in the order the model creates them.
+    ///
+    /// No other constraints are considered. In particular, the two bounds are
+    /// computed independently. A receipt that has to wait for capacity does
+    /// not delay its successors in the bound. Strategies also need to decide
+    /// without knowing the future, while the bound knows all transactions
+    /// upfront. The bound is a yardstick for how far a strategy is from
+    /// optimal, it is usually not achievable.
+    ///
+    /// Transactions the producer submits after the current round are not
+    /// known and not included. Neither are transactions evicted from a
+    /// mempool. Dropped receipts are included, an optimal scheduler would
+    /// have executed them. Returns 0 without transactions.
+    pub fn optimal_completion_bound(&self) -> Round {
+        let receipts = self.all_receipts();
+        let mut work = self.retired.bound_work.clone();
+        for tx in self.transactions.all_transactions() {
+            work.add_transaction(tx, &receipts, &self.config);
+        }
+        let mut bound = work.critical_path;
+        for (shard, arrivals) in work.arrivals {
+            bound = bound.max(self.capacity_bound(shard, arrivals));
+        }
+        bound
+    }
+
+    /// Every receipt of every transaction, including the ones currently in a
+    /// queue or in transit, which are not stored with their transaction.
+    pub(super) fn all_receipts(&self) -> HashMap<ReceiptId, &Receipt> {
+        let mut receipts: HashMap<ReceiptId, &Receipt> =
+            self.acks.in_transit().map(|receipt| (receipt.id, receipt)).collect();
+        for tx in self.transactions.all_transactions() {
+            let stored = tx
+                .future_receipts
+                .values()
+                .chain(tx.executed_receipts.values())
+                .chain(tx.dropped_receipts.values());
+            receipts.extend(stored.map(|receipt| (receipt.id, receipt)));
+        }
+        for &shard in &self.shard_ids {
+            for receipt in self.queues.shard_queues(shard).flat_map(|queue| queue.iter()) {
+                receipts.insert(receipt.id, receipt);
+            }
+        }
+        for (_, receipt) in &self.unapplied {
+            receipts.insert(receipt.id, receipt);
+        }
+        receipts
+    }
+
+    /// Round in which the shard has burnt all the given work at the earliest,
+    /// simulating a shard that always burns its full capacity.
+    fn capacity_bound(&self, shard: ShardId, arrivals: Arrivals) -> Round {
+        let Arrivals { gas: mut arrivals, max_item } = arrivals;
+        let Some((&first, _)) = arrivals.first_key_value() else {
+            return 0;
+        };
+
+        let mut backlog: GGas = 0;
+        let mut done = 0;
+        let mut round = first;
+        while backlog > 0 || !arrivals.is_empty() {
+            backlog = backlog.saturating_add(arrivals.remove(&round).unwrap_or(0));
+            if backlog > 0 {
+                // the largest possible limit under jitter
+                let gas_limit = self.config.gas_limit(shard, round) as f64
+                    * (1.0 + self.config.gas_limit_jitter);
+                let capacity = (gas_limit as GGas).saturating_add(max_item);
+                backlog = backlog.saturating_sub(capacity);
+                done = round;
+            }
+            round += 1;
+        }
+        done
+    }
+}
+
+impl BoundWork {
+    /// Add the work of a transaction. Evicted and rejected transactions have
+    /// none.
+    pub(super) fn add_transaction(
+        &mut self,
+        tx: &Transaction,
+        receipts: &HashMap<ReceiptId, &Receipt>,
+        config: &ModelConfig,
+    ) {
+        if tx.evicted_at.is_some() || tx.rejection.is_some() {
+            return;
+        }
+        for w in tx_work(tx, receipts, config) {
+            self.critical_path = self.critical_path.max(w.earliest);
+            let arrivals = self.arrivals.entry(w.shard).or_default();
+            let gas = arrivals.gas.entry(w.earliest).or_default();
+            *gas = gas.saturating_add(w.gas);
+            arrivals.max_item = arrivals.max_item.max(w.gas);
+        }
+    }
+}
+
+/// All work of a transaction, with the earliest round for each piece of it.
+fn tx_work(
+    tx: &Transaction,
+    receipts: &HashMap<ReceiptId, &Receipt>,
+    config: &ModelConfig,
+) -> Vec<Work> {
+    let mut out = vec![Work {
+        shard: tx.sender_shard,
+        earliest: tx.submitted_at,
+        gas: tx.tx_conversion_cost,
+    }];
+    // earliest execution round and creator of each receipt
+    let mut earliest: HashMap<ReceiptId, (Round, ShardId)> = HashMap::new();
+    if tx.execution_mode == ExecutionMode::Sequential {
+        // one receipt per round, each created by its predecessor in the
+        // depth-first order
+        let mut creator = tx.sender_shard;
+        let mut execution = tx.submitted_at;
+        for id in tx.depth_first_receipts() {
+            execution += 1;
+            earliest.insert(id, (execution, creator));
+            creator = receipts[&id].receiver;
+        }
+    } else {
+        // (receipt, shard that creates it, round it is created)
+        let mut stack = vec![(tx.initial_receipt, tx.sender_shard, tx.submitted_at)];
+        while let Some((id, creator, created)) = stack.pop() {
+            let execution = created + 1;
+            if earliest.get(&id).is_some_and(|&(known, _)| known >= execution) {
+                continue;
+            }
+            earliest.insert(id, (execution, creator));
+            for &next in &tx.outgoing[&id] {
+                stack.push((next, receipts[&id].receiver, execution));
+            }
+        }
+    }
+    for (id, (execution, creator)) in earliest {
+        let receipt = receipts[&id];
+        out.push(Work {
+            shard: receipt.receiver,
+            earliest: execution,
+            gas: receipt.execution_gas(),
+        });
+        if creator != receipt.receiver {
+            let gas = config.forwarding_cost(receipt.size);
+            out.push(Work { shard: creator, earliest: execution - 1, gas });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{NepStrategy, NoQueueShard, TrafficLight};
+    use crate::workload::{BalancedProducer, Producer};
+    use crate::{CongestionStrategy, ReceiptDefinition, TransactionBuilder, TGAS};
+    use rand::RngCore;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Lets the inner producer submit transactions for the first few rounds.
+    struct FirstRounds {
+        inner: Box<dyn Producer>,
+        rounds: Round,
+    }
+
+    impl Producer for FirstRounds {
+        fn init(&mut self, shards: &[ShardId]) {
+            self.inner.init(shards);
+        }
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round > self.rounds {
+                return vec![];
+            }
+            self.inner.produce_transactions(round, shards, tx_factory, rng)
+        }
+    }
+
+    /// Submits a chain of five receipts hopping between shards in round 1.
+    struct Chain;
+
+    impl Producer for Chain {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            let receipt = |i: usize| ReceiptDefinition {
+                receiver: shards[i % shards.len()],
+                size: 100,
+                attached_gas: 10 * TGAS,
+                execution_gas: TGAS,
+            };
+            let mut tx = tx_factory(shards[0]);
+            let mut prev = tx.add_first_receipt(receipt(1), TGAS);
+            for i in 2..=5 {
+                prev = tx.new_outgoing_receipt(prev, receipt(i));
+            }
+            vec![tx]
+        }
+    }
+
+    /// Runs the model until all transactions completed and returns the
+    /// bound computed after the last submission and the round of the last
+    /// completion.
+    fn bound_and_actual(
+        shards: Vec<Box<dyn CongestionStrategy>>,
+        producer: Box<dyn Producer>,
+        submission_rounds: Round,
+    ) -> (Round, Round) {
+        let mut model = Model::new(shards, producer);
+        let last_completion = Rc::new(Cell::new(0));
+        let sink = last_completion.clone();
+        model.on_transaction_complete(move |_, round| sink.set(round));
+
+        for _ in 0..submission_rounds {
+            model.step();
+        }
+        let bound = model.optimal_completion_bound();
+        while model.progress().finished_transactions < model.transactions.all_transactions().count()
+        {
+            model.step();
+            assert!(model.round() < 1000, "model did not finish");
+        }
+        assert_eq!(bound, model.optimal_completion_bound());
+        (bound, last_completion.get())
+    }
+
+    #[test]
+    fn bound_of_a_single_chain_is_its_length() {
+        let shards = (0..3).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let (bound, actual) = bound_and_actual(shards, Box::new(Chain), 1);
+        assert_eq!(bound, 6);
+        assert_eq!(actual, 6);
+    }
+
+    #[test]
+    fn bound_does_not_exceed_actual_completion() {
+        let strategies: [fn() -> Box<dyn CongestionStrategy>; 2] =
+            [|| Box::<NepStrategy>::default(), || Box::<TrafficLight>::default()];
+        for strategy in strategies {
+            let shards = (0..4).map(|_| strategy()).collect();
+            let producer = FirstRounds { inner: Box::<BalancedProducer>::default(), rounds: 3 };
+            let (bound, actual) = bound_and_actual(shards, Box::new(producer), 3);
+            // more work than fits in the critical path
+            assert!(bound > 5, "{bound}");
+            assert!(bound <= actual, "{bound} > {actual}");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{ChunkStats, Model, Round, Transaction, TransactionStatus};
+
+impl Model {
+    /// One number between 0 and 1 that summarizes how congested the model
+    /// execution has been so far, independent of the number of shards.
+    ///
+    /// The index is the mean of two scores, each in `[0, 1)`:
+    ///
+    /// - Queue occupancy: For each chunk, `b` is the gas attached to all
+    ///   receipts queued on the shard after the chunk, divided by the chunk's
+    ///   gas limit. In other words, `b` is the backlog in chunks. The score is
+    ///   the mean of `b / (1 + b)` over all chunks.
+    /// - Latency stretch: For each transaction, the stretch is its delay
+    ///   divided by the fewest rounds it could take without congestion. That
+    ///   is the longest receipt chain, or the number of receipts for
+    ///   sequential transactions. Unresolved transactions use their current
+    ///   age and count with a stretch of at least 1. Failed transactions are
+    ///   ignored. With `s` the mean stretch, the score is `1 - 1 / s`.
+    ///
+    /// Both scores are 0 when nothing waits and approach 1 when queues and
+    /// delays grow without bound. Since they are averages over chunks and
+    /// transactions, the index is comparable between different numbers of
+    /// shards. It is 0 before the first round.
+    pub fn congestion_index(&self) -> f64 {
+        (self.occupancy_score() + self.stretch_score()) / 2.0
+    }
+
+    fn occupancy_score(&self) -> f64 {
+        let count = self.retired_chunks.chunks + self.chunk_stats.len();
+        if count == 0 {
+            return 0.0;
+        }
+        let sum =
+            self.retired_chunks.occupancy_sum + self.chunk_stats.iter().map(occupancy).sum::<f64>();
+        sum / count as f64
+    }
+
+    fn stretch_score(&self) -> f64 {
+        let mut sum = self.retired.stretch_sum;
+        let mut count = self.retired.stretch_count;
+        for stretch in self.transactions.all_transactions().filter_map(|tx| stretch(tx, self.round))
+        {
+            sum += stretch;
+            count += 1;
+        }
+        if count == 0 {
+            return 0.0;
+        }
+        1.0 - count as f64 / sum
+    }
+}
+
+/// How many times longer than necessary a transaction took, or has taken so
+/// far, at least 1. `None` for failed transactions.
+pub(super) fn stretch(tx: &Transaction, round: Round) -> Option<f64> {
+    let delay = match tx.status() {
+        TransactionStatus::FinishedSuccess => tx.delay(),
+        TransactionStatus::Init | TransactionStatus::Pending => round - tx.submitted_at,
+        TransactionStatus::Failed => return None,
+    };
+    Some((delay as f64 / tx.min_delay() as f64).max(1.0))
+}
+
+/// `b / (1 + b)` of a chunk, with `b` its backlog in chunks.
+pub(super) fn occupancy(stats: &ChunkStats) -> f64 {
+    match (stats.queued_gas, stats.gas_limit) {
+        (0, _) => 0.0,
+        (_, 0) => 1.0,
+        (queued, limit) => {
+            let backlog = queued as f64 / limit as f64;
+            backlog / (1.0 + backlog)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{NepStrategy, NoQueueShard};
+    use crate::workload::Producer;
+    use crate::{CongestionStrategy, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+    use rand::RngCore;
+
+    /// Every round, each shard sends the same number of transactions with a
+    /// 100 TGas receipt to the next shard, independent of the shard count.
+    struct Ring(usize);
+
+    impl Producer for Ring {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let mut out = vec![];
+            for (i, &sender) in shards.iter().enumerate() {
+                for _ in 0..self.0 {
+                    let mut tx = tx_factory(sender);
+                    let receipt = ReceiptDefinition {
+                        receiver: shards[(i + 1) % shards.len()],
+                        size: 1000,
+                        attached_gas: 100 * TGAS,
+                        execution_gas: 100 * TGAS,
+                    };
+                    tx.add_first_receipt(receipt, TGAS);
+                    out.push(tx);
+                }
+            }
+            out
+        }
+    }
+
+    fn index(
+        num_shards: usize,
+        strategy: fn() -> Box<dyn CongestionStrategy>,
+        tx_per_shard: usize,
+    ) -> f64 {
+        let shards = (0..num_shards).map(|_| strategy()).collect();
+        let mut model = Model::new(shards, Box::new(Ring(tx_per_shard)));
+        for _ in 0..200 {
+            model.step();
+        }
+        model.congestion_index()
+    }
+
+    #[test]
+    fn index_ranges_from_idle_to_saturated() {
+        let nep = || Box::<NepStrategy>::default() as _;
+        let no_queues = || Box::new(NoQueueShard {}) as _;
+        for num_shards in [4, 16] {
+            // 30% of the gas limit
+            let idle = index(num_shards, nep, 3);
+            assert!(idle < 0.1, "{idle}");
+            // twice the gas limit, and nothing stops new transactions
+            let saturated = index(num_shards, no_queues, 20);
+            assert!(saturated > 0.9, "{saturated}");
+        }
+    }
+}
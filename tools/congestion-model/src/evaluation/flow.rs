@@ -0,0 +1,143 @@
+use crate::{GGas, Model, ShardId, Transaction};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+impl Model {
+    /// Gas burnt so far, by the shard where the transaction originated and the
+    /// shard that burnt it.
+    ///
+    /// Includes transaction conversion, which always burns on the origin
+    /// shard, and receipt execution. Forwarding gas is charged to chunks
+    /// rather than to receipts and is not included, neither are the send costs
+    /// of receipts.
+    pub fn gas_flow(&self) -> BTreeMap<(ShardId, ShardId), GGas> {
+        let mut flow = self.retired.flow.clone();
+        for tx in self.transactions.all_transactions() {
+            add_flow(&mut flow, tx);
+        }
+        flow
+    }
+
+    /// Write [`Model::gas_flow`] as JSON, in the node and link format that
+    /// d3-sankey, Plotly and most other Sankey libraries consume.
+    ///
+    /// There is one node per origin shard, followed by one node per executing
+    /// shard. Each link goes from an origin to an executing shard and carries
+    /// the gas in GGas as its value.
+    ///
+    /// ```json
+    /// {"nodes":[{"name":"origin 0"},{"name":"shard 0"}],"links":[{"source":0,"target":1,"value":5000}]}
+    /// ```
+    pub fn write_flow_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let num_shards = self.shard_ids.len();
+        let origins = self.shard_ids.iter().map(|shard| format!("{{\"name\":\"origin {shard}\"}}"));
+        let executors =
+            self.shard_ids.iter().map(|shard| format!("{{\"name\":\"shard {shard}\"}}"));
+        let nodes: Vec<String> = origins.chain(executors).collect();
+        let links: Vec<String> = self
+            .gas_flow()
+            .into_iter()
+            .filter(|&(_, gas)| gas > 0)
+            .map(|((origin, executor), gas)| {
+                let target = num_shards + *executor;
+                format!("{{\"source\":{},\"target\":{target},\"value\":{gas}}}", *origin)
+            })
+            .collect();
+        writeln!(out, "{{\"nodes\":[{}],\"links\":[{}]}}", nodes.join(","), links.join(","))
+    }
+}
+
+pub(super) fn add_flow(flow: &mut BTreeMap<(ShardId, ShardId), GGas>, tx: &Transaction) {
+    if !tx.future_receipts.contains_key(&tx.initial_receipt) {
+        *flow.entry((tx.sender_shard, tx.sender_shard)).or_default() += tx.tx_conversion_cost;
+    }
+    for receipt in tx.executed_receipts.values() {
+        *flow.entry((receipt.origin_shard, receipt.receiver)).or_default() += receipt.gas_burnt();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::Producer;
+    use crate::{ReceiptDefinition, Round, TransactionBuilder, TGAS};
+    use rand::RngCore;
+
+    /// In round 1, shards 0 and 1 each submit a transaction that hops through
+    /// shards 1, 2 and 0.
+    struct Hops;
+
+    impl Producer for Hops {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            let receipt = |shard: usize| ReceiptDefinition {
+                receiver: shards[shard],
+                size: 100,
+                attached_gas: 10 * TGAS,
+                execution_gas: TGAS,
+            };
+            (0..2)
+                .map(|sender| {
+                    let mut tx = tx_factory(shards[sender]);
+                    let first = tx.add_first_receipt(receipt(1), TGAS);
+                    let second = tx.new_outgoing_receipt(first, receipt(2));
+                    tx.new_outgoing_receipt(second, receipt(0));
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    fn run_hops() -> Model {
+        let shards = (0..3).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(shards, Box::new(Hops));
+        for _ in 0..5 {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn provenance_propagates_through_hops() {
+        let model = run_hops();
+        for tx in model.transactions.all_transactions() {
+            assert_eq!(tx.executed_receipts.len(), 3);
+            for receipt in tx.executed_receipts.values() {
+                assert_eq!(receipt.origin_shard, tx.sender_shard);
+            }
+        }
+
+        let s = |i| model.shard_ids[i];
+        let expected = BTreeMap::from([
+            ((s(0), s(0)), 2 * TGAS),
+            ((s(0), s(1)), TGAS),
+            ((s(0), s(2)), TGAS),
+            ((s(1), s(0)), TGAS),
+            ((s(1), s(1)), 2 * TGAS),
+            ((s(1), s(2)), TGAS),
+        ]);
+        assert_eq!(model.gas_flow(), expected);
+    }
+
+    #[test]
+    fn flow_json_links_origins_to_shards() {
+        let mut json = vec![];
+        run_hops().write_flow_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with(r#"{"nodes":[{"name":"origin 0"},"#), "{json}");
+        assert!(json.contains(r#"{"name":"shard 2"}]"#), "{json}");
+        assert!(json.contains(r#"{"source":1,"target":5,"value":1000}"#), "{json}");
+        assert_eq!(json.matches("\"source\"").count(), 6);
+    }
+}
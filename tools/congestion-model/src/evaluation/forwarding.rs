@@ -0,0 +1,173 @@
+use crate::{GGas, Model, Round, ShardId};
+use std::collections::HashMap;
+
+/// Traffic caused by reliable delivery, see [`crate::ModelConfig::acks`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AckTraffic {
+    pub acks_sent: usize,
+    pub retransmitted_receipts: usize,
+    /// Retransmitted receipts that arrived although the receiver already had
+    /// them, because the ack was lost or late.
+    pub discarded_duplicates: usize,
+    /// Gas burnt for acks and retransmissions.
+    pub gas: GGas,
+}
+
+impl Model {
+    /// Total gas each shard burnt so far for forwarding receipts to other
+    /// shards.
+    pub fn forwarding_gas(&self) -> HashMap<ShardId, GGas> {
+        let mut out: HashMap<ShardId, GGas> = self
+            .shard_ids
+            .iter()
+            .map(|shard| {
+                (*shard, self.retired_chunks.shards.get(shard).map_or(0, |r| r.forwarding_gas))
+            })
+            .collect();
+        for stats in &self.chunk_stats {
+            *out.get_mut(&stats.shard).unwrap() += stats.forwarding_gas;
+        }
+        out
+    }
+
+    /// Total traffic for acks and retransmissions so far, over all shards.
+    pub fn ack_traffic(&self) -> AckTraffic {
+        let retired = &self.retired_chunks;
+        let mut traffic = AckTraffic {
+            acks_sent: retired.acks_sent,
+            retransmitted_receipts: retired.retransmitted_receipts,
+            discarded_duplicates: self.acks.discarded_duplicates,
+            gas: retired.ack_gas,
+        };
+        for stats in &self.chunk_stats {
+            traffic.acks_sent += stats.acks_sent;
+            traffic.retransmitted_receipts += stats.retransmitted_receipts;
+            traffic.gas += stats.ack_gas;
+        }
+        traffic
+    }
+
+    /// Number of receipts forwarded to a shard in the given round, i.e. the
+    /// receipts that arrive there at the start of the next round.
+    pub fn received_receipts(&self, round: Round, shard_id: ShardId) -> usize {
+        self.round_chunk_stats(round)
+            .iter()
+            .filter_map(|stats| stats.forwarded_receipts.get(&shard_id))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NepStrategy;
+    use crate::workload::{BalancedProducer, Producer, TransactionBuilder};
+    use crate::{GGas, Model, ModelConfig, ReceiptDefinition, Round, ShardId, GGAS, TGAS};
+    use rand::RngCore;
+
+    /// Submits nothing.
+    struct NoTransactions;
+
+    impl Producer for NoTransactions {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            _shards: &[ShardId],
+            _tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn forwarding_burns_forward_and_send_cost() {
+        let config = ModelConfig {
+            forward_gas: 2 * TGAS,
+            forward_gas_per_byte: GGAS,
+            ..ModelConfig::default()
+        };
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::new(NoTransactions)).with_config(config);
+        let (a, b) = (model.shard_ids[0], model.shard_ids[1]);
+        let mut tx = model.new_transaction(a);
+        let call = tx.add_first_receipt(
+            ReceiptDefinition {
+                receiver: b,
+                size: 1000,
+                attached_gas: 10 * TGAS,
+                execution_gas: 5 * TGAS,
+            },
+            TGAS,
+        );
+        tx.set_send_cost(call, 3 * TGAS);
+        model.submit(tx, 1);
+        assert_eq!(model.gas_ledger().send_burnt, 0);
+
+        // shard a converts the transaction and forwards the receipt to b
+        model.step();
+        let forwarding = model.config.forwarding_cost(1000);
+        assert_eq!(forwarding, 3 * TGAS);
+        let sender = &model.round_chunk_stats(1)[0];
+        assert_eq!(sender.shard, a);
+        assert_eq!(sender.gas_burnt, TGAS + forwarding + 3 * TGAS);
+        assert_eq!(sender.forwarding_gas, forwarding);
+        assert_eq!(sender.send_gas, 3 * TGAS);
+        assert_eq!(model.forwarding_gas()[&a], forwarding);
+        assert_eq!(model.forwarding_gas()[&b], 0);
+        // the chunk pays for forwarding, the transaction only for the send
+        let ledger = model.gas_ledger();
+        assert_eq!(ledger.conversion_burnt, TGAS);
+        assert_eq!(ledger.send_burnt, 3 * TGAS);
+        assert!(ledger.is_balanced(), "{ledger:?}");
+    }
+
+    #[test]
+    fn received_receipts_match_forwarded_receipts() {
+        let shards = (0..3).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        for _ in 0..10 {
+            model.step();
+        }
+        for round in 1..=10 {
+            let chunks = model.round_chunk_stats(round);
+            assert!(chunks.iter().all(|stats| stats.round == round));
+            let forwarded: usize =
+                chunks.iter().flat_map(|stats| stats.forwarded_receipts.values()).sum();
+            let received: usize =
+                model.shard_ids.iter().map(|&shard| model.received_receipts(round, shard)).sum();
+            assert_eq!(received, forwarded);
+        }
+        assert!(model.received_receipts(5, model.shard_ids[0]) > 0);
+        assert_eq!(model.received_receipts(0, model.shard_ids[0]), 0);
+        assert_eq!(model.received_receipts(11, model.shard_ids[0]), 0);
+    }
+
+    #[test]
+    fn forwarding_cost_saturates_for_huge_receipts() {
+        let config = ModelConfig { forward_gas_per_byte: GGAS, ..ModelConfig::default() };
+        assert_eq!(config.forwarding_cost(u64::MAX), GGas::MAX);
+
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::new(NoTransactions)).with_config(config);
+        let (a, b) = (model.shard_ids[0], model.shard_ids[1]);
+        let mut tx = model.new_transaction(a);
+        tx.add_first_receipt(
+            ReceiptDefinition {
+                receiver: b,
+                size: u64::MAX,
+                attached_gas: 10 * TGAS,
+                execution_gas: 5 * TGAS,
+            },
+            TGAS,
+        );
+        model.submit(tx, 1);
+        model.step();
+        let sender = &model.round_chunk_stats(1)[0];
+        assert_eq!(sender.forwarded_receipts[&b], 1);
+        assert_eq!(sender.forwarding_gas, GGas::MAX);
+        assert_eq!(sender.gas_burnt, GGas::MAX);
+        assert!(model.optimal_completion_bound() > 1);
+    }
+}
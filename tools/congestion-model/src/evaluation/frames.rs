@@ -0,0 +1,104 @@
+use crate::Model;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Writes one frame per round with the queue depth of each shard into a
+/// directory, to be stitched into an animation.
+///
+/// Each frame is a CSV file named after its round, e.g. `frame_000042.csv`,
+/// see [`Model::write_queue_frame`].
+pub struct FrameExporter {
+    dir: PathBuf,
+}
+
+impl FrameExporter {
+    /// Write frames into `dir`, creating it if necessary. Existing frames are
+    /// overwritten.
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Write the frame of the model's current round.
+    pub fn write_frame(&self, model: &Model) -> std::io::Result<()> {
+        let path = self.dir.join(format!("frame_{:06}.csv", model.round));
+        let mut file = std::fs::File::create(path)?;
+        model.write_queue_frame(&mut file)
+    }
+}
+
+impl Model {
+    /// Write the current queues of all shards as CSV, one line per shard.
+    ///
+    /// ```csv
+    /// shard,queued_receipts,queued_bytes,queued_gas,mempool_transactions
+    /// 0,12,12000,3600000,4
+    /// ```
+    pub fn write_queue_frame(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "shard,queued_receipts,queued_bytes,queued_gas,mempool_transactions")?;
+        let queue_lengths = self.queue_lengths();
+        for shard in &self.shard_ids {
+            let lengths = &queue_lengths[shard];
+            let queued = lengths.queued_receipts;
+            writeln!(
+                out,
+                "{shard},{},{},{},{}",
+                queued.num, queued.size, queued.gas, lengths.unprocessed_incoming_transactions
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::AllForOneProducer;
+
+    fn congested_model() -> Model {
+        let shards = (0..3).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<AllForOneProducer>::default());
+        for _ in 0..10 {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn frame_has_one_line_per_shard() {
+        let model = congested_model();
+        let mut frame = vec![];
+        model.write_queue_frame(&mut frame).unwrap();
+        let frame = String::from_utf8(frame).unwrap();
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines.len(), 4, "{frame}");
+        let lengths = model.queue_lengths();
+        for (shard, line) in model.shard_ids.iter().zip(&lines[1..]) {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields[0], shard.to_string());
+            assert_eq!(fields[1], lengths[shard].queued_receipts.num.to_string());
+        }
+        assert!(lengths.values().any(|lengths| lengths.queued_receipts.num > 0));
+    }
+
+    #[test]
+    fn exporter_writes_a_file_per_round() {
+        let dir =
+            std::env::temp_dir().join(format!("congestion-model-frames-{}", std::process::id()));
+        let exporter = FrameExporter::new(&dir).unwrap();
+        let mut model = congested_model();
+        for _ in 0..3 {
+            model.step();
+            exporter.write_frame(&model).unwrap();
+        }
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, ["frame_000011.csv", "frame_000012.csv", "frame_000013.csv"]);
+    }
+}
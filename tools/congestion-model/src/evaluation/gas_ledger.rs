@@ -0,0 +1,358 @@
+use crate::{GGas, Model, Receipt, Transaction};
+
+/// Accounts for all gas attached to the transactions of a model execution.
+///
+/// The model does not derive the gas of a child receipt from its parent, every
+/// receipt brings its own attached gas. The ledger therefore treats the
+/// conversion cost of a transaction and the attached gas of all its receipts
+/// as paid for when the transaction is submitted. From there, each unit of gas
+/// should end up in exactly one of the other fields. A receipt that a strategy
+/// lost track of, or forwarded twice, shows up as an imbalance.
+///
+/// Gas burnt for forwarding receipts (see [`crate::ModelConfig::forward_gas`])
+/// is charged to chunks rather than to transactions and is not part of the
+/// ledger. Send costs of receipts (see [`crate::Receipt::send_cost`]) are paid
+/// by the transaction, they are attached on top of the receipt's gas and stay
+/// with it until the sender burns them. Priority gas (see
+/// [`crate::Receipt::attached_priority_gas`]) is held the same way and burnt
+/// in full when the receipt executes. Neither is part of
+/// [`Model::gas_throughput`], which only counts the gas of conversions,
+/// executions and sends.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasLedger {
+    /// Gas paid for by all submitted transactions.
+    pub attached: GGas,
+    /// Gas burnt for converting transactions to receipts.
+    pub conversion_burnt: GGas,
+    /// Gas burnt for executing receipts.
+    pub execution_burnt: GGas,
+    /// Gas burnt by senders for forwarding receipts to other shards.
+    pub send_burnt: GGas,
+    /// Priority gas burnt by executed receipts.
+    pub priority_burnt: GGas,
+    /// Gas attached to executed receipts but not burnt by them.
+    pub refunded: GGas,
+    /// Gas attached to receipts that have been created but not executed.
+    pub queued: GGas,
+    /// Gas attached to receipts that were dropped by a shard.
+    pub dropped: GGas,
+    /// Gas attached to receipts lost between shards that wait for
+    /// retransmission, see [`crate::ModelConfig::acks`].
+    pub in_transit: GGas,
+    /// Gas of transactions evicted from a mempool, including all their
+    /// receipts.
+    pub evicted: GGas,
+    /// Gas of transactions rejected by their sender shard, including all
+    /// their receipts, except the share of the conversion cost burnt for the
+    /// rejection, which is part of `conversion_burnt`.
+    pub rejected: GGas,
+    /// Gas of transactions not converted, yet, and of receipts not created,
+    /// yet.
+    pub not_created: GGas,
+}
+
+impl GasLedger {
+    /// All fields by name, in declaration order.
+    pub(super) fn fields(&self) -> [(&'static str, GGas); 12] {
+        [
+            ("attached", self.attached),
+            ("conversion_burnt", self.conversion_burnt),
+            ("execution_burnt", self.execution_burnt),
+            ("send_burnt", self.send_burnt),
+            ("priority_burnt", self.priority_burnt),
+            ("refunded", self.refunded),
+            ("queued", self.queued),
+            ("dropped", self.dropped),
+            ("in_transit", self.in_transit),
+            ("evicted", self.evicted),
+            ("rejected", self.rejected),
+            ("not_created", self.not_created),
+        ]
+    }
+
+    /// Sum of all fields except `attached`.
+    ///
+    /// Panics on overflow.
+    pub fn accounted(&self) -> GGas {
+        [
+            self.conversion_burnt,
+            self.execution_burnt,
+            self.send_burnt,
+            self.priority_burnt,
+            self.refunded,
+            self.queued,
+            self.dropped,
+            self.in_transit,
+            self.evicted,
+            self.rejected,
+            self.not_created,
+        ]
+        .into_iter()
+        .fold(0, checked_add)
+    }
+
+    /// Whether every unit of attached gas is accounted for exactly once.
+    pub fn is_balanced(&self) -> bool {
+        self.attached == self.accounted()
+    }
+
+    pub(super) fn add_transaction(&mut self, tx: &Transaction) {
+        if tx.evicted_at.is_some() {
+            self.attached = checked_add(self.attached, tx.total_attached_gas);
+            self.evicted = checked_add(self.evicted, tx.total_attached_gas);
+            return;
+        }
+        if let Some(rejection) = &tx.rejection {
+            self.attached = checked_add(self.attached, tx.total_attached_gas);
+            self.conversion_burnt = checked_add(self.conversion_burnt, rejection.gas_burnt);
+            let unburnt =
+                tx.total_attached_gas.checked_sub(rejection.gas_burnt).unwrap_or_else(|| {
+                    panic!(
+                        "rejected transaction {:?} burnt {} GGas but only had {} GGas attached",
+                        tx.id, rejection.gas_burnt, tx.total_attached_gas
+                    )
+                });
+            self.rejected = checked_add(self.rejected, unburnt);
+            return;
+        }
+        let converted = !tx.future_receipts.contains_key(&tx.initial_receipt);
+        if converted {
+            self.conversion_burnt = checked_add(self.conversion_burnt, tx.tx_conversion_cost);
+        } else {
+            self.not_created = checked_add(self.not_created, tx.tx_conversion_cost);
+        }
+        self.attached = checked_add(self.attached, tx.total_attached_gas);
+        self.send_burnt = checked_add(self.send_burnt, tx.send_gas_burnt);
+
+        for receipt in tx.future_receipts.values() {
+            self.not_created = checked_add(self.not_created, held(receipt));
+        }
+        for receipt in tx.dropped_receipts.values() {
+            self.dropped = checked_add(self.dropped, held(receipt));
+        }
+        for receipt in tx.executed_receipts.values() {
+            self.execution_burnt = checked_add(self.execution_burnt, receipt.gas_burnt());
+            self.priority_burnt = checked_add(self.priority_burnt, receipt.attached_priority_gas);
+            // a receipt that never left its shard gets its send cost back
+            let refunded = checked_add(refund(receipt), receipt.unpaid_send_cost());
+            self.refunded = checked_add(self.refunded, refunded);
+        }
+    }
+}
+
+impl Model {
+    /// Break down where the gas attached to all transactions so far went.
+    ///
+    /// Panics if a receipt burnt more gas than was attached to it or if a sum
+    /// overflows.
+    pub fn gas_ledger(&self) -> GasLedger {
+        let mut ledger = self.retired.ledger.clone();
+        for tx in self.transactions.all_transactions() {
+            ledger.add_transaction(tx);
+        }
+        // Pending receipts live in queues, not in the transaction registry.
+        for &shard in &self.shard_ids {
+            for receipt in self.queues.shard_queues(shard).flat_map(|queue| queue.iter()) {
+                ledger.queued = checked_add(ledger.queued, held(receipt));
+            }
+        }
+        for (_, receipt) in &self.unapplied {
+            ledger.queued = checked_add(ledger.queued, held(receipt));
+        }
+        for receipt in self.acks.in_transit() {
+            ledger.in_transit = checked_add(ledger.in_transit, held(receipt));
+        }
+        ledger
+    }
+}
+
+/// Gas attached to a receipt that has not been burnt, yet.
+fn held(receipt: &Receipt) -> GGas {
+    [receipt.attached_gas, receipt.unpaid_send_cost(), receipt.attached_priority_gas]
+        .into_iter()
+        .fold(0, checked_add)
+}
+
+fn refund(receipt: &Receipt) -> GGas {
+    receipt.attached_gas.checked_sub(receipt.gas_burnt()).unwrap_or_else(|| {
+        panic!(
+            "receipt {:?} burnt {} GGas but only had {} GGas attached",
+            receipt.id,
+            receipt.gas_burnt(),
+            receipt.attached_gas
+        )
+    })
+}
+
+fn checked_add(a: GGas, b: GGas) -> GGas {
+    a.checked_add(b).expect("gas ledger overflowed u64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ChunkExecutionContext;
+    use crate::strategy::{NepStrategy, QueueFactory, TrafficLight};
+    use crate::workload::{
+        AllForOneProducer, BalancedProducer, LinearImbalanceProducer, Producer, WhaleProducer,
+    };
+    use crate::{CongestionStrategy, ModelConfig, ReceiptDefinition, ShardId, TGAS};
+
+    /// Executes transactions but loses all receipts of other shards.
+    struct LeakyShard;
+
+    impl CongestionStrategy for LeakyShard {
+        fn init(&mut self, _id: ShardId, _other: &[ShardId], _queues: &mut dyn QueueFactory) {}
+
+        fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+            while let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let receipt = ctx.accept_transaction(tx);
+                ctx.forward_receipt(receipt);
+            }
+            while let Some(receipt) = ctx.incoming_receipts().pop_front() {
+                drop(receipt);
+            }
+        }
+    }
+
+    fn producers() -> Vec<Box<dyn Producer>> {
+        vec![
+            Box::<BalancedProducer>::default(),
+            Box::new(WhaleProducer::new(Box::<BalancedProducer>::default(), 0.1, 20)),
+            Box::<AllForOneProducer>::default(),
+            Box::new(LinearImbalanceProducer::big_receipts()),
+        ]
+    }
+
+    #[test]
+    fn ledger_balances_every_round() {
+        for producer in producers() {
+            let shards = (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+            let mut model = Model::new(shards, producer);
+            for _ in 0..50 {
+                model.step();
+                let ledger = model.gas_ledger();
+                assert!(ledger.is_balanced(), "{ledger:?}");
+            }
+            let ledger = model.gas_ledger();
+            assert!(ledger.execution_burnt > 0 && ledger.queued > 0, "{ledger:?}");
+        }
+
+        let shards = (0..4).map(|_| Box::<TrafficLight>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        for _ in 0..50 {
+            model.step();
+            assert!(model.gas_ledger().is_balanced());
+        }
+    }
+
+    #[test]
+    fn send_costs_are_a_separate_category() {
+        let mut model = Model::new(
+            (0..2).map(|_| Box::<NepStrategy>::default() as _).collect(),
+            Box::new(BalancedProducer::with_sizes_and_fan_out(vec![100], 1)),
+        );
+        let (a, b) = (model.shard_ids()[0], model.shard_ids()[1]);
+        let def = |receiver| ReceiptDefinition {
+            receiver,
+            size: 1000,
+            attached_gas: 10 * TGAS,
+            execution_gas: 5 * TGAS,
+        };
+        // a call to the other shard and a local receipt, both with a send cost
+        let mut tx = model.new_transaction(a);
+        let call = tx.add_first_receipt(def(b), TGAS);
+        let local = tx.new_outgoing_receipt(call, def(b));
+        tx.set_send_cost(call, 3 * TGAS);
+        tx.set_send_cost(local, 7 * TGAS);
+        let id = model.submit(tx, 1);
+
+        let mut send_gas = 0;
+        for _ in 0..10 {
+            model.step();
+            let ledger = model.gas_ledger();
+            assert!(ledger.is_balanced(), "{ledger:?}");
+            send_gas += model.chunk_stats.iter().rev().take(2).map(|s| s.send_gas).sum::<GGas>();
+        }
+        assert_eq!(model.transactions[id].executed_receipts.len(), 2);
+        // only the call left its shard, the local send cost is refunded
+        assert_eq!(model.transactions[id].send_gas_burnt, 3 * TGAS);
+        assert_eq!(model.gas_ledger().send_burnt, 3 * TGAS);
+        assert_eq!(send_gas, 3 * TGAS);
+    }
+
+    #[test]
+    fn priority_gas_is_burnt_not_refunded() {
+        let mut model = Model::new(
+            (0..2).map(|_| Box::<NepStrategy>::default() as _).collect(),
+            Box::new(BalancedProducer::with_sizes_and_fan_out(vec![100], 1)),
+        );
+        let (a, b) = (model.shard_ids()[0], model.shard_ids()[1]);
+        let def = ReceiptDefinition {
+            receiver: b,
+            size: 100,
+            attached_gas: 10 * TGAS,
+            execution_gas: 5 * TGAS,
+        };
+        let mut tx = model.new_transaction(a).with_priority_gas(2 * TGAS);
+        let call = tx.add_first_receipt(def.clone(), TGAS);
+        tx.new_outgoing_receipt(call, def);
+        let id = model.submit(tx, 1);
+
+        for _ in 0..10 {
+            model.step();
+            let ledger = model.gas_ledger();
+            assert!(ledger.is_balanced(), "{ledger:?}");
+        }
+        assert_eq!(model.transactions[id].executed_receipts.len(), 2);
+        let ledger = model.gas_ledger();
+        assert_eq!(ledger.priority_burnt, 4 * TGAS);
+        assert_eq!(model.transactions[id].total_attached_gas, 25 * TGAS);
+        assert_eq!(model.transactions[id].gas_burnt(), 11 * TGAS);
+    }
+
+    #[test]
+    fn rejected_transactions_burn_part_of_the_conversion() {
+        let config = ModelConfig {
+            max_tx_size: Some(1000),
+            rejected_tx_cost_fraction: 0.5,
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(
+            (0..2).map(|_| Box::<NepStrategy>::default() as _).collect(),
+            Box::new(BalancedProducer::with_sizes_and_fan_out(vec![100], 1)),
+        )
+        .with_config(config);
+        let (a, b) = (model.shard_ids()[0], model.shard_ids()[1]);
+        let def = ReceiptDefinition {
+            receiver: b,
+            size: 100,
+            attached_gas: 10 * TGAS,
+            execution_gas: 5 * TGAS,
+        };
+        let mut tx = model.new_transaction(a).with_size(2000);
+        tx.add_first_receipt(def, 4 * TGAS);
+        let id = model.submit(tx, 1);
+
+        model.step();
+        let tx = &model.transactions[id];
+        let rejection = tx.rejection.as_ref().expect("oversized transaction was not rejected");
+        assert_eq!(rejection.gas_burnt, 2 * TGAS);
+        let ledger = model.gas_ledger();
+        assert!(ledger.is_balanced(), "{ledger:?}");
+        // the small transactions of the workload are not rejected
+        assert_eq!(ledger.rejected, tx.total_attached_gas - 2 * TGAS);
+        assert!(ledger.conversion_burnt >= 2 * TGAS, "{ledger:?}");
+    }
+
+    #[test]
+    fn lost_receipts_unbalance_the_ledger() {
+        let shards = (0..2).map(|_| Box::new(LeakyShard) as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        model.step();
+        assert!(model.gas_ledger().is_balanced());
+        model.step();
+        let ledger = model.gas_ledger();
+        assert!(ledger.accounted() < ledger.attached);
+    }
+}
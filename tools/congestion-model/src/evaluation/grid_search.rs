@@ -0,0 +1,220 @@
+use crate::workload::Producer;
+use crate::{CongestionStrategy, Model, ModelConfig};
+
+/// A strategy parameter and the values [`grid_search`] tries for it.
+#[derive(Clone, Debug)]
+pub struct GridParameter {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// One combination of parameter values, in the order of the grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridPoint {
+    pub values: Vec<(String, f64)>,
+}
+
+/// Result of [`grid_search`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestConfig {
+    /// The configuration with the lowest objective. The first one in grid
+    /// order among equally good ones.
+    pub point: GridPoint,
+    pub metric: f64,
+    /// Every configuration with its objective, in grid order.
+    pub grid: Vec<(GridPoint, f64)>,
+}
+
+/// Inputs for [`grid_search`] besides the strategy, like [`super::LoadCurveConfig`].
+///
+/// The workload is given as a factory because every grid point runs on a
+/// fresh model, possibly on another thread.
+pub struct GridSearchConfig {
+    workload: Box<dyn Fn() -> Box<dyn Producer> + Sync>,
+    model_config: ModelConfig,
+    seed: u64,
+    rounds: usize,
+    threads: usize,
+}
+
+impl GridPoint {
+    /// Value of the named parameter.
+    pub fn get(&self, name: &str) -> f64 {
+        self.values
+            .iter()
+            .find(|(param, _)| param == name)
+            .unwrap_or_else(|| panic!("no grid parameter {name}"))
+            .1
+    }
+}
+
+impl GridSearchConfig {
+    /// Run the given workload for 500 rounds per grid point, with seed 0, the
+    /// default model config and a single thread.
+    pub fn new(workload: impl Fn() -> Box<dyn Producer> + Sync + 'static) -> Self {
+        Self {
+            workload: Box::new(workload),
+            model_config: ModelConfig::default(),
+            seed: 0,
+            rounds: 500,
+            threads: 1,
+        }
+    }
+
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Run grid points on this many threads. The results do not depend on it.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "grid search needs at least one thread");
+        self.threads = threads;
+        self
+    }
+}
+
+/// Find the strategy parameters that minimize an objective.
+///
+/// Runs a fresh model for every combination of the parameter values, the
+/// full cross product with the last parameter changing fastest. The strategy
+/// factory builds the shards for a grid point and the objective measures the
+/// model after the run, lower is better. Every run uses the same workload and
+/// seed, so only the parameters differ. For example, to minimize p99 latency:
+///
+/// ```rust,ignore
+/// let grid = [GridParameter { name: "max_tx_gas".into(), values: vec![50.0, 500.0] }];
+/// let best = grid_search(
+///     &GridSearchConfig::new(|| Box::<BalancedProducer>::default()).with_threads(4),
+///     |point| nep_shards(point.get("max_tx_gas") as GGas * TGAS),
+///     &grid,
+///     |model| model.latency_stats().p99 as f64,
+/// );
+/// ```
+pub fn grid_search(
+    config: &GridSearchConfig,
+    strategy: impl Fn(&GridPoint) -> Vec<Box<dyn CongestionStrategy>> + Sync,
+    param_grid: &[GridParameter],
+    objective: impl Fn(&Model) -> f64 + Sync,
+) -> BestConfig {
+    let points = cross_product(param_grid);
+    let run = |point: &GridPoint| {
+        let mut model = Model::new(strategy(point), (config.workload)())
+            .with_seed(config.seed)
+            .with_config(config.model_config.clone());
+        for _ in 0..config.rounds {
+            model.step();
+        }
+        objective(&model)
+    };
+
+    // Models are not `Send`, each thread builds and runs its own.
+    let threads = config.threads.min(points.len());
+    let mut metrics = vec![0.0; points.len()];
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                let (points, run) = (&points, &run);
+                scope.spawn(move || {
+                    let mine = (worker..points.len()).step_by(threads);
+                    mine.map(|index| (index, run(&points[index]))).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (index, metric) in worker.join().expect("grid search worker panicked") {
+                metrics[index] = metric;
+            }
+        }
+    });
+
+    let grid: Vec<(GridPoint, f64)> = points.into_iter().zip(metrics).collect();
+    let (point, metric) = grid
+        .iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .cloned()
+        .expect("grid has at least one point");
+    BestConfig { point, metric, grid }
+}
+
+fn cross_product(param_grid: &[GridParameter]) -> Vec<GridPoint> {
+    let mut points = vec![GridPoint { values: vec![] }];
+    for param in param_grid {
+        assert!(!param.values.is_empty(), "grid parameter {} has no values", param.name);
+        points = points
+            .into_iter()
+            .flat_map(|point| {
+                param.values.iter().map(move |&value| {
+                    let mut values = point.values.clone();
+                    values.push((param.name.clone(), value));
+                    GridPoint { values }
+                })
+            })
+            .collect();
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::BalancedProducer;
+    use crate::{GGas, TGAS};
+
+    fn search(threads: usize) -> BestConfig {
+        let config = GridSearchConfig::new(|| Box::<BalancedProducer>::default())
+            .with_rounds(50)
+            .with_threads(threads);
+        let grid = [
+            GridParameter { name: "max_tx_gas".into(), values: vec![5.0, 50.0, 500.0] },
+            GridParameter { name: "min_tx_gas".into(), values: vec![0.0, 5.0] },
+        ];
+        let strategy = |point: &GridPoint| -> Vec<Box<dyn CongestionStrategy>> {
+            (0..4)
+                .map(|_| {
+                    Box::new(NepStrategy {
+                        max_tx_gas: point.get("max_tx_gas") as GGas * TGAS,
+                        min_tx_gas: point.get("min_tx_gas") as GGas * TGAS,
+                        ..NepStrategy::default()
+                    }) as _
+                })
+                .collect()
+        };
+        grid_search(&config, strategy, &grid, |model| model.latency_stats().p99 as f64)
+    }
+
+    #[test]
+    fn grid_search_reports_every_point_and_the_best() {
+        let best = search(1);
+        assert_eq!(best.grid.len(), 6);
+        let order: Vec<(f64, f64)> = best
+            .grid
+            .iter()
+            .map(|(point, _)| (point.get("max_tx_gas"), point.get("min_tx_gas")))
+            .collect();
+        assert_eq!(order[..3], [(5.0, 0.0), (5.0, 5.0), (50.0, 0.0)]);
+
+        let lowest = best.grid.iter().map(|(_, metric)| *metric).fold(f64::INFINITY, f64::min);
+        assert_eq!(best.metric, lowest);
+        let first_lowest = best.grid.iter().find(|(_, metric)| *metric == lowest).unwrap();
+        assert_eq!(best.point, first_lowest.0);
+        // the parameters make a difference
+        assert!(best.grid.iter().any(|(_, metric)| *metric != lowest), "{best:?}");
+    }
+
+    #[test]
+    fn grid_search_does_not_depend_on_threads() {
+        assert_eq!(search(1), search(4));
+    }
+}
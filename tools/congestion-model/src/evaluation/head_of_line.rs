@@ -0,0 +1,74 @@
+use crate::{ChunkStats, Model, Round, ShardId};
+
+impl ChunkStats {
+    /// Whether a single receipt consumed the majority of the chunk gas limit.
+    pub fn dominated_by_single_receipt(&self) -> bool {
+        self.max_receipt_gas > self.gas_limit / 2
+    }
+}
+
+impl Model {
+    /// All chunks in which a single receipt consumed the majority of the
+    /// shard's gas budget.
+    ///
+    /// Everything queued behind such a receipt has to wait, which makes these
+    /// chunks a direct indicator of head-of-line blocking.
+    pub fn dominated_chunks(&self) -> impl Iterator<Item = &ChunkStats> {
+        self.chunk_stats.iter().filter(|stats| stats.dominated_by_single_receipt())
+    }
+
+    /// Statistics of the chunks of a round, one per shard. Empty for rounds
+    /// not executed, yet, or no longer kept, see
+    /// [`crate::CHUNK_STATS_RETENTION`].
+    pub(super) fn round_chunk_stats(&self, round: Round) -> &[ChunkStats] {
+        // every round records one chunk per shard
+        let num_shards = self.shard_ids.len();
+        let Some(first) = self.chunk_stats.first() else { return &[] };
+        let Some(offset) = round.checked_sub(first.round) else { return &[] };
+        let start = offset as usize * num_shards;
+        self.chunk_stats.get(start..start + num_shards).unwrap_or(&[])
+    }
+
+    /// Statistics of the most recent chunk of a shard, if any was produced.
+    pub fn last_chunk_stats(&self, shard_id: ShardId) -> Option<&ChunkStats> {
+        self.chunk_stats
+            .iter()
+            .rev()
+            .take(self.shard_ids.len())
+            .find(|stats| stats.shard == shard_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::BalancedProducer;
+    use crate::{GAS_LIMIT, TGAS};
+
+    #[test]
+    fn only_chunks_with_a_majority_receipt_are_dominated() {
+        let shards = (0..2).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        let (a, b) = (model.shard_ids[0], model.shard_ids[1]);
+        let chunk = |shard, gas_burnt, max_receipt_gas| ChunkStats {
+            gas_burnt,
+            max_receipt_gas,
+            ..ChunkStats::idle(1, shard, GAS_LIMIT)
+        };
+        // one 600 TGas receipt blocks the chunk, the other has many small ones
+        let whale = chunk(a, 700 * TGAS, 600 * TGAS);
+        let small = chunk(b, 900 * TGAS, 100 * TGAS);
+        assert!(whale.dominated_by_single_receipt());
+        assert!(!small.dominated_by_single_receipt());
+        // exactly half is no majority, no matter what else burnt gas
+        let half = chunk(a, 500 * TGAS, 500 * TGAS);
+        let idle = chunk(b, 0, 0);
+        assert!(!half.dominated_by_single_receipt());
+        assert!(!idle.dominated_by_single_receipt());
+
+        model.chunk_stats = vec![whale, small, half, idle];
+        let dominated: Vec<_> = model.dominated_chunks().map(|stats| stats.shard).collect();
+        assert_eq!(dominated, [a]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+/// Distribution of integer samples, such as receipt ages in rounds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    /// Number of samples for each observed value.
+    counts: BTreeMap<u64, u64>,
+}
+
+impl Histogram {
+    pub fn add(&mut self, value: u64) {
+        *self.counts.entry(value).or_default() += 1;
+    }
+
+    /// Total number of samples.
+    pub fn count(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.counts.keys().next().copied()
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.counts.keys().next_back().copied()
+    }
+
+    /// Value at the given percentile, using the same nearest-rank definition
+    /// as the transaction delay statistics.
+    pub fn percentile(&self, p: usize) -> Option<u64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (count - 1) * p as u64 / 100;
+        let mut seen = 0;
+        for (&value, &n) in &self.counts {
+            seen += n;
+            if seen > rank {
+                return Some(value);
+            }
+        }
+        unreachable!("rank is always smaller than the number of samples")
+    }
+
+    /// Observed values and how often they occurred, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.counts.iter().map(|(&value, &n)| (value, n))
+    }
+}
+
+impl FromIterator<u64> for Histogram {
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        let mut histogram = Self::default();
+        for value in iter {
+            histogram.add(value);
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_matches_sorted_samples() {
+        let samples = [7, 1, 3, 3, 9, 0, 3, 12, 5, 1];
+        let histogram: Histogram = samples.iter().copied().collect();
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        for p in [0, 10, 50, 90, 99, 100] {
+            assert_eq!(histogram.percentile(p), Some(sorted[(sorted.len() - 1) * p / 100]));
+        }
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.min(), Some(0));
+        assert_eq!(histogram.max(), Some(12));
+        assert_eq!(Histogram::default().percentile(50), None);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{ChunkStats, GGas, Model, ModelConfig, ShardId};
+
+/// Default of [`crate::ModelConfig::congestion_threshold`].
+const DEFAULT_CONGESTION_THRESHOLD: f64 = 0.9;
+
+impl Model {
+    /// Number of rounds in which the shard burnt no gas at all while another
+    /// shard was congested.
+    ///
+    /// A shard counts as congested in a round if it burnt at least
+    /// [`crate::ModelConfig::congestion_threshold`] of its gas limit. An idle
+    /// shard next to a congested one means the load is badly distributed.
+    /// Rounds in which all shards are idle do not count.
+    pub fn idle_rounds(&self, shard: ShardId) -> usize {
+        let retired = self.retired_chunks.shards.get(&shard).map_or(0, |s| s.idle_rounds);
+        let idle = idle_while_congested(&self.chunk_stats, self.shard_ids.len(), &self.config);
+        retired + idle.filter(|stats| stats.shard == shard).count()
+    }
+
+    /// Sum of the gas limits of all chunks counted by [`Model::idle_rounds`],
+    /// over all shards.
+    ///
+    /// This is the capacity the network had but could not use because of
+    /// imbalance.
+    pub fn wasted_capacity(&self) -> GGas {
+        let idle = idle_while_congested(&self.chunk_stats, self.shard_ids.len(), &self.config);
+        self.retired_chunks.wasted_capacity + idle.map(|stats| stats.gas_limit).sum::<GGas>()
+    }
+}
+
+/// The chunks counted by [`Model::idle_rounds`], in statistics of whole
+/// rounds.
+pub(super) fn idle_while_congested<'a>(
+    chunk_stats: &'a [ChunkStats],
+    num_shards: usize,
+    config: &ModelConfig,
+) -> impl Iterator<Item = &'a ChunkStats> {
+    let threshold = config.congestion_threshold.unwrap_or(DEFAULT_CONGESTION_THRESHOLD);
+    let congested = move |stats: &ChunkStats| {
+        stats.gas_burnt as f64 >= threshold * stats.gas_limit as f64 && stats.gas_burnt > 0
+    };
+    // every round records one chunk per shard
+    chunk_stats.chunks(num_shards).flat_map(move |round| {
+        let idle = round.iter().filter(|stats| stats.gas_burnt == 0);
+        idle.filter(move |stats| {
+            round.iter().any(|other| other.shard != stats.shard && congested(other))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NepStrategy;
+    use crate::workload::Producer;
+    use crate::{
+        CongestionStrategy, Model, ModelConfig, ReceiptDefinition, Round, ShardId,
+        TransactionBuilder, GAS_LIMIT, TGAS,
+    };
+    use rand::RngCore;
+
+    /// Local transactions, only on shard 0, and more than it can execute.
+    struct Hotspot;
+
+    impl Producer for Hotspot {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            (0..20)
+                .map(|_| {
+                    let mut tx = tx_factory(shards[0]);
+                    let receipt = ReceiptDefinition {
+                        receiver: shards[0],
+                        size: 100,
+                        attached_gas: 100 * TGAS,
+                        execution_gas: 100 * TGAS,
+                    };
+                    tx.add_first_receipt(receipt, 5 * TGAS);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    fn run(congestion_threshold: Option<f64>) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..3).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let config = ModelConfig { congestion_threshold, ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::new(Hotspot)).with_config(config);
+        for _ in 0..20 {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn idle_shards_next_to_a_hotspot() {
+        let model = run(None);
+        let ids = model.shard_ids();
+        assert_eq!(model.idle_rounds(ids[0]), 0);
+        let idle = model.idle_rounds(ids[1]);
+        // the hotspot needs a few rounds to fill up
+        assert!(idle > 15, "idle rounds: {idle}");
+        assert_eq!(model.idle_rounds(ids[2]), idle);
+        assert_eq!(model.wasted_capacity(), 2 * idle as u64 * GAS_LIMIT);
+    }
+
+    #[test]
+    fn threshold_decides_what_is_congested() {
+        assert!(run(Some(0.5)).wasted_capacity() > 0);
+        assert_eq!(run(Some(1.5)).wasted_capacity(), 0);
+    }
+}
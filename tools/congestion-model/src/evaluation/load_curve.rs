@@ -0,0 +1,135 @@
+use crate::workload::{Producer, ScaledProducer};
+use crate::{CongestionStrategy, Model, ModelConfig, Round, TransactionStatus};
+
+/// Transaction latency in rounds, from submission to the last change.
+///
+/// Transactions that are still waiting or pending are included with their
+/// current age, which is a lower bound of their final latency. Without them,
+/// a saturated system would look fast because only the lucky transactions
+/// finish. Failed transactions are not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of successfully finished transactions.
+    pub finished: u64,
+    /// Number of transactions that are still waiting or pending.
+    pub unresolved: u64,
+    pub p50: Round,
+    pub p90: Round,
+    pub p99: Round,
+    pub max: Round,
+}
+
+/// Inputs for [`load_curve`].
+///
+/// Strategy and workload are given as factories because each load level runs
+/// on a fresh model.
+pub struct LoadCurveConfig {
+    strategy: Box<dyn Fn() -> Vec<Box<dyn CongestionStrategy>>>,
+    workload: Box<dyn Fn() -> Box<dyn Producer>>,
+    model_config: ModelConfig,
+    seed: u64,
+    rounds: usize,
+}
+
+impl Model {
+    /// Latency statistics of all transactions submitted so far, measured at
+    /// the current round.
+    pub fn latency_stats(&self) -> LatencyStats {
+        let mut latencies = self.retired.successful_delays.clone();
+        let mut stats =
+            LatencyStats { finished: self.retired.finished as u64, ..LatencyStats::default() };
+        for tx in self.transactions.all_transactions() {
+            match tx.status() {
+                TransactionStatus::FinishedSuccess => {
+                    stats.finished += 1;
+                    latencies.add(tx.delay());
+                }
+                TransactionStatus::Init | TransactionStatus::Pending => {
+                    stats.unresolved += 1;
+                    latencies.add(self.round - tx.submitted_at);
+                }
+                TransactionStatus::Failed => {}
+            }
+        }
+        stats.p50 = latencies.percentile(50).unwrap_or(0);
+        stats.p90 = latencies.percentile(90).unwrap_or(0);
+        stats.p99 = latencies.percentile(99).unwrap_or(0);
+        stats.max = latencies.max().unwrap_or(0);
+        stats
+    }
+}
+
+impl LoadCurveConfig {
+    /// Run the given strategy and workload for 500 rounds per load level,
+    /// with seed 0 and the default model config.
+    pub fn new(
+        strategy: impl Fn() -> Vec<Box<dyn CongestionStrategy>> + 'static,
+        workload: impl Fn() -> Box<dyn Producer> + 'static,
+    ) -> Self {
+        Self {
+            strategy: Box::new(strategy),
+            workload: Box::new(workload),
+            model_config: ModelConfig::default(),
+            seed: 0,
+            rounds: 500,
+        }
+    }
+
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+}
+
+/// Measure transaction latency at increasing levels of offered load.
+///
+/// For each multiplier, a fresh model runs the configured workload with its
+/// submission rate scaled by the multiplier (see [`ScaledProducer`]). All runs
+/// use the same seed. Plotting p99 over the multiplier shows the typical
+/// hockey stick, with the knee where the strategy reaches its capacity.
+pub fn load_curve(config: &LoadCurveConfig, multipliers: Vec<f64>) -> Vec<(f64, LatencyStats)> {
+    multipliers
+        .into_iter()
+        .map(|multiplier| {
+            let workload = ScaledProducer::new((config.workload)(), multiplier);
+            let mut model = Model::new((config.strategy)(), Box::new(workload))
+                .with_seed(config.seed)
+                .with_config(config.model_config.clone());
+            for _ in 0..config.rounds {
+                model.step();
+            }
+            (multiplier, model.latency_stats())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::BalancedProducer;
+
+    #[test]
+    fn latency_grows_with_load() {
+        let config = LoadCurveConfig::new(
+            || (0..4).map(|_| Box::<NepStrategy>::default() as _).collect(),
+            || Box::<BalancedProducer>::default(),
+        )
+        .with_rounds(100);
+        let curve = load_curve(&config, vec![0.1, 0.5, 1.0, 2.0]);
+
+        let p99: Vec<Round> = curve.iter().map(|(_, stats)| stats.p99).collect();
+        assert!(p99.windows(2).all(|w| w[0] <= w[1]), "{curve:?}");
+        assert!(p99[3] > 2 * p99[0], "{curve:?}");
+    }
+}
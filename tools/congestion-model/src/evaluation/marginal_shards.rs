@@ -0,0 +1,146 @@
+use super::LatencyStats;
+use crate::workload::Producer;
+use crate::{CongestionStrategy, GGas, Model, ModelConfig, PGAS};
+use std::ops::RangeInclusive;
+
+/// Inputs for [`marginal_shards`].
+///
+/// The workload must keep its total load independent of the number of
+/// shards and spread it over however many shards the model has, like
+/// [`crate::workload::AccountTrafficProducer`] without a fixed map, whose
+/// accounts are split evenly over the shards on init.
+pub struct MarginalShardsConfig {
+    strategy: Box<dyn Fn(usize) -> Vec<Box<dyn CongestionStrategy>>>,
+    workload: Box<dyn Fn() -> Box<dyn Producer>>,
+    model_config: ModelConfig,
+    seed: u64,
+    rounds: usize,
+}
+
+/// Outcome of a run with one number of shards, see [`marginal_shards`].
+#[derive(Clone, Debug)]
+pub struct ShardCountResult {
+    pub shards: usize,
+    /// Gas attached to all submitted transactions, the same for every number
+    /// of shards if the workload keeps its load constant.
+    pub offered_gas: GGas,
+    /// See [`Model::congestion_index`].
+    pub congestion_index: f64,
+    pub latency: LatencyStats,
+}
+
+impl MarginalShardsConfig {
+    /// Run the strategy, built for the given number of shards, and the
+    /// workload for 500 rounds per shard count, with seed 0 and the default
+    /// model config.
+    pub fn new(
+        strategy: impl Fn(usize) -> Vec<Box<dyn CongestionStrategy>> + 'static,
+        workload: impl Fn() -> Box<dyn Producer> + 'static,
+    ) -> Self {
+        Self {
+            strategy: Box::new(strategy),
+            workload: Box::new(workload),
+            model_config: ModelConfig::default(),
+            seed: 0,
+            rounds: 500,
+        }
+    }
+
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+}
+
+/// Measure congestion with each of the given numbers of shards under the same
+/// total load.
+///
+/// Each shard count runs on a fresh model with the same seed. The difference
+/// between consecutive results is the marginal benefit of one more shard,
+/// see [`print_marginal_table`]. Once it approaches zero, more shards no
+/// longer help.
+pub fn marginal_shards(
+    config: &MarginalShardsConfig,
+    shard_counts: RangeInclusive<usize>,
+) -> Vec<ShardCountResult> {
+    assert!(*shard_counts.start() >= 1, "models need at least one shard");
+    shard_counts
+        .map(|shards| {
+            let mut model = Model::new((config.strategy)(shards), (config.workload)())
+                .with_seed(config.seed)
+                .with_config(config.model_config.clone());
+            for _ in 0..config.rounds {
+                model.step();
+            }
+            ShardCountResult {
+                shards,
+                offered_gas: model.gas_ledger().attached,
+                congestion_index: model.congestion_index(),
+                latency: model.latency_stats(),
+            }
+        })
+        .collect()
+}
+
+/// Print one row per shard count, with the change of congestion index and
+/// p99 latency from the row above, the marginal effect of its last shard.
+pub fn print_marginal_table(results: &[ShardCountResult]) {
+    println!(
+        "{:>8}{:>16}{:>18}{:>16}{:>16}{:>16}",
+        "SHARDS", "OFFERED PGAS", "CONGESTION INDEX", "MARGINAL", "p99 TX DELAY", "MARGINAL"
+    );
+    let mut prev: Option<&ShardCountResult> = None;
+    for result in results {
+        let (index_change, p99_change) = match prev {
+            Some(prev) => (
+                format!("{:+.4}", result.congestion_index - prev.congestion_index),
+                format!("{:+}", result.latency.p99 as i64 - prev.latency.p99 as i64),
+            ),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        println!(
+            "{:>8}{:>16}{:>18.4}{:>16}{:>16}{:>16}",
+            result.shards,
+            result.offered_gas / PGAS,
+            result.congestion_index,
+            index_change,
+            result.latency.p99,
+            p99_change,
+        );
+        prev = Some(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::AccountTrafficProducer;
+
+    #[test]
+    fn more_shards_reduce_congestion_with_diminishing_returns() {
+        let config = MarginalShardsConfig::new(
+            |shards| (0..shards).map(|_| Box::<NepStrategy>::default() as _).collect(),
+            || Box::new(AccountTrafficProducer::new(1000, 0, 20)),
+        )
+        .with_rounds(100);
+        let results = marginal_shards(&config, 1..=6);
+
+        assert_eq!(results.iter().map(|r| r.shards).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+        assert!(results.iter().all(|r| r.offered_gas == results[0].offered_gas), "{results:?}");
+        let index: Vec<f64> = results.iter().map(|r| r.congestion_index).collect();
+        assert!(index.windows(2).all(|w| w[1] <= w[0]), "{index:?}");
+        // the first added shard helps more than the last one
+        assert!(index[0] - index[1] > index[4] - index[5], "{index:?}");
+    }
+}
@@ -0,0 +1,233 @@
+use super::retired::RetiredShard;
+use crate::{ChunkStats, Model, ShardId};
+use std::collections::HashMap;
+
+impl Model {
+    /// Total number of transactions each shard evicted from its mempool so
+    /// far, see [`crate::ModelConfig::mempool_capacity`].
+    pub fn evicted_transactions(&self) -> HashMap<ShardId, usize> {
+        self.sum_per_shard(|r| r.evicted_transactions, |stats| stats.evicted_transactions)
+    }
+
+    /// Total number of transactions each shard rejected before conversion so
+    /// far, see [`crate::ModelConfig::max_tx_size`].
+    pub fn rejected_transactions(&self) -> HashMap<ShardId, usize> {
+        self.sum_per_shard(|r| r.rejected_transactions, |stats| stats.rejected_transactions)
+    }
+
+    /// Share of all transactions submitted so far that their sender shard
+    /// rejected, 0 without transactions.
+    pub fn tx_rejection_rate(&self) -> f64 {
+        let rejected: usize = self.rejected_transactions().values().sum();
+        let progress = self.progress();
+        let submitted = progress.finished_transactions
+            + progress.pending_transactions
+            + progress.waiting_transactions
+            + progress.failed_transactions;
+        if submitted == 0 {
+            return 0.0;
+        }
+        rejected as f64 / submitted as f64
+    }
+
+    fn sum_per_shard(
+        &self,
+        retired: impl Fn(&RetiredShard) -> usize,
+        count: impl Fn(&ChunkStats) -> usize,
+    ) -> HashMap<ShardId, usize> {
+        let mut out: HashMap<ShardId, usize> = self
+            .shard_ids
+            .iter()
+            .map(|shard| (*shard, self.retired_chunks.shards.get(shard).map_or(0, &retired)))
+            .collect();
+        for stats in &self.chunk_stats {
+            *out.get_mut(&stats.shard).unwrap() += count(stats);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ChunkExecutionContext;
+    use crate::strategy::NoQueueShard;
+    use crate::strategy::{NepStrategy, QueueFactory};
+    use crate::workload::{BalancedProducer, OversizedTxProducer, Producer};
+    use crate::{
+        CongestionStrategy, MempoolEviction, ModelConfig, ReceiptDefinition, RejectionReason,
+        Round, TransactionBuilder, TransactionStatus, TGAS,
+    };
+    use rand::RngCore;
+
+    /// Never converts any transactions.
+    struct Idle;
+
+    impl CongestionStrategy for Idle {
+        fn init(&mut self, _id: ShardId, _other: &[ShardId], _queues: &mut dyn QueueFactory) {}
+
+        fn compute_chunk(&mut self, _ctx: &mut ChunkExecutionContext) {}
+    }
+
+    /// Submits ten transactions with fees 0 to 9 in round 1, in random order.
+    struct TenFees;
+
+    impl Producer for TenFees {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            [3, 9, 0, 7, 7, 1, 8, 2, 5, 4]
+                .into_iter()
+                .map(|fee| {
+                    let mut tx = tx_factory(shards[0]).with_fee(fee);
+                    let receipt = ReceiptDefinition {
+                        receiver: shards[0],
+                        size: 100,
+                        attached_gas: TGAS,
+                        execution_gas: TGAS,
+                    };
+                    tx.add_first_receipt(receipt, TGAS);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    /// Submits transactions of 999, 1000 and 1001 bytes in round 1.
+    struct AroundThousandBytes;
+
+    impl Producer for AroundThousandBytes {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            [999, 1000, 1001]
+                .into_iter()
+                .map(|size| {
+                    let mut tx = tx_factory(shards[0]).with_size(size);
+                    let receipt = ReceiptDefinition {
+                        receiver: shards[0],
+                        size: 100,
+                        attached_gas: TGAS,
+                        execution_gas: TGAS,
+                    };
+                    tx.add_first_receipt(receipt, 10 * TGAS);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn transactions_above_max_size_are_rejected() {
+        let config = ModelConfig {
+            max_tx_size: Some(1000),
+            rejected_tx_cost_fraction: 0.5,
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(vec![Box::new(NoQueueShard {})], Box::new(AroundThousandBytes))
+            .with_config(config);
+        for _ in 0..3 {
+            model.step();
+        }
+
+        let views: Vec<_> = model.transaction_views().collect();
+        let rejections: Vec<_> = views.iter().map(|view| view.rejection()).collect();
+        assert_eq!(rejections[..2], [None, None]);
+        let rejection = rejections[2].unwrap();
+        assert_eq!(rejection.round, 1);
+        assert_eq!(rejection.reason, RejectionReason::TooLarge { size: 1001, max_tx_size: 1000 });
+        assert_eq!(rejection.gas_burnt, 5 * TGAS);
+        let statuses: Vec<_> = views.iter().map(|view| view.status()).collect();
+        assert_eq!(
+            statuses,
+            [
+                TransactionStatus::FinishedSuccess,
+                TransactionStatus::FinishedSuccess,
+                TransactionStatus::Failed
+            ]
+        );
+        assert!(views[2].receipts()[0].state() == crate::ReceiptState::Future);
+
+        // the rejection burns its share in the chunk, next to two conversions
+        let stats = &model.chunk_stats[0];
+        assert_eq!((stats.rejected_transactions, stats.rejection_gas), (1, 5 * TGAS));
+        assert_eq!(stats.gas_burnt, 25 * TGAS);
+        assert_eq!(model.tx_rejection_rate(), 1.0 / 3.0);
+        let ledger = model.gas_ledger();
+        assert!(ledger.is_balanced(), "{ledger:?}");
+        assert_eq!(ledger.rejected, 6 * TGAS);
+    }
+
+    #[test]
+    fn rejections_burn_nothing_by_default() {
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let producer = OversizedTxProducer::new(Box::<BalancedProducer>::default(), 0.1, 10_000);
+        let config = ModelConfig { max_tx_size: Some(5_000), ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::new(producer)).with_config(config);
+        for _ in 0..50 {
+            model.step();
+            let ledger = model.gas_ledger();
+            assert!(ledger.is_balanced(), "{ledger:?}");
+        }
+
+        let rate = model.tx_rejection_rate();
+        assert!((0.05..0.15).contains(&rate), "{rate}");
+        let rejected: usize = model.rejected_transactions().values().sum();
+        assert_eq!(rejected, model.transaction_views().filter(|v| v.rejection().is_some()).count());
+        assert!(model.chunk_stats.iter().all(|stats| stats.rejection_gas == 0));
+    }
+
+    #[test]
+    fn flood_evicts_oldest_transactions() {
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let config = ModelConfig { mempool_capacity: Some(5), ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..20 {
+            model.step();
+        }
+
+        assert!(model.chunk_stats.iter().all(|stats| stats.mempool_depth <= 5));
+        let evicted: usize = model.evicted_transactions().values().sum();
+        // both shards get 12 new transactions per round
+        assert_eq!(evicted, 2 * 20 * (12 - 5));
+        let progress = model.progress();
+        assert_eq!(progress.failed_transactions, evicted);
+        assert!(progress.finished_transactions > 0);
+        let ledger = model.gas_ledger();
+        assert!(ledger.is_balanced() && ledger.evicted > 0, "{ledger:?}");
+    }
+
+    #[test]
+    fn lowest_fees_are_evicted_first() {
+        let config = ModelConfig {
+            mempool_capacity: Some(4),
+            mempool_eviction: MempoolEviction::LowestFee,
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(vec![Box::new(Idle)], Box::new(TenFees)).with_config(config);
+        model.step();
+
+        let mempool = model.queues.incoming_transactions(model.shard_ids[0]);
+        let fees: Vec<u64> = mempool.iter().map(|&tx| model.transactions[tx].fee).collect();
+        // the order of the remaining transactions is preserved
+        assert_eq!(fees, [9, 7, 7, 8]);
+    }
+}
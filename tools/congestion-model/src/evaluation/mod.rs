@@ -1,14 +1,53 @@
+pub use amplification::Amplification;
 use chrono::{Duration, Utc};
+pub use forwarding::AckTraffic;
+pub use frames::FrameExporter;
+pub use gas_ledger::GasLedger;
+pub use grid_search::{grid_search, BestConfig, GridParameter, GridPoint, GridSearchConfig};
+pub use histogram::Histogram;
+pub use load_curve::{load_curve, LatencyStats, LoadCurveConfig};
+pub use marginal_shards::{
+    marginal_shards, print_marginal_table, MarginalShardsConfig, ShardCountResult,
+};
+pub use onset_recovery::OnsetRecovery;
 pub use queue_lengths::{QueueStats, ShardQueueLengths};
+pub use read_write::ReadWriteGas;
+pub use receipt_latency::ReceiptLatency;
+pub use refund_callbacks::RefundCallbackLoad;
+pub(crate) use retired::{RetiredChunks, RetiredTransactions};
 pub use transaction_progress::TransactionStatus;
+pub use transaction_view::{ReceiptRecord, ReceiptState, TransactionView};
 pub use user_experience::UserExperience;
+pub use witness::ChunkUtilization;
 
 use crate::{GGas, Model};
 
+mod amplification;
+mod completion_bound;
+mod congestion_index;
+mod flow;
+mod forwarding;
+mod frames;
+mod gas_ledger;
+mod grid_search;
+mod head_of_line;
+mod histogram;
+mod idle_time;
+mod load_curve;
+mod marginal_shards;
+mod mempool;
+mod onset_recovery;
+mod queue_age;
 mod queue_lengths;
+mod read_write;
+mod receipt_latency;
+mod refund_callbacks;
+mod retired;
 pub mod summary_table;
 mod transaction_progress;
+mod transaction_view;
 mod user_experience;
+mod witness;
 
 #[derive(Debug, Clone)]
 pub struct GasThroughput {
@@ -28,14 +67,15 @@ pub type StatsWriter = Option<Box<csv::Writer<std::fs::File>>>;
 
 impl Model {
     pub fn gas_throughput(&self) -> GasThroughput {
-        GasThroughput { total: self.transactions.all_transactions().map(|tx| tx.gas_burnt()).sum() }
+        let live: GGas = self.transactions.all_transactions().map(|tx| tx.gas_burnt()).sum();
+        GasThroughput { total: self.retired.gas_burnt + live }
     }
 
     pub fn progress(&self) -> Progress {
-        let mut finished_transactions = 0;
+        let mut finished_transactions = self.retired.finished;
         let mut pending_transactions = 0;
         let mut waiting_transactions = 0;
-        let mut failed_transactions = 0;
+        let mut failed_transactions = self.retired.failed;
 
         for tx in self.transactions.all_transactions() {
             match tx.status() {
@@ -74,6 +114,31 @@ impl Model {
             }
         }
 
+        for shard_id in self.shard_ids.clone() {
+            let field_name = format!("shard_{}_dominated_by_single_receipt", shard_id);
+            stats_writer.write_field(field_name).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(format!("shard_{}_max_queue_age", shard_id)).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(format!("shard_{}_forwarding_gas", shard_id)).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(format!("shard_{}_mempool_depth", shard_id)).unwrap();
+            stats_writer.write_field(format!("shard_{}_evicted_transactions", shard_id)).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(format!("shard_{}_debug_state", shard_id)).unwrap();
+        }
+
+        stats_writer.write_field("gas_utilization").unwrap();
+        stats_writer.write_field("witness_utilization").unwrap();
+
         stats_writer.write_field("successful_tx_delay_avg").unwrap();
         stats_writer.write_field("successful_tx_delay_median").unwrap();
         stats_writer.write_field("successful_tx_delay_90th_percentile").unwrap();
@@ -113,6 +178,42 @@ impl Model {
             }
         }
 
+        for shard_id in self.shard_ids.clone() {
+            let dominated = self
+                .last_chunk_stats(shard_id)
+                .map_or(false, |stats| stats.dominated_by_single_receipt());
+            stats_writer.write_field(format!("{}", dominated as u8)).unwrap();
+        }
+
+        let max_queue_age = self.max_queue_age(self.round);
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(format!("{}", max_queue_age[&shard_id])).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            let forwarding_gas = self.last_chunk_stats(shard_id).map_or(0, |s| s.forwarding_gas);
+            stats_writer.write_field(format!("{}", forwarding_gas)).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            let mempool_depth = self.queues.incoming_transactions(shard_id).len();
+            let evicted = self.last_chunk_stats(shard_id).map_or(0, |s| s.evicted_transactions);
+            stats_writer.write_field(format!("{}", mempool_depth)).unwrap();
+            stats_writer.write_field(format!("{}", evicted)).unwrap();
+        }
+
+        for shard_id in self.shard_ids.clone() {
+            stats_writer.write_field(self.strategy_debug_state(shard_id).to_string()).unwrap();
+        }
+
+        // empty before the first round and without a witness limit
+        let last_round = self.chunk_stats.len().checked_sub(self.shard_ids.len());
+        let utilization = last_round.map(|start| self.utilization(&self.chunk_stats[start..]));
+        let gas = utilization.as_ref().map(|u| u.gas.to_string());
+        let witness = utilization.and_then(|u| u.witness).map(|w| w.to_string());
+        stats_writer.write_field(gas.unwrap_or_default()).unwrap();
+        stats_writer.write_field(witness.unwrap_or_default()).unwrap();
+
         let user_exp = self.user_experience();
         stats_writer.write_field(user_exp.successful_tx_delay_avg.to_string()).unwrap();
         stats_writer.write_field(user_exp.successful_tx_delay_median.to_string()).unwrap();
@@ -0,0 +1,135 @@
+use crate::{GGas, Model, Round};
+use std::ops::Range;
+
+/// How quickly queues react to a burst of load, see [`Model::onset_recovery`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnsetRecovery {
+    /// Rounds from the first round of the burst until a queue first exceeded
+    /// the threshold. 0 if a queue already exceeded it in the first round,
+    /// `None` if none did during or after the burst.
+    pub onset: Option<Round>,
+    /// Rounds from the first round after the burst until all queues were
+    /// back at or below the threshold for the rest of the run. 0 if they
+    /// already were. `None` if they were not recovered at the end of the run,
+    /// or if the burst has not ended, yet.
+    pub recovery: Option<Round>,
+}
+
+impl OnsetRecovery {
+    pub fn recovered(&self) -> bool {
+        self.recovery.is_some()
+    }
+}
+
+impl Model {
+    /// Onset and recovery of congestion around a burst of load in the given
+    /// rounds.
+    ///
+    /// The queue of a shard in a round is the gas attached to all its queued
+    /// receipts after its chunk, see [`crate::ChunkStats::queued_gas`]. The
+    /// network counts as congested in a round if any shard's queue exceeds
+    /// `threshold`. Rounds before the burst are ignored.
+    pub fn onset_recovery(&self, burst: Range<Round>, threshold: GGas) -> OnsetRecovery {
+        assert!(burst.start < burst.end, "burst must last at least one round");
+        // every round records one chunk per shard
+        let congested: Vec<(Round, bool)> = self
+            .chunk_stats
+            .chunks(self.shard_ids.len())
+            .map(|chunks| {
+                (chunks[0].round, chunks.iter().any(|stats| stats.queued_gas > threshold))
+            })
+            .filter(|&(round, _)| round >= burst.start)
+            .collect();
+
+        let onset = congested
+            .iter()
+            .find(|&&(_, congested)| congested)
+            .map(|&(round, _)| round - burst.start);
+        let recovery = if self.round < burst.end {
+            None
+        } else {
+            match congested.iter().rev().find(|&&(_, congested)| congested) {
+                // the last congested round must be the final one to not recover
+                Some(&(round, _)) if round == self.round => None,
+                Some(&(round, _)) => Some((round + 1).saturating_sub(burst.end)),
+                None => Some(0),
+            }
+        };
+        OnsetRecovery { onset, recovery }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::Producer;
+    use crate::{ReceiptDefinition, ShardId, TransactionBuilder, PGAS, TGAS};
+    use rand::RngCore;
+
+    const BURST: Range<Round> = 10..20;
+
+    /// Twice the capacity of the shard during the burst, a tenth of it
+    /// otherwise.
+    struct Burst;
+
+    impl Producer for Burst {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let num = if BURST.contains(&round) { 20 } else { 1 };
+            (0..num)
+                .map(|_| {
+                    let mut tx = tx_factory(shards[0]);
+                    let receipt = ReceiptDefinition {
+                        receiver: shards[0],
+                        size: 100,
+                        attached_gas: 100 * TGAS,
+                        execution_gas: 100 * TGAS,
+                    };
+                    tx.add_first_receipt(receipt, 0);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    fn run(rounds: usize) -> Model {
+        let mut model = Model::new(vec![Box::new(NoQueueShard {})], Box::new(Burst));
+        for _ in 0..rounds {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn onset_and_recovery_around_a_burst() {
+        // the queue grows by 1 PGas per round of the burst, from the round
+        // after it starts, and drains by 0.9 PGas per round after it
+        let model = run(50);
+        let measured = model.onset_recovery(BURST, 2 * PGAS);
+        assert_eq!(measured, OnsetRecovery { onset: Some(3), recovery: Some(9) });
+        assert!(measured.recovered());
+    }
+
+    #[test]
+    fn queues_below_the_threshold_recover_immediately() {
+        let measured = run(50).onset_recovery(BURST, 20 * PGAS);
+        assert_eq!(measured, OnsetRecovery { onset: None, recovery: Some(0) });
+    }
+
+    #[test]
+    fn run_ending_congested_never_recovers() {
+        let measured = run(25).onset_recovery(BURST, 2 * PGAS);
+        assert_eq!(measured, OnsetRecovery { onset: Some(3), recovery: None });
+        assert!(!measured.recovered());
+        // the burst has not ended, yet
+        assert_eq!(run(15).onset_recovery(BURST, 2 * PGAS).recovery, None);
+    }
+}
@@ -0,0 +1,39 @@
+use super::Histogram;
+use crate::{Model, Round, ShardId};
+use std::collections::HashMap;
+
+impl Model {
+    /// Distribution of how long receipts currently sitting in queues have
+    /// been waiting, measured at the given round.
+    ///
+    /// The age of a receipt is `round - created_at`. All queues of a shard
+    /// are included, not only the incoming receipts. A backlog with high ages
+    /// indicates a strategy that keeps admitting new work while old work rots.
+    pub fn queue_age_distribution(&self, round: Round) -> HashMap<ShardId, Histogram> {
+        self.shard_ids
+            .iter()
+            .map(|&shard| {
+                let ages = self
+                    .queues
+                    .shard_queues(shard)
+                    .flat_map(|queue| queue.iter())
+                    .map(|receipt| {
+                        let created_at =
+                            receipt.created_at.expect("queued receipts must have been created");
+                        round.saturating_sub(created_at)
+                    })
+                    .collect();
+                (shard, ages)
+            })
+            .collect()
+    }
+
+    /// Age of the oldest queued receipt per shard, measured at the given
+    /// round. Shards with empty queues report 0.
+    pub fn max_queue_age(&self, round: Round) -> HashMap<ShardId, Round> {
+        self.queue_age_distribution(round)
+            .into_iter()
+            .map(|(shard, ages)| (shard, ages.max().unwrap_or(0)))
+            .collect()
+    }
+}
@@ -43,6 +43,11 @@ impl Model {
         out
     }
 
+    /// All receipt queues of a shard, including its incoming receipts.
+    pub fn shard_queues(&self, shard_id: ShardId) -> impl Iterator<Item = &Queue> {
+        self.queues.shard_queues(shard_id)
+    }
+
     /// Current max queue length stats.
     pub fn max_queue_length(&self) -> ShardQueueLengths {
         let mut out = ShardQueueLengths::default();
@@ -0,0 +1,81 @@
+use crate::{GGas, Model, ShardId};
+use std::collections::HashMap;
+
+/// Gas burnt by a shard, split by whether it changed state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadWriteGas {
+    /// Gas burnt by read-only receipts.
+    pub read: GGas,
+    /// All other gas, including transaction conversion and forwarding.
+    pub write: GGas,
+}
+
+impl Model {
+    /// Total gas each shard burnt so far, split into reads and writes.
+    pub fn read_write_gas(&self) -> HashMap<ShardId, ReadWriteGas> {
+        let mut out: HashMap<ShardId, ReadWriteGas> = self
+            .shard_ids
+            .iter()
+            .map(|shard| {
+                let retired = self.retired_chunks.shards.get(shard);
+                (*shard, retired.map_or_else(ReadWriteGas::default, |r| r.read_write))
+            })
+            .collect();
+        for stats in &self.chunk_stats {
+            let gas = out.get_mut(&stats.shard).unwrap();
+            gas.read += stats.read_gas_burnt;
+            gas.write += stats.gas_burnt - stats.read_gas_burnt;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::{BalancedProducer, ReadTrafficProducer};
+    use crate::{ReceiptDefinition, TGAS};
+
+    #[test]
+    fn read_only_receipts_stay_on_their_shard() {
+        let shards = (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let producer = ReadTrafficProducer::new(Box::<BalancedProducer>::default(), 20, 10 * TGAS);
+        let mut model = Model::new(shards, Box::new(producer));
+        let mut reads_seen = 0;
+        for _ in 0..50 {
+            model.step();
+            for &shard in model.shard_ids() {
+                for receipt in model.shard_queues(shard).flat_map(|queue| queue.iter()) {
+                    if receipt.read_only {
+                        assert_eq!(receipt.receiver, shard);
+                        reads_seen += 1;
+                    }
+                }
+            }
+        }
+        assert!(reads_seen > 0);
+
+        let ledger = model.gas_ledger();
+        assert!(ledger.is_balanced(), "{ledger:?}");
+        let total: GGas = model.read_write_gas().values().map(|gas| gas.read + gas.write).sum();
+        assert_eq!(total, model.gas_throughput().total);
+        for gas in model.read_write_gas().values() {
+            assert!(gas.read > 0 && gas.write > gas.read, "{gas:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "must execute on the shard that creates it")]
+    fn read_only_receipt_to_other_shard_panics() {
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        let &[sender, receiver] = model.shard_ids() else { unreachable!() };
+        let mut tx = model.transactions.new_transaction_builder(sender, 0);
+        let def =
+            ReceiptDefinition { receiver, size: 100, attached_gas: TGAS, execution_gas: TGAS };
+        let first = tx.add_first_receipt(def, TGAS);
+        tx.mark_read_only(first);
+        model.transactions.build_transaction(tx);
+    }
+}
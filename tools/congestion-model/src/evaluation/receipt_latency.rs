@@ -0,0 +1,100 @@
+use super::Histogram;
+use crate::{Model, Receipt, Round};
+
+/// Receipt latency in rounds, from creation to execution, split into refunds
+/// and all other receipts.
+///
+/// Receipts still waiting in a queue are included with their current age.
+/// Dropped receipts and receipts that have not been created, yet, are not.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReceiptLatency {
+    /// Receipts created with [`crate::TransactionBuilder::new_refund_receipt`].
+    pub refund: Histogram,
+    /// All other receipts.
+    pub user: Histogram,
+}
+
+impl Model {
+    /// Latency of all receipts created so far, measured at the current round.
+    ///
+    /// Refunds queue like any other receipt, so under congestion their
+    /// delivery is delayed, too. Comparing the two distributions shows
+    /// whether a refund backlog builds up.
+    pub fn receipt_latency(&self) -> ReceiptLatency {
+        let mut latency = self.retired.receipt_latency.clone();
+        for receipt in self.all_receipts().into_values() {
+            latency.add(receipt, self.round);
+        }
+        latency
+    }
+}
+
+impl ReceiptLatency {
+    pub(super) fn add(&mut self, receipt: &Receipt, round: Round) {
+        let Some(created_at) = receipt.created_at else { return };
+        if receipt.dropped_at.is_some() {
+            return;
+        }
+        let until = receipt.executed_at.unwrap_or(round);
+        let histogram = if receipt.refund { &mut self.refund } else { &mut self.user };
+        histogram.add(until - created_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::Producer;
+    use crate::{ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+    use rand::RngCore;
+
+    /// Shard 0 sends cheap calls to shard 1 with refunds back, while local
+    /// transactions overload shard 0.
+    struct CongestedRefunds;
+
+    impl Producer for CongestedRefunds {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let receipt = |receiver, gas| ReceiptDefinition {
+                receiver,
+                size: 100,
+                attached_gas: gas,
+                execution_gas: gas,
+            };
+            let mut out = vec![];
+            for _ in 0..50 {
+                let mut tx = tx_factory(shards[0]);
+                let call = tx.add_first_receipt(receipt(shards[1], 10 * TGAS), 0);
+                tx.new_refund_receipt(call);
+                out.push(tx);
+            }
+            for _ in 0..15 {
+                let mut tx = tx_factory(shards[0]);
+                tx.add_first_receipt(receipt(shards[0], 100 * TGAS), 0);
+                out.push(tx);
+            }
+            out
+        }
+    }
+
+    #[test]
+    fn refunds_queue_behind_congestion() {
+        let shards = (0..2).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(shards, Box::new(CongestedRefunds));
+        for _ in 0..50 {
+            model.step();
+        }
+        let latency = model.receipt_latency();
+        assert_eq!(latency.user.percentile(50), Some(1), "{latency:?}");
+        assert!(latency.refund.percentile(50) > Some(5), "{latency:?}");
+        assert!(latency.refund.max() > latency.user.max(), "{latency:?}");
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{GGas, Model, Transaction};
+
+/// Work done by receipts that refunds triggered, see
+/// [`crate::TransactionBuilder::new_refund_callback`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RefundCallbackLoad {
+    /// Number of callbacks executed so far.
+    pub executed: usize,
+    /// Gas the executed callbacks burnt.
+    pub gas_burnt: GGas,
+}
+
+impl Model {
+    /// Extra load from refunds that trigger callbacks, over all transactions.
+    pub fn refund_callback_load(&self) -> RefundCallbackLoad {
+        let mut load = self.retired.refund_callbacks;
+        for tx in self.transactions.all_transactions() {
+            load.add_transaction(tx);
+        }
+        load
+    }
+}
+
+impl RefundCallbackLoad {
+    pub(super) fn add_transaction(&mut self, tx: &Transaction) {
+        for receipt in tx.executed_receipts.values().filter(|receipt| receipt.refund_callback) {
+            self.executed += 1;
+            self.gas_burnt += receipt.gas_burnt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NepStrategy;
+    use crate::workload::{BalancedProducer, RefundCallbackProducer};
+    use crate::{CongestionStrategy, Model};
+
+    fn run(callback_probability: f64) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let producer =
+            RefundCallbackProducer::new(Box::<BalancedProducer>::default(), callback_probability);
+        let mut model = Model::new(shards, Box::new(producer));
+        for _ in 0..50 {
+            model.step();
+        }
+        model
+    }
+
+    #[test]
+    fn callbacks_wait_for_their_refund_to_execute() {
+        let model = run(0.5);
+        let receipts = model.all_receipts();
+        let mut created = 0;
+        for tx in model.transactions.all_transactions() {
+            for (id, predecessors) in &tx.dependencies {
+                let callback = receipts[id];
+                if !callback.refund_callback {
+                    continue;
+                }
+                let [refund] = predecessors.as_slice() else {
+                    panic!("callback {id:?} must only depend on its refund");
+                };
+                let refund = receipts[refund];
+                assert!(refund.refund);
+                assert_eq!(callback.receiver, tx.sender_shard);
+                match callback.created_at {
+                    Some(round) => {
+                        assert_eq!(refund.executed_at, Some(round));
+                        created += 1;
+                    }
+                    None => assert_eq!(refund.executed_at, None),
+                }
+            }
+        }
+        assert!(created > 100, "only {created} callbacks created");
+        assert!(model.gas_ledger().is_balanced());
+    }
+
+    #[test]
+    fn callbacks_add_load() {
+        let without = run(0.0);
+        assert_eq!(without.refund_callback_load().executed, 0);
+        let with = run(1.0);
+        let load = with.refund_callback_load();
+        assert!(load.executed > 100, "{load:?}");
+        assert_eq!(load.gas_burnt, load.executed as u64 * 20 * crate::TGAS);
+    }
+}
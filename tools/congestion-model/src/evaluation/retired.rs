@@ -0,0 +1,202 @@
+use super::completion_bound::BoundWork;
+use super::congestion_index::{occupancy, stretch};
+use super::flow::add_flow;
+use super::idle_time::idle_while_congested;
+use super::{
+    Amplification, GasLedger, Histogram, ReadWriteGas, ReceiptLatency, RefundCallbackLoad,
+    TransactionStatus, TransactionView,
+};
+use crate::{ChunkStats, GGas, ModelConfig, Receipt, ReceiptId, Round, ShardId, Transaction};
+use std::collections::{BTreeMap, HashMap};
+
+/// What the aggregate metrics need from transactions pruned with
+/// [`ModelConfig::prune_completed`], collected when they completed.
+///
+/// Each metric starts from its part here and adds the transactions still in
+/// the registry, so pruning does not change any of them. Only the views of
+/// single transactions are gone.
+#[derive(Default)]
+pub(crate) struct RetiredTransactions {
+    pub(super) finished: usize,
+    pub(super) failed: usize,
+    pub(super) gas_burnt: GGas,
+    pub(super) successful_delays: Histogram,
+    pub(super) rejected_delays: Histogram,
+    pub(super) stretch_sum: f64,
+    pub(super) stretch_count: usize,
+    pub(super) in_flight_rounds: Round,
+    pub(super) ledger: GasLedger,
+    pub(super) amplification: Amplification,
+    pub(super) flow: BTreeMap<(ShardId, ShardId), GGas>,
+    pub(super) refund_callbacks: RefundCallbackLoad,
+    pub(super) receipt_latency: ReceiptLatency,
+    pub(super) bound_work: BoundWork,
+}
+
+impl RetiredTransactions {
+    /// Take over the contributions of a transaction that just completed.
+    pub(crate) fn retire(&mut self, tx: &Transaction, config: &ModelConfig, round: Round) {
+        match tx.status() {
+            TransactionStatus::FinishedSuccess => {
+                self.finished += 1;
+                self.successful_delays.add(tx.delay());
+            }
+            TransactionStatus::Failed => {
+                self.failed += 1;
+                self.rejected_delays.add(tx.delay());
+            }
+            TransactionStatus::Init | TransactionStatus::Pending => {
+                panic!("tried to retire transaction {:?} that has not completed", tx.id)
+            }
+        }
+        self.gas_burnt += tx.gas_burnt();
+        if let Some(stretch) = stretch(tx, round) {
+            self.stretch_sum += stretch;
+            self.stretch_count += 1;
+        }
+
+        // a completed transaction holds all of its receipts
+        let receipts: HashMap<ReceiptId, &Receipt> = tx
+            .future_receipts
+            .values()
+            .chain(tx.executed_receipts.values())
+            .chain(tx.dropped_receipts.values())
+            .map(|receipt| (receipt.id, receipt))
+            .collect();
+        self.in_flight_rounds += tx.in_flight_rounds(receipts[&tx.initial_receipt], round);
+        for receipt in receipts.values() {
+            self.receipt_latency.add(receipt, round);
+        }
+        self.bound_work.add_transaction(tx, &receipts, config);
+
+        self.ledger.add_transaction(tx);
+        self.amplification.add(&TransactionView { tx });
+        add_flow(&mut self.flow, tx);
+        self.refund_callbacks.add_transaction(tx);
+    }
+}
+
+/// What the aggregate metrics need from the chunk statistics that
+/// [`ModelConfig::prune_completed`] drops once they are older than
+/// [`crate::CHUNK_STATS_RETENTION`] rounds.
+///
+/// Like [`RetiredTransactions`], each metric adds the statistics still kept.
+#[derive(Default)]
+pub(crate) struct RetiredChunks {
+    pub(super) chunks: usize,
+    pub(super) occupancy_sum: f64,
+    pub(super) shards: HashMap<ShardId, RetiredShard>,
+    pub(super) acks_sent: usize,
+    pub(super) retransmitted_receipts: usize,
+    pub(super) ack_gas: GGas,
+    pub(super) wasted_capacity: GGas,
+    pub(crate) timed_out_yields: usize,
+}
+
+/// Totals of one shard in [`RetiredChunks`].
+#[derive(Clone, Copy, Default)]
+pub(super) struct RetiredShard {
+    pub(super) read_write: ReadWriteGas,
+    pub(super) forwarding_gas: GGas,
+    pub(super) evicted_transactions: usize,
+    pub(super) rejected_transactions: usize,
+    pub(super) idle_rounds: usize,
+}
+
+impl RetiredChunks {
+    /// Take over the contributions of the chunks of one round, one per shard.
+    pub(crate) fn retire_round(&mut self, round: &[ChunkStats], config: &ModelConfig) {
+        for stats in round {
+            self.chunks += 1;
+            self.occupancy_sum += occupancy(stats);
+            self.acks_sent += stats.acks_sent;
+            self.retransmitted_receipts += stats.retransmitted_receipts;
+            self.ack_gas += stats.ack_gas;
+            self.timed_out_yields += stats.timed_out_yields;
+            let shard = self.shards.entry(stats.shard).or_default();
+            shard.read_write.read += stats.read_gas_burnt;
+            shard.read_write.write += stats.gas_burnt - stats.read_gas_burnt;
+            shard.forwarding_gas += stats.forwarding_gas;
+            shard.evicted_transactions += stats.evicted_transactions;
+            shard.rejected_transactions += stats.rejected_transactions;
+        }
+        for stats in idle_while_congested(round, round.len(), config) {
+            self.shards.entry(stats.shard).or_default().idle_rounds += 1;
+            self.wasted_capacity += stats.gas_limit;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NepStrategy;
+    use crate::workload::{BalancedProducer, RefundCallbackProducer};
+    use crate::{AckConfig, CongestionStrategy, Model, ModelConfig, CHUNK_STATS_RETENTION};
+
+    /// Long enough to drop chunk statistics.
+    const ROUNDS: usize = 2 * CHUNK_STATS_RETENTION;
+
+    fn run(prune_completed: bool) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let workload = BalancedProducer::with_sizes_and_fan_out(vec![100, 1000], 3);
+        let workload = RefundCallbackProducer::new(Box::new(workload), 0.5);
+        // evictions make some transactions fail, acks retransmit drops
+        let config = ModelConfig {
+            drop_probability: 0.05,
+            acks: Some(AckConfig { ack_size: 100, ack_gas: 0, retransmit_timeout: 3 }),
+            mempool_capacity: Some(50),
+            prune_completed,
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(shards, Box::new(workload)).with_config(config).with_seed(3);
+        for _ in 0..ROUNDS {
+            model.step();
+            if prune_completed {
+                // nothing keeps growing with the length of the run
+                assert!(model.chunk_stats.len() <= CHUNK_STATS_RETENTION * 4);
+                let (unacked, delivered) = model.acks.tracked_receipts();
+                assert!(delivered <= unacked, "{delivered} delivered, {unacked} unacked");
+            }
+        }
+        model
+    }
+
+    #[test]
+    fn pruning_keeps_all_aggregate_metrics() {
+        let full = run(false);
+        let pruned = run(true);
+        let progress = full.progress();
+        assert!(progress.finished_transactions > 0 && progress.failed_transactions > 0);
+        // failed transactions may still have receipts in flight
+        let remaining = full.in_flight_transactions().len() + progress.waiting_transactions;
+        assert_eq!(pruned.transaction_views().count(), remaining);
+
+        assert_eq!(format!("{:?}", pruned.progress()), format!("{progress:?}"));
+        assert_eq!(pruned.gas_throughput().total, full.gas_throughput().total);
+        assert_eq!(pruned.latency_stats(), full.latency_stats());
+        assert_eq!(
+            format!("{:?}", pruned.user_experience()),
+            format!("{:?}", full.user_experience())
+        );
+        assert_eq!(pruned.gas_ledger(), full.gas_ledger());
+        assert_eq!(pruned.chunk_stats.len(), CHUNK_STATS_RETENTION * 4);
+        assert_eq!(full.chunk_stats.len(), ROUNDS * 4);
+        assert_eq!(pruned.read_write_gas(), full.read_write_gas());
+        assert_eq!(pruned.forwarding_gas(), full.forwarding_gas());
+        assert_eq!(pruned.ack_traffic(), full.ack_traffic());
+        assert!(full.ack_traffic().retransmitted_receipts > 0);
+        assert_eq!(pruned.evicted_transactions(), full.evicted_transactions());
+        assert_eq!(pruned.rejected_transactions(), full.rejected_transactions());
+        assert_eq!(pruned.wasted_capacity(), full.wasted_capacity());
+        assert_eq!(pruned.timed_out_yields(), full.timed_out_yields());
+        assert_eq!(pruned.amplification_histogram(), full.amplification_histogram());
+        assert_eq!(pruned.gas_flow(), full.gas_flow());
+        assert_eq!(pruned.refund_callback_load(), full.refund_callback_load());
+        assert_eq!(pruned.receipt_latency(), full.receipt_latency());
+        assert_eq!(pruned.optimal_completion_bound(), full.optimal_completion_bound());
+        assert_eq!(pruned.average_in_flight_transactions(), full.average_in_flight_transactions());
+        // the stretch is summed in another order
+        assert!((pruned.congestion_index() - full.congestion_index()).abs() < 1e-9);
+    }
+}
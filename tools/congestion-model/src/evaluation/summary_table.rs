@@ -1,37 +1,278 @@
-use super::{GasThroughput, Progress, ShardQueueLengths, UserExperience};
-use crate::{PGAS, TGAS};
-
-pub fn print_summary_header() {
-    println!(
-        "{:<25}{:<25}{:>25}{:>25}{:>16}{:>16}{:>16}{:>16}{:>16}",
-        "WORKLOAD",
-        "STRATEGY",
-        "BURNT GAS PER CHUNK",
-        "TRANSACTIONS FINISHED",
-        "MEDIAN TX DELAY",
-        "90p TX DELAY",
-        "MAX QUEUE LEN",
-        "MAX QUEUE SIZE",
-        "MAX QUEUE PGAS",
-    );
+use super::{GasLedger, ShardQueueLengths, TransactionStatus};
+use crate::{GGas, Model, Round, PGAS, TGAS};
+use std::str::FromStr;
+
+/// How [`print_summary_header`] and [`print_summary_row`] print the summary
+/// of each run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// Aligned columns for reading, with a subset of the metrics.
+    #[default]
+    Human,
+    /// One JSON object per run and line, see [`RunSummary::to_json`].
+    Json,
+    /// Tab-separated values with a header line.
+    Tsv,
+}
+
+impl FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("unknown format {s:?}, expected human, json or tsv")),
+        }
+    }
+}
+
+/// Top-level metrics at the end of a run, for scripts to parse.
+///
+/// The field names are the keys of the JSON and TSV output and stay stable.
+/// Gas is in GGas, times in rounds. In JSON, the gas ledger is a nested object
+/// under `gas_ledger` with the field names of [`GasLedger`]. In TSV, its
+/// fields are flattened to `gas_ledger.attached` and so on.
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    pub workload: String,
+    pub strategy: String,
+    pub rounds: Round,
+    /// Average gas burnt per chunk, after warmup.
+    pub burnt_gas_per_chunk: GGas,
+    pub finished_transactions: u64,
+    pub failed_transactions: u64,
+    /// Transactions still waiting or pending at the end.
+    pub unresolved_transactions: u64,
+    /// Receipts dropped by a shard or lost between shards, of all
+    /// transactions.
+    pub dropped_receipts: u64,
+    /// Delay of successful transactions, see [`crate::Model::user_experience`].
+    pub tx_delay_median: Round,
+    pub tx_delay_p90: Round,
+    /// Latency including unresolved transactions, see
+    /// [`crate::Model::latency_stats`].
+    pub latency_p50: Round,
+    pub latency_p90: Round,
+    pub latency_p99: Round,
+    pub latency_max: Round,
+    /// Largest queue over all shards and rounds, see
+    /// [`crate::Model::max_queue_length`].
+    pub max_queue_receipts: u64,
+    pub max_queue_bytes: u64,
+    pub max_queue_gas: GGas,
+    /// See [`crate::Model::congestion_index`].
+    pub congestion_index: f64,
+    /// Sum over all shards of [`crate::Model::idle_rounds`].
+    pub idle_rounds: u64,
+    /// See [`crate::Model::wasted_capacity`].
+    pub wasted_capacity: GGas,
+    pub gas_ledger: GasLedger,
 }
 
-pub fn print_summary_row(
-    workload: &str,
-    strategy: &str,
-    progress: &Progress,
-    throughput: &GasThroughput,
-    max_queues: &ShardQueueLengths,
-    user_experience: &UserExperience,
-) {
-    println!(
-        "{workload:<25}{strategy:<25}{:>20} TGas{:>25}{:>16}{:>16}{:>16}{:>16}{:>16}",
-        throughput.total / TGAS,
-        progress.finished_transactions,
-        user_experience.successful_tx_delay_median,
-        user_experience.successful_tx_delay_90th_percentile,
-        max_queues.queued_receipts.num,
-        bytesize::ByteSize::b(max_queues.queued_receipts.size),
-        max_queues.queued_receipts.gas / PGAS,
-    );
+impl RunSummary {
+    /// Summary of the model at its current round.
+    ///
+    /// The model does not remember the largest queues or the gas burnt during
+    /// warmup, the caller tracks them during the run.
+    pub fn new(
+        workload: &str,
+        strategy: &str,
+        model: &Model,
+        burnt_gas_per_chunk: GGas,
+        max_queues: &ShardQueueLengths,
+    ) -> Self {
+        let progress = model.progress();
+        let latency = model.latency_stats();
+        let user_experience = model.user_experience();
+        // the histogram only covers completed transactions
+        let amplification = model.amplification_histogram();
+        let completed_drops: u64 = amplification.dropped.iter().map(|(drops, n)| drops * n).sum();
+        let in_flight_drops: usize = model
+            .transaction_views()
+            .filter(|view| {
+                matches!(view.status(), TransactionStatus::Init | TransactionStatus::Pending)
+            })
+            .map(|view| view.dropped_receipt_count())
+            .sum();
+        Self {
+            workload: workload.to_owned(),
+            strategy: strategy.to_owned(),
+            rounds: model.round(),
+            burnt_gas_per_chunk,
+            finished_transactions: progress.finished_transactions as u64,
+            failed_transactions: progress.failed_transactions as u64,
+            unresolved_transactions: latency.unresolved,
+            dropped_receipts: completed_drops + in_flight_drops as u64,
+            tx_delay_median: user_experience.successful_tx_delay_median,
+            tx_delay_p90: user_experience.successful_tx_delay_90th_percentile,
+            latency_p50: latency.p50,
+            latency_p90: latency.p90,
+            latency_p99: latency.p99,
+            latency_max: latency.max,
+            max_queue_receipts: max_queues.queued_receipts.num,
+            max_queue_bytes: max_queues.queued_receipts.size,
+            max_queue_gas: max_queues.queued_receipts.gas,
+            congestion_index: model.congestion_index(),
+            idle_rounds: model
+                .shard_ids()
+                .iter()
+                .map(|&shard| model.idle_rounds(shard) as u64)
+                .sum(),
+            wasted_capacity: model.wasted_capacity(),
+            gas_ledger: model.gas_ledger(),
+        }
+    }
+
+    /// All metrics as one JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        let ledger: serde_json::Map<String, serde_json::Value> = self
+            .gas_ledger
+            .fields()
+            .into_iter()
+            .map(|(key, gas)| (key.to_owned(), gas.into()))
+            .collect();
+        let mut json: serde_json::Map<String, serde_json::Value> =
+            self.fields().into_iter().map(|(key, value)| (key.to_owned(), value)).collect();
+        json.insert("gas_ledger".to_owned(), ledger.into());
+        json.into()
+    }
+
+    /// Columns of the TSV output, as `(key, value)`.
+    fn tsv_fields(&self) -> Vec<(String, String)> {
+        let top_level = self.fields().into_iter().map(|(key, value)| match value {
+            // unquoted, names cannot contain tabs or line breaks
+            serde_json::Value::String(value) => (key.to_owned(), value.replace(['\t', '\n'], " ")),
+            value => (key.to_owned(), value.to_string()),
+        });
+        let ledger = self
+            .gas_ledger
+            .fields()
+            .into_iter()
+            .map(|(key, gas)| (format!("gas_ledger.{key}"), gas.to_string()));
+        top_level.chain(ledger).collect()
+    }
+
+    /// All fields but the gas ledger, in output order.
+    fn fields(&self) -> Vec<(&'static str, serde_json::Value)> {
+        vec![
+            ("workload", self.workload.as_str().into()),
+            ("strategy", self.strategy.as_str().into()),
+            ("rounds", self.rounds.into()),
+            ("burnt_gas_per_chunk", self.burnt_gas_per_chunk.into()),
+            ("finished_transactions", self.finished_transactions.into()),
+            ("failed_transactions", self.failed_transactions.into()),
+            ("unresolved_transactions", self.unresolved_transactions.into()),
+            ("dropped_receipts", self.dropped_receipts.into()),
+            ("tx_delay_median", self.tx_delay_median.into()),
+            ("tx_delay_p90", self.tx_delay_p90.into()),
+            ("latency_p50", self.latency_p50.into()),
+            ("latency_p90", self.latency_p90.into()),
+            ("latency_p99", self.latency_p99.into()),
+            ("latency_max", self.latency_max.into()),
+            ("max_queue_receipts", self.max_queue_receipts.into()),
+            ("max_queue_bytes", self.max_queue_bytes.into()),
+            ("max_queue_gas", self.max_queue_gas.into()),
+            ("congestion_index", self.congestion_index.into()),
+            ("idle_rounds", self.idle_rounds.into()),
+            ("wasted_capacity", self.wasted_capacity.into()),
+        ]
+    }
+}
+
+/// Print what comes before the first row, nothing for JSON.
+pub fn print_summary_header(format: SummaryFormat) {
+    match format {
+        SummaryFormat::Human => println!(
+            "{:<25}{:<25}{:>25}{:>25}{:>16}{:>16}{:>16}{:>16}{:>16}",
+            "WORKLOAD",
+            "STRATEGY",
+            "BURNT GAS PER CHUNK",
+            "TRANSACTIONS FINISHED",
+            "MEDIAN TX DELAY",
+            "90p TX DELAY",
+            "MAX QUEUE LEN",
+            "MAX QUEUE SIZE",
+            "MAX QUEUE PGAS",
+        ),
+        SummaryFormat::Json => {}
+        SummaryFormat::Tsv => {
+            let keys: Vec<String> =
+                RunSummary::default().tsv_fields().into_iter().map(|(key, _)| key).collect();
+            println!("{}", keys.join("\t"));
+        }
+    }
+}
+
+pub fn print_summary_row(summary: &RunSummary, format: SummaryFormat) {
+    match format {
+        SummaryFormat::Human => println!(
+            "{:<25}{:<25}{:>20} TGas{:>25}{:>16}{:>16}{:>16}{:>16}{:>16}",
+            summary.workload,
+            summary.strategy,
+            summary.burnt_gas_per_chunk / TGAS,
+            summary.finished_transactions,
+            summary.tx_delay_median,
+            summary.tx_delay_p90,
+            summary.max_queue_receipts,
+            bytesize::ByteSize::b(summary.max_queue_bytes),
+            summary.max_queue_gas / PGAS,
+        ),
+        SummaryFormat::Json => println!("{}", summary.to_json()),
+        SummaryFormat::Tsv => {
+            let values: Vec<String> =
+                summary.tsv_fields().into_iter().map(|(_, value)| value).collect();
+            println!("{}", values.join("\t"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::AllForOneProducer;
+
+    fn summary() -> RunSummary {
+        let shards = (0..3).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<AllForOneProducer>::default());
+        for _ in 0..20 {
+            model.step();
+        }
+        RunSummary::new("All for one", "NEP", &model, 0, &model.max_queue_length())
+    }
+
+    #[test]
+    fn json_holds_all_metrics() {
+        let summary = summary();
+        let json = summary.to_json();
+        assert_eq!(json["strategy"].to_string(), "\"NEP\"");
+        assert_eq!(json["rounds"].as_u64(), Some(20));
+        assert_eq!(json["finished_transactions"].as_u64(), Some(summary.finished_transactions));
+        assert_eq!(json["latency_p99"].as_u64(), Some(summary.latency_p99));
+        assert_eq!(json["congestion_index"].as_f64(), Some(summary.congestion_index));
+        assert_eq!(json["wasted_capacity"].as_u64(), Some(summary.wasted_capacity));
+        assert_eq!(json["gas_ledger"]["attached"].as_u64(), Some(summary.gas_ledger.attached));
+        assert!(summary.gas_ledger.attached > 0);
+    }
+
+    #[test]
+    fn tsv_columns_match_the_header() {
+        let fields = summary().tsv_fields();
+        let header = RunSummary::default().tsv_fields();
+        assert_eq!(fields.len(), header.len());
+        assert!(fields.iter().zip(&header).all(|((key, _), (header_key, _))| key == header_key));
+        assert_eq!(fields[0], ("workload".to_owned(), "All for one".to_owned()));
+        assert!(header.iter().any(|(key, _)| key == "gas_ledger.not_created"));
+        assert!(fields.iter().all(|(_, value)| !value.contains('\t')));
+    }
+
+    #[test]
+    fn formats_parse_by_name() {
+        assert_eq!("json".parse(), Ok(SummaryFormat::Json));
+        assert_eq!("tsv".parse(), Ok(SummaryFormat::Tsv));
+        assert_eq!("human".parse(), Ok(SummaryFormat::Human));
+        assert!("csv".parse::<SummaryFormat>().is_err());
+    }
 }
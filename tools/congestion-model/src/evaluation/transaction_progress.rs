@@ -1,4 +1,4 @@
-use crate::{GGas, Transaction};
+use crate::{GGas, Model, Receipt, Round, Transaction, TransactionId};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TransactionStatus {
@@ -10,7 +10,10 @@ pub enum TransactionStatus {
 
 impl Transaction {
     pub(crate) fn status(&self) -> TransactionStatus {
-        if !self.dropped_receipts.is_empty() {
+        if !self.dropped_receipts.is_empty()
+            || self.evicted_at.is_some()
+            || self.rejection.is_some()
+        {
             return TransactionStatus::Failed;
         }
 
@@ -26,11 +29,180 @@ impl Transaction {
     }
 
     pub(crate) fn gas_burnt(&self) -> GGas {
+        if let Some(rejection) = &self.rejection {
+            return rejection.gas_burnt;
+        }
         if self.future_receipts.contains_key(&self.initial_receipt) {
             return 0;
         }
         let receipts_gas: GGas =
             self.executed_receipts.values().map(|receipt| receipt.gas_burnt()).sum();
-        receipts_gas + self.tx_conversion_cost
+        receipts_gas + self.tx_conversion_cost + self.send_gas_burnt
+    }
+
+    /// Rounds the transaction has been in flight, up to the end of the given
+    /// round. 0 if it has not been converted.
+    pub(super) fn in_flight_rounds(&self, initial_receipt: &Receipt, round: Round) -> Round {
+        let Some(converted) = initial_receipt.created_at else { return 0 };
+        if self.is_in_flight() {
+            round + 1 - converted
+        } else {
+            self.last_change() - converted
+        }
+    }
+}
+
+impl Transaction {
+    /// Whether the transaction has started and can still make progress.
+    ///
+    /// Progress is only possible while receipts are pending. Receipts that
+    /// haven't been created, yet, are only created by executing a pending
+    /// receipt. Hence, a transaction whose pending receipts have all been
+    /// dropped is dead, even if it still has future receipts.
+    ///
+    /// A transaction with some dropped receipts but other branches still
+    /// pending is considered in flight, even though its status is already
+    /// [`TransactionStatus::Failed`].
+    pub(crate) fn is_in_flight(&self) -> bool {
+        !self.pending_receipts.is_empty()
+    }
+}
+
+impl Model {
+    /// Transactions that have started but not completed, yet.
+    ///
+    /// See [`Transaction::is_in_flight`] for how transactions with dropped
+    /// receipts are treated.
+    pub fn in_flight_transactions(&self) -> Vec<TransactionId> {
+        self.transactions
+            .all_transactions()
+            .filter(|tx| tx.is_in_flight())
+            .map(|tx| tx.id)
+            .collect()
+    }
+
+    /// Number of transactions in flight at the end of a round, averaged over
+    /// all rounds so far. 0 before the first round.
+    ///
+    /// A transaction is in flight from the round it is converted until the
+    /// round before it completes.
+    pub fn average_in_flight_transactions(&self) -> f64 {
+        if self.round == 0 {
+            return 0.0;
+        }
+        let receipts = self.all_receipts();
+        let mut in_flight_rounds = self.retired.in_flight_rounds;
+        for tx in self.transactions.all_transactions() {
+            in_flight_rounds += tx.in_flight_rounds(receipts[&tx.initial_receipt], self.round);
+        }
+        in_flight_rounds as f64 / self.round as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ChunkExecutionContext;
+    use crate::strategy::QueueFactory;
+    use crate::workload::Producer;
+    use crate::{CongestionStrategy, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+    use rand::RngCore;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Executes all receipts, except on the `drop_at` shard which drops all.
+    struct DropAtShard {
+        drop_at: usize,
+        id: Option<ShardId>,
+    }
+
+    impl CongestionStrategy for DropAtShard {
+        fn init(&mut self, id: ShardId, _: &[ShardId], _: &mut dyn QueueFactory) {
+            self.id = Some(id);
+        }
+
+        fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+            while let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let receipt = ctx.accept_transaction(tx);
+                ctx.forward_receipt(receipt);
+            }
+            while let Some(receipt) = ctx.incoming_receipts().pop_front() {
+                if *self.id.unwrap() == self.drop_at {
+                    ctx.drop_receipt(receipt);
+                } else {
+                    for outgoing in ctx.execute_receipt(receipt) {
+                        ctx.forward_receipt(outgoing);
+                    }
+                }
+            }
+        }
+    }
+
+    /// In the first round, submits one transaction with a chain of three
+    /// receipts on shard 0 and one that continues with a chain of two
+    /// receipts on shard 1.
+    struct TwoTransactions;
+
+    impl Producer for TwoTransactions {
+        fn init(&mut self, _: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            let receipt = |shard| ReceiptDefinition {
+                receiver: shard,
+                size: 100,
+                attached_gas: 10 * TGAS,
+                execution_gas: TGAS,
+            };
+
+            let mut local = tx_factory(shards[0]);
+            let first = local.add_first_receipt(receipt(shards[0]), TGAS);
+            let second = local.new_outgoing_receipt(first, receipt(shards[0]));
+            local.new_outgoing_receipt(second, receipt(shards[0]));
+
+            let mut remote = tx_factory(shards[0]);
+            let first = remote.add_first_receipt(receipt(shards[0]), TGAS);
+            let second = remote.new_outgoing_receipt(first, receipt(shards[1]));
+            remote.new_outgoing_receipt(second, receipt(shards[1]));
+
+            vec![local, remote]
+        }
+    }
+
+    #[test]
+    fn transactions_with_only_dropped_receipts_are_not_in_flight() {
+        let shards = (0..2).map(|_| Box::new(DropAtShard { drop_at: 1, id: None }) as _).collect();
+        let mut model = Model::new(shards, Box::new(TwoTransactions));
+        let in_flight = |model: &Model| model.in_flight_transactions().len();
+        let completed_at = Rc::new(RefCell::new(vec![]));
+        let sink = completed_at.clone();
+        model.on_transaction_complete(move |_, round| sink.borrow_mut().push(round));
+
+        // both accepted, first receipts executed on shard 0
+        model.step();
+        model.step();
+        assert_eq!(in_flight(&model), 2);
+        // shard 1 drops the second remote receipt, the third one is never
+        // created
+        model.step();
+        assert_eq!(in_flight(&model), 1);
+        // last local receipt executed
+        model.step();
+        assert_eq!(in_flight(&model), 0);
+
+        let progress = model.progress();
+        assert_eq!(progress.finished_transactions, 1);
+        assert_eq!(progress.failed_transactions, 1);
+        assert_eq!(*completed_at.borrow(), [3, 4]);
+        // in flight at the end of rounds 1 to 3 and 1 to 2
+        assert_eq!(model.average_in_flight_transactions(), 5.0 / 4.0);
     }
 }
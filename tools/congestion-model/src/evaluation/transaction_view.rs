@@ -0,0 +1,223 @@
+use super::TransactionStatus;
+use crate::{GGas, Model, ReceiptId, Rejection, Round, ShardId, Transaction, TransactionId};
+use std::collections::HashMap;
+
+/// Read-only view of a transaction for analysis after a model run.
+///
+/// Only the [`Model`] hands out views. Strategies never get access to it, so
+/// what the view reveals, like the gas each receipt burnt, stays hidden from
+/// them during the run.
+pub struct TransactionView<'model> {
+    pub(super) tx: &'model Transaction,
+}
+
+/// A receipt of a transaction, as seen through a [`TransactionView`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceiptRecord {
+    id: ReceiptId,
+    state: ReceiptState,
+    gas_burnt: GGas,
+}
+
+/// Where a receipt is in its life cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiptState {
+    /// The receipt has not been created.
+    Future,
+    /// The receipt was created and waits for execution, in a queue or on the
+    /// way to its receiver.
+    Pending,
+    Executed,
+    Dropped,
+}
+
+impl Model {
+    /// View of a single transaction.
+    ///
+    /// Panics if the transaction was pruned, see
+    /// [`crate::ModelConfig::prune_completed`].
+    pub fn transaction_view(&self, id: TransactionId) -> TransactionView<'_> {
+        TransactionView { tx: &self.transactions[id] }
+    }
+
+    /// Views of all transactions submitted so far, except the pruned ones.
+    pub fn transaction_views(&self) -> impl Iterator<Item = TransactionView<'_>> {
+        self.transactions.all_transactions().map(|tx| TransactionView { tx })
+    }
+}
+
+impl TransactionView<'_> {
+    pub fn id(&self) -> TransactionId {
+        self.tx.id
+    }
+
+    pub fn status(&self) -> TransactionStatus {
+        self.tx.status()
+    }
+
+    pub fn submitted_at(&self) -> Round {
+        self.tx.submitted_at
+    }
+
+    pub fn sender_shard(&self) -> ShardId {
+        self.tx.sender_shard
+    }
+
+    /// Gas burnt for converting the transaction, 0 if it was not converted.
+    /// For a rejected transaction, the gas burnt for rejecting it.
+    pub fn conversion_gas_burnt(&self) -> GGas {
+        if let Some(rejection) = &self.tx.rejection {
+            rejection.gas_burnt
+        } else if self.tx.future_receipts.contains_key(&self.tx.initial_receipt) {
+            0
+        } else {
+            self.tx.tx_conversion_cost
+        }
+    }
+
+    /// Why and when the sender shard rejected the transaction, if it did.
+    pub fn rejection(&self) -> Option<Rejection> {
+        self.tx.rejection
+    }
+
+    /// All receipts of the transaction, in depth-first order of its DAG.
+    pub fn receipts(&self) -> Vec<ReceiptRecord> {
+        self.tx.depth_first_receipts().into_iter().map(|id| self.record(id)).collect()
+    }
+
+    /// Number of receipts that executed, so far.
+    ///
+    /// For a finished transaction, this is its work amplification: how many
+    /// receipts the shards had to execute for one submitted transaction.
+    pub fn receipt_count(&self) -> usize {
+        self.tx.executed_receipts.len()
+    }
+
+    /// Number of receipts that were dropped, not included in
+    /// [`TransactionView::receipt_count`].
+    pub fn dropped_receipt_count(&self) -> usize {
+        self.tx.dropped_receipts.len()
+    }
+
+    /// Number of receipts cut from the DAG before submission, see
+    /// [`crate::TransactionBuilder::with_max_receipts`].
+    pub fn truncated_receipt_count(&self) -> usize {
+        self.tx.truncated_receipts
+    }
+
+    /// Number of receipts at each level of the transaction's DAG, starting
+    /// with the first receipt at level 0.
+    ///
+    /// A receipt's level is the longest path to it from the first receipt, so
+    /// the receipts of one level could all execute in the same round. Deep,
+    /// narrow profiles are bound by latency, shallow, wide ones by
+    /// throughput. Timeout receipts of yields are not part of the profile.
+    pub fn parallelism_profile(&self) -> Vec<usize> {
+        let tx = self.tx;
+        let mut levels: HashMap<ReceiptId, usize> = HashMap::new();
+        let mut stack = vec![(tx.initial_receipt, 0)];
+        while let Some((id, level)) = stack.pop() {
+            if levels.get(&id).is_some_and(|&known| known >= level) {
+                continue;
+            }
+            levels.insert(id, level);
+            stack.extend(tx.outgoing[&id].iter().map(|&next| (next, level + 1)));
+        }
+        let mut profile = vec![0; levels.values().max().map_or(0, |&max| max + 1)];
+        for level in levels.into_values() {
+            profile[level] += 1;
+        }
+        profile
+    }
+
+    fn record(&self, id: ReceiptId) -> ReceiptRecord {
+        let tx = self.tx;
+        let (state, gas_burnt) = if let Some(receipt) = tx.executed_receipts.get(&id) {
+            (ReceiptState::Executed, receipt.gas_burnt())
+        } else if tx.dropped_receipts.contains_key(&id) {
+            (ReceiptState::Dropped, 0)
+        } else if tx.pending_receipts.contains(&id) {
+            (ReceiptState::Pending, 0)
+        } else {
+            (ReceiptState::Future, 0)
+        };
+        ReceiptRecord { id, state, gas_burnt }
+    }
+}
+
+impl ReceiptRecord {
+    pub fn id(&self) -> ReceiptId {
+        self.id
+    }
+
+    pub fn state(&self) -> ReceiptState {
+        self.state
+    }
+
+    /// Gas the receipt burnt when it executed. 0 for receipts that did not
+    /// execute, whether they are pending, dropped or not created.
+    pub fn gas_burnt(&self) -> GGas {
+        self.gas_burnt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::BalancedProducer;
+    use crate::{ModelConfig, ReceiptDefinition, TGAS};
+
+    #[test]
+    fn receipts_burn_gas_only_when_executed() {
+        let shards = (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let config = ModelConfig { drop_probability: 0.05, ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..30 {
+            model.step();
+        }
+
+        let mut total = 0;
+        let mut seen = vec![];
+        for view in model.transaction_views() {
+            total += view.conversion_gas_burnt();
+            for record in view.receipts() {
+                assert_eq!(record.id().transaction_id(), view.id());
+                match record.state() {
+                    ReceiptState::Executed => assert!(record.gas_burnt() > 0),
+                    _ => assert_eq!(record.gas_burnt(), 0),
+                }
+                total += record.gas_burnt();
+                if !seen.contains(&record.state()) {
+                    seen.push(record.state());
+                }
+            }
+        }
+        assert_eq!(total, model.gas_throughput().total);
+        assert_eq!(seen.len(), 4, "{seen:?}");
+    }
+
+    #[test]
+    fn parallelism_profile_uses_longest_path() {
+        let shards = (0..2).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        let shard = model.shard_ids()[1];
+        let def = ReceiptDefinition {
+            receiver: shard,
+            size: 100,
+            attached_gas: TGAS,
+            execution_gas: TGAS,
+        };
+        // a diamond a -> (b, c) -> d with a tail d -> e and a shortcut a -> e
+        let mut tx = model.new_transaction(shard);
+        let a = tx.add_first_receipt(def.clone(), TGAS);
+        let b = tx.new_outgoing_receipt(a, def.clone());
+        let c = tx.new_outgoing_receipt(a, def.clone());
+        let d = tx.new_outgoing_receipt(b, def.clone());
+        tx.new_dependency(c, d);
+        let e = tx.new_outgoing_receipt(d, def);
+        tx.new_dependency(a, e);
+        let id = model.submit(tx, 1);
+        assert_eq!(model.transaction_view(id).parallelism_profile(), vec![1, 2, 1, 1]);
+    }
+}
@@ -1,3 +1,4 @@
+use super::Histogram;
 use crate::{Model, Round, Transaction, TransactionStatus};
 
 #[derive(Debug, Clone)]
@@ -13,29 +14,26 @@ pub struct UserExperience {
 
 impl Model {
     pub fn user_experience(&self) -> UserExperience {
-        let mut successful_delays = vec![];
-        let mut rejected_delays = vec![];
+        let mut successful_delays = self.retired.successful_delays.clone();
+        let mut rejected_delays = self.retired.rejected_delays.clone();
         let mut unresolved_transactions = 0;
         for tx in self.transactions.all_transactions() {
             match tx.status() {
                 TransactionStatus::Init | TransactionStatus::Pending => {
                     unresolved_transactions += 1
                 }
-                TransactionStatus::Failed => rejected_delays.push(tx.delay()),
-                TransactionStatus::FinishedSuccess => successful_delays.push(tx.delay()),
+                TransactionStatus::Failed => rejected_delays.add(tx.delay()),
+                TransactionStatus::FinishedSuccess => successful_delays.add(tx.delay()),
             }
         }
 
-        successful_delays.sort();
-        rejected_delays.sort();
-
         UserExperience {
             successful_tx_delay_avg: avg(&successful_delays),
-            successful_tx_delay_median: percentile(&successful_delays, 50),
-            successful_tx_delay_90th_percentile: percentile(&successful_delays, 90),
+            successful_tx_delay_median: successful_delays.percentile(50).unwrap_or(0),
+            successful_tx_delay_90th_percentile: successful_delays.percentile(90).unwrap_or(0),
             rejected_tx_delay_avg: avg(&rejected_delays),
-            rejected_tx_delay_median: percentile(&rejected_delays, 50),
-            rejected_tx_delay_90th_percentile: percentile(&rejected_delays, 90),
+            rejected_tx_delay_median: rejected_delays.percentile(50).unwrap_or(0),
+            rejected_tx_delay_90th_percentile: rejected_delays.percentile(90).unwrap_or(0),
             unresolved_transactions,
         }
     }
@@ -47,7 +45,10 @@ impl Transaction {
     }
 
     pub fn last_change(&self) -> Round {
-        let mut last_change = self.submitted_at;
+        let mut last_change = self
+            .evicted_at
+            .or_else(|| self.rejection.map(|rejection| rejection.round))
+            .unwrap_or(self.submitted_at);
         for receipt in self.executed_receipts.values() {
             last_change = last_change.max(receipt.executed_at.unwrap());
         }
@@ -58,19 +59,10 @@ impl Transaction {
     }
 }
 
-fn avg(data: &[u64]) -> u64 {
-    if data.is_empty() {
-        0
-    } else {
-        data.iter().copied().sum::<u64>() / data.len() as u64
-    }
-}
-
-/// Input must be sorted
-fn percentile(data: &[u64], p: usize) -> u64 {
+fn avg(data: &Histogram) -> u64 {
     if data.is_empty() {
         0
     } else {
-        data[(data.len() - 1) * p / 100]
+        data.iter().map(|(value, n)| value * n).sum::<u64>() / data.count()
     }
 }
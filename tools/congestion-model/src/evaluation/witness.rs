@@ -0,0 +1,170 @@
+use crate::{ChunkStats, Model, Round};
+
+/// How full the chunks of a round were, by gas and by state witness, see
+/// [`Model::chunk_utilization`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkUtilization {
+    pub round: Round,
+    /// Gas burnt over the gas limits of all shards. Can exceed 1, the receipt
+    /// crossing the limit still executes.
+    pub gas: f64,
+    /// Witness size over [`crate::ModelConfig::max_witness_per_chunk`] of all
+    /// shards. `None` without a witness limit.
+    pub witness: Option<f64>,
+}
+
+impl ChunkUtilization {
+    /// Whether the witness limit was closer to binding than the gas limit.
+    pub fn witness_bound(&self) -> bool {
+        self.witness.is_some_and(|witness| witness > self.gas)
+    }
+}
+
+impl Model {
+    /// Gas and witness utilization for every round so far.
+    pub fn chunk_utilization(&self) -> Vec<ChunkUtilization> {
+        self.chunk_stats
+            .chunks(self.shard_ids.len())
+            .map(|chunks| self.utilization(chunks))
+            .collect()
+    }
+
+    /// Utilization of the chunks of one round, one per shard.
+    pub(super) fn utilization(&self, chunks: &[ChunkStats]) -> ChunkUtilization {
+        let ratio =
+            |used: u64, limit: u64| if limit == 0 { 0.0 } else { used as f64 / limit as f64 };
+        let gas_burnt = chunks.iter().map(|stats| stats.gas_burnt).sum();
+        let gas_limit = chunks.iter().map(|stats| stats.gas_limit).sum();
+        let witness = self.config.max_witness_per_chunk.map(|max| {
+            let witness_size = chunks.iter().map(|stats| stats.witness_size).sum();
+            ratio(witness_size, max * chunks.len() as u64)
+        });
+        ChunkUtilization { round: chunks[0].round, gas: ratio(gas_burnt, gas_limit), witness }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ChunkExecutionContext;
+    use crate::strategy::{NewTxLast, NoQueueShard, QueueFactory};
+    use crate::workload::Producer;
+    use crate::{
+        CongestionStrategy, ModelConfig, ReceiptDefinition, ShardId, TransactionBuilder, TGAS,
+    };
+    use rand::RngCore;
+
+    /// Per round, 20 cheap receipts that each read 100 kB of state, more than
+    /// a 1 MB witness holds but only 200 TGas.
+    struct BigReads;
+
+    impl Producer for BigReads {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            (0..20)
+                .map(|_| {
+                    let mut tx = tx_factory(shards[0]);
+                    let def = ReceiptDefinition {
+                        receiver: shards[0],
+                        size: 100,
+                        attached_gas: 10 * TGAS,
+                        execution_gas: 10 * TGAS,
+                    };
+                    let receipt = tx.add_first_receipt(def, 0);
+                    tx.set_witness_cost(receipt, 100_000);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    /// Executes every incoming receipt, ignoring both gas and witness limits.
+    struct Greedy;
+
+    impl CongestionStrategy for Greedy {
+        fn init(&mut self, _: ShardId, _: &[ShardId], _: &mut dyn QueueFactory) {}
+
+        fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+            while let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let receipt = ctx.accept_transaction(tx);
+                ctx.forward_receipt(receipt);
+            }
+            while let Some(receipt) = ctx.incoming_receipts().pop_front() {
+                for outgoing in ctx.execute_receipt(receipt) {
+                    ctx.forward_receipt(outgoing);
+                }
+            }
+        }
+    }
+
+    fn run_with(
+        strategy: Box<dyn CongestionStrategy>,
+        max_witness_per_chunk: Option<u64>,
+    ) -> Model {
+        let config = ModelConfig { max_witness_per_chunk, ..Default::default() };
+        let mut model = Model::new(vec![strategy], Box::new(BigReads)).with_config(config);
+        for _ in 0..20 {
+            model.step();
+        }
+        model
+    }
+
+    fn run(max_witness_per_chunk: Option<u64>) -> Model {
+        run_with(Box::new(NoQueueShard {}), max_witness_per_chunk)
+    }
+
+    #[test]
+    fn witness_limit_binds_before_gas() {
+        let model = run(Some(1_000_000));
+        // ten receipts reach the limit exactly, the queue grows every round
+        for stats in &model.chunk_stats[1..] {
+            assert_eq!(stats.witness_size, 1_000_000, "{stats:?}");
+        }
+        assert!(model.queue_lengths()[&model.shard_ids[0]].incoming_receipts.num > 100);
+        let utilization = model.chunk_utilization();
+        assert_eq!(utilization.len(), 20);
+        for round in &utilization[1..] {
+            assert_eq!(round.witness, Some(1.0));
+            assert!(round.gas < 0.2, "{round:?}");
+            assert!(round.witness_bound());
+        }
+    }
+
+    #[test]
+    fn without_a_limit_everything_executes() {
+        let model = run(None);
+        assert!(model.chunk_stats[1..].iter().all(|stats| stats.witness_size == 2_000_000));
+        // only the receipts converted in the last round wait
+        assert_eq!(model.queue_lengths()[&model.shard_ids[0]].incoming_receipts.num, 20);
+        assert!(model.chunk_utilization().iter().all(|round| !round.witness_bound()));
+    }
+
+    #[test]
+    fn conversion_continues_with_a_full_witness() {
+        // converts transactions only after the receipts filled the witness
+        let model = run_with(Box::new(NewTxLast {}), Some(1_000_000));
+        for stats in &model.chunk_stats[1..] {
+            assert_eq!(stats.witness_size, 1_000_000, "{stats:?}");
+            // all transactions of the previous round were converted
+            assert_eq!(stats.mempool_depth, 20, "{stats:?}");
+        }
+    }
+
+    #[test]
+    fn receipts_beyond_the_witness_are_deferred() {
+        let model = run_with(Box::new(Greedy), Some(1_000_000));
+        for stats in &model.chunk_stats[1..] {
+            assert_eq!(stats.witness_size, 1_000_000, "{stats:?}");
+        }
+        // ten receipts per round execute, the others wait for later chunks
+        let queued = model.queue_lengths()[&model.shard_ids[0]].incoming_receipts.num;
+        assert_eq!(queued, 20 * 20 - 19 * 10);
+    }
+}
@@ -1,12 +1,24 @@
 mod evaluation;
 mod model;
+mod scenario;
 pub mod strategy;
 pub mod workload;
 
 pub use evaluation::{
-    summary_table, QueueStats, ShardQueueLengths, StatsWriter, TransactionStatus,
+    grid_search, load_curve, marginal_shards, print_marginal_table, summary_table, AckTraffic,
+    Amplification, BestConfig, ChunkUtilization, FrameExporter, GasLedger, GridParameter,
+    GridPoint, GridSearchConfig, Histogram, LatencyStats, LoadCurveConfig, MarginalShardsConfig,
+    OnsetRecovery, QueueStats, ReadWriteGas, ReceiptLatency, ReceiptRecord, ReceiptState,
+    RefundCallbackLoad, ShardCountResult, ShardQueueLengths, StatsWriter, TransactionStatus,
+    TransactionView, UserExperience,
 };
-pub use model::{Model, Queue, QueueId, Receipt, ShardId, TransactionId};
+pub use model::{
+    AccountGroupedQueue, AckConfig, ChunkStats, EpochSchedule, EventDriven, ExecutionMode,
+    FixedStep, MempoolEviction, Model, ModelConfig, ModelState, Queue, QueueId, Receipt,
+    ReceiptCapPolicy, ReceiptPriority, Rejection, RejectionReason, RoundPolicy, ShardId,
+    ShardOrder, TransactionId, YieldConfig, CHUNK_STATS_RETENTION,
+};
+pub use scenario::{Component, Scenario, ScenarioError, SCENARIO_VERSION};
 pub use strategy::CongestionStrategy;
 pub use workload::{ReceiptDefinition, ReceiptId, TransactionBuilder};
 
@@ -1,17 +1,24 @@
 use bytesize::ByteSize;
 use chrono::Utc;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use congestion_model::strategy::{
-    FancyGlobalTransactionStop, GlobalTxStopShard, NepStrategy, NewTxLast, NoQueueShard,
-    SimpleBackpressure, SmoothTrafficLight, TrafficLight,
+    AdaptiveThreshold, FancyGlobalTransactionStop, GlobalNearlyDone, GlobalTxStopShard,
+    NepStrategy, NewTxLast, NoQueueShard, PriorityAging, PriorityGas, SimpleBackpressure,
+    SmoothTrafficLight, TrafficLight,
 };
+use congestion_model::summary_table::{RunSummary, SummaryFormat};
 use congestion_model::workload::{
-    AllForOneProducer, BalancedProducer, FairnessBenchmarkProducer, LinearImbalanceProducer,
-    Producer,
+    AccountTrafficProducer, AllForOneProducer, BalancedProducer, ClosedLoopProducer,
+    FairnessBenchmarkProducer, LinearImbalanceProducer, OversizedTxProducer, Producer,
+    ReadTrafficProducer, RefundCallbackProducer, WhaleProducer, YieldResumeProducer,
 };
 use congestion_model::{
-    summary_table, CongestionStrategy, Model, ShardQueueLengths, StatsWriter, PGAS, TGAS,
+    marginal_shards, print_marginal_table, summary_table, AccountGroupedQueue, AckConfig,
+    Component, CongestionStrategy, EpochSchedule, FrameExporter, MarginalShardsConfig, Model,
+    ModelConfig, ReceiptCapPolicy, Scenario, ScenarioError, ShardId, ShardOrder, ShardQueueLengths,
+    StatsWriter, TransactionId, YieldConfig, PGAS, TGAS,
 };
+use std::io::Write;
 use std::time::Duration;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{self, Layer};
@@ -52,11 +59,226 @@ struct Args {
     /// other.
     #[clap(long)]
     write_stats_filepath: Option<String>,
+
+    /// Optional path to write the gas flow from origin shards to executing
+    /// shards after the model run, as JSON for Sankey diagrams. Can only be
+    /// used when a single strategy and a single workload are selected.
+    #[clap(long)]
+    write_flow_json: Option<String>,
+
+    /// Optional directory to write one CSV frame of the queue depths per
+    /// round into, to animate them. Can only be used when a single strategy
+    /// and a single workload are selected.
+    #[clap(long)]
+    write_frames: Option<String>,
+
     /// At most N transactions can stay in the transaction pool and the remainder is rejected.
     ///
     /// This can be useful to look at transaction delays.
     #[clap(long, default_value_t = usize::MAX)]
     tx_pool_size: usize,
+
+    /// Seed for the random number generator used by workloads.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Probability for each transaction to be turned into a whale, which
+    /// multiplies gas and size of its heaviest receipt.
+    #[clap(long, default_value_t = 0.0)]
+    whale_probability: f64,
+
+    /// How much heavier a whale receipt is than the original receipt.
+    #[clap(long, default_value_t = 10)]
+    whale_gas_multiplier: u64,
+
+    /// Probability for each refund to trigger a callback receipt on the
+    /// sender shard once it executed.
+    #[clap(long, default_value_t = 0.0)]
+    refund_callback_probability: f64,
+
+    /// Probability for each transaction to declare `--oversized-tx-size`
+    /// bytes, to be rejected with `--max-tx-size`.
+    #[clap(long, default_value_t = 0.0)]
+    oversized_tx_probability: f64,
+
+    /// Declared size in bytes of oversized transactions.
+    #[clap(long, default_value_t = 4_000_000)]
+    oversized_tx_size: u64,
+
+    /// Gas in GGas that a shard burns for each receipt it forwards to another
+    /// shard.
+    #[clap(long, default_value_t = 0)]
+    forward_gas: u64,
+
+    /// Gas in GGas that a shard burns for each byte of receipts it forwards to
+    /// another shard.
+    #[clap(long, default_value_t = 0)]
+    forward_gas_per_byte: u64,
+
+    /// Changes of the chunk gas limit over time, as a comma separated list of
+    /// ROUND:TGAS pairs. Each change applies to all shards from the start of
+    /// its round onwards. Example: "500:1500,800:2000".
+    #[clap(long, value_parser = parse_gas_limit_change, value_delimiter = ',')]
+    gas_limit_schedule: Vec<(u64, u64)>,
+
+    /// Total gas in TGas all chunks of a round may burn together. Shards
+    /// executing earlier in the round get served first.
+    #[clap(long)]
+    global_gas_limit: Option<u64>,
+
+    /// Order in which shards execute within a round: "ascending",
+    /// "descending", "rotating", or a comma separated permutation of shard
+    /// indices like "2,0,1".
+    #[clap(long, default_value = "ascending")]
+    shard_order: ShardOrder,
+
+    /// Rounds per epoch, after which the validator groups rotate over the
+    /// shards. Requires --validator-group-performance.
+    #[clap(long)]
+    epoch_length: Option<u64>,
+
+    /// Gas limit factor of each validator group, as a comma separated list.
+    /// Example: "1,0.5" halves the capacity of every other shard, alternating
+    /// each epoch.
+    #[clap(long, value_delimiter = ',')]
+    validator_group_performance: Vec<f64>,
+
+    /// Maximum size in bytes of a single receipt. Workloads producing larger
+    /// receipts panic.
+    #[clap(long)]
+    max_receipt_size: Option<u64>,
+
+    /// Maximum declared size in bytes of a transaction. Sender shards reject
+    /// larger transactions before conversion.
+    #[clap(long)]
+    max_tx_size: Option<u64>,
+
+    /// Maximum state witness in bytes of a chunk. Chunks stop executing
+    /// receipts once the witness costs of their receipts reach it.
+    #[clap(long)]
+    max_witness_per_chunk: Option<u64>,
+
+    /// Share of the conversion cost burnt for each rejected transaction.
+    #[clap(long, default_value_t = 0.0)]
+    rejected_tx_cost_fraction: f64,
+
+    /// Maximum number of receipts of a transaction. Sender shards reject
+    /// transactions with more receipts before conversion.
+    #[clap(long)]
+    max_outstanding_receipts_per_tx: Option<usize>,
+
+    /// Cut transactions down to `--max-outstanding-receipts-per-tx` receipts
+    /// instead of rejecting them.
+    #[clap(long, default_value = "false")]
+    truncate_excess_receipts: bool,
+
+    /// Gas in GGas that a shard burns each time it executes a receipt for
+    /// another account than the previous receipt.
+    #[clap(long, default_value_t = 0)]
+    account_switch_gas: u64,
+
+    /// Batch the incoming receipts of each shard by account, looking this
+    /// many receipts ahead. Keeps arrival order if not set.
+    #[clap(long)]
+    account_grouping_lookahead: Option<usize>,
+
+    /// Relative variance of each chunk's gas limit. With 0.1, the gas limit
+    /// varies uniformly between 90% and 110% of its scheduled value.
+    #[clap(long, default_value_t = 0.0)]
+    gas_limit_jitter: f64,
+
+    /// Maximum number of transactions in each shard's mempool. Transactions
+    /// beyond this are evicted, oldest first.
+    #[clap(long)]
+    mempool_capacity: Option<usize>,
+
+    /// Probability for each receipt sent to another shard to get lost.
+    #[clap(long, default_value_t = 0.0)]
+    drop_probability: f64,
+
+    /// Acknowledge every receipt sent to another shard with an ack of this
+    /// size in bytes, and retransmit receipts without ack. Disabled if not
+    /// set.
+    #[clap(long)]
+    ack_size: Option<u64>,
+
+    /// Gas in GGas that a shard burns for creating each ack.
+    #[clap(long, default_value_t = 0)]
+    ack_gas: u64,
+
+    /// Rounds a shard waits for an ack before it sends a receipt again.
+    #[clap(long, default_value_t = 4)]
+    retransmit_timeout: u64,
+
+    /// Probability for each yielded receipt to be resumed in a round.
+    #[clap(long, default_value_t = 0.1)]
+    resume_probability: f64,
+
+    /// Rounds after which a yielded receipt without resume times out.
+    #[clap(long, default_value_t = 200)]
+    yield_timeout: u64,
+
+    /// Share of its gas limit a shard must burn to count as congested when
+    /// reporting capacity wasted on idle shards, more than 0 and at most 1.
+    /// Defaults to 0.9.
+    #[clap(long, value_parser = parse_congestion_threshold)]
+    congestion_threshold: Option<f64>,
+
+    /// Apply each chunk one round after it was produced, which delays every
+    /// receipt by one more round.
+    #[clap(long, default_value = "false")]
+    chunk_apply_lag: bool,
+
+    /// Remove transactions from memory once they complete, for long runs.
+    /// The reported statistics stay the same.
+    #[clap(long, default_value = "false")]
+    prune_completed: bool,
+
+    /// Format of the summary printed for each run: "human" for aligned
+    /// columns, "json" for one JSON object per line, or "tsv" for
+    /// tab-separated values with a header. See `RunSummary` for the keys.
+    #[clap(long, default_value = "human")]
+    format: SummaryFormat,
+
+    /// Print the receipts left in each shard's queues after the run, with the
+    /// shards holding the most gas first. Printed to stderr with the json and
+    /// tsv formats.
+    #[clap(long, default_value = "false")]
+    dump_queues: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Step through a single workload and strategy round by round and show
+    /// the state of one shard after each round.
+    ///
+    /// Press enter to advance one round, type a number N to advance N rounds
+    /// at once, or "q" to quit.
+    Spotlight {
+        /// Index of the shard to show.
+        #[arg(long, default_value_t = 0)]
+        shard: usize,
+    },
+    /// Run the "Account Traffic" workload with --shards, one more, and so on
+    /// up to the given number of shards, and print the marginal effect of
+    /// each added shard on congestion and latency.
+    ///
+    /// The workload keeps its total load and spreads its accounts evenly over
+    /// all shards. Uses a single strategy and the model arguments.
+    Marginal {
+        /// Largest number of shards to run with.
+        #[arg(long)]
+        max_shards: usize,
+    },
+    /// Run the experiment described by a scenario file and print its summary.
+    /// All other arguments are ignored, see `Scenario` for the format.
+    Run {
+        /// Path to the scenario file.
+        scenario: String,
+    },
 }
 
 fn main() {
@@ -67,16 +289,57 @@ fn main() {
     let subscriber = tracing_subscriber::registry().with(layer);
     tracing::subscriber::set_global_default(subscriber).expect("could not set a global subscriber");
 
-    summary_table::print_summary_header();
+    if let Some(Command::Run { scenario }) = &args.command {
+        if let Err(err) = run_scenario(scenario, args.format) {
+            eprintln!("{scenario}: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let workload_names = parse_workload_names(args.workload.as_ref());
     let strategy_names = parse_strategy_names(args.strategy.as_ref());
 
+    if let Some(Command::Marginal { max_shards }) = args.command {
+        if strategy_names.len() != 1 {
+            panic!(
+                "marginal can only be used with a single strategy. Parsed {:?} strategies. ",
+                strategy_names
+            );
+        }
+        let strategy_name = strategy_names[0].clone();
+        let config = MarginalShardsConfig::new(
+            move |num_shards| strategy(&strategy_name, num_shards),
+            || Box::<AccountTrafficProducer>::default(),
+        )
+        .with_model_config(model_config(&args))
+        .with_seed(args.seed)
+        .with_rounds(args.rounds);
+        print_marginal_table(&marginal_shards(&config, args.shards..=max_shards));
+        return;
+    }
+
+    if let Some(Command::Spotlight { shard }) = args.command {
+        if workload_names.len() != 1 || strategy_names.len() != 1 {
+            panic!("spotlight can only be used with single workload and strategy. Parsed {:?} workloads and {:?} strategies. ", workload_names, strategy_names);
+        }
+        run_spotlight(&strategy_names[0], &workload_names[0], &args, shard);
+        return;
+    }
+
+    summary_table::print_summary_header(args.format);
+
     if args.write_stats_filepath.is_some()
         && (workload_names.len() != 1 || strategy_names.len() != 1)
     {
         panic!("write_stats_filepath can only be used with single workload and strategy. Parsed {:?} workloads and {:?} strategies. ", workload_names, strategy_names);
     }
+    if args.write_flow_json.is_some() && (workload_names.len() != 1 || strategy_names.len() != 1) {
+        panic!("write_flow_json can only be used with single workload and strategy. Parsed {:?} workloads and {:?} strategies. ", workload_names, strategy_names);
+    }
+    if args.write_frames.is_some() && (workload_names.len() != 1 || strategy_names.len() != 1) {
+        panic!("write_frames can only be used with single workload and strategy. Parsed {:?} workloads and {:?} strategies. ", workload_names, strategy_names);
+    }
 
     for workload_name in &workload_names {
         for strategy_name in &strategy_names {
@@ -87,15 +350,7 @@ fn main() {
                 strategy_name,
             );
 
-            run_model(
-                &strategy_name,
-                &workload_name,
-                args.shards,
-                args.rounds,
-                args.warmup,
-                stats_writer,
-                args.tx_pool_size,
-            );
+            run_model(&strategy_name, &workload_name, &args, stats_writer);
         }
     }
 }
@@ -116,18 +371,231 @@ fn parse_stats_writer(
     Some(stats_writer)
 }
 
-fn run_model(
-    strategy_name: &str,
+fn build_model(strategy_name: &str, workload_name: &str, args: &Args) -> Model {
+    let strategy = strategy(strategy_name, args.shards);
+    // The closed loop learns about completions from a model callback, which
+    // can only be registered once the model exists.
+    let (mut workload, completions) = workload_with_completions(workload_name);
+    if args.whale_probability > 0.0 {
+        workload = Box::new(WhaleProducer::new(
+            workload,
+            args.whale_probability,
+            args.whale_gas_multiplier,
+        ));
+    }
+    if args.refund_callback_probability > 0.0 {
+        workload =
+            Box::new(RefundCallbackProducer::new(workload, args.refund_callback_probability));
+    }
+    if args.oversized_tx_probability > 0.0 {
+        workload = Box::new(OversizedTxProducer::new(
+            workload,
+            args.oversized_tx_probability,
+            args.oversized_tx_size,
+        ));
+    }
+    let mut model =
+        Model::new(strategy, workload).with_seed(args.seed).with_config(model_config(args));
+    if let Some(completions) = completions {
+        model.on_transaction_complete(completions);
+    }
+    model
+}
+
+/// The model config from the command line arguments.
+fn model_config(args: &Args) -> ModelConfig {
+    ModelConfig {
+        forward_gas: args.forward_gas,
+        forward_gas_per_byte: args.forward_gas_per_byte,
+        gas_limit_schedule: args.gas_limit_schedule.clone(),
+        global_gas_limit: args.global_gas_limit.map(|tgas| tgas * TGAS),
+        shard_order: args.shard_order.clone(),
+        epoch_schedule: args.epoch_length.map(|epoch_length| EpochSchedule {
+            epoch_length,
+            group_performance: args.validator_group_performance.clone(),
+        }),
+        max_receipt_size: args.max_receipt_size,
+        max_tx_size: args.max_tx_size,
+        max_witness_per_chunk: args.max_witness_per_chunk,
+        rejected_tx_cost_fraction: args.rejected_tx_cost_fraction,
+        max_outstanding_receipts_per_tx: args.max_outstanding_receipts_per_tx,
+        receipt_cap_policy: if args.truncate_excess_receipts {
+            ReceiptCapPolicy::Truncate
+        } else {
+            ReceiptCapPolicy::Reject
+        },
+        account_switch_gas: args.account_switch_gas,
+        account_grouping: args.account_grouping_lookahead.map(AccountGroupedQueue::new),
+        drop_probability: args.drop_probability,
+        gas_limit_jitter: args.gas_limit_jitter,
+        mempool_capacity: args.mempool_capacity,
+        acks: args.ack_size.map(|ack_size| AckConfig {
+            ack_size,
+            ack_gas: args.ack_gas,
+            retransmit_timeout: args.retransmit_timeout,
+        }),
+        yield_resume: YieldConfig {
+            resume_probability: args.resume_probability,
+            timeout: args.yield_timeout,
+        },
+        congestion_threshold: args.congestion_threshold,
+        chunk_apply_lag: args.chunk_apply_lag,
+        prune_completed: args.prune_completed,
+        ..ModelConfig::default()
+    }
+}
+
+/// The named workload and, for workloads that react to completions, the
+/// callback to register with the model.
+fn workload_with_completions(
     workload_name: &str,
+) -> (Box<dyn Producer>, Option<Box<dyn FnMut(TransactionId, u64)>>) {
+    // The closed loop learns about completions from a model callback, which
+    // can only be registered once the model exists.
+    if workload_name == "Closed Loop" {
+        let closed_loop = ClosedLoopProducer::default();
+        let completions = Box::new(closed_loop.completion_callback());
+        return (Box::new(closed_loop), Some(completions));
+    }
+    (workload(workload_name), None)
+}
+
+fn run_scenario(path: &str, format: SummaryFormat) -> Result<(), ScenarioError> {
+    let mut scenario = Scenario::from_file(path)?;
+    let strategy_name = find_name(&strategy_names(), &scenario.strategy.name).ok_or_else(|| {
+        let message = format!("unknown strategy, available are {:?}", strategy_names());
+        scenario.strategy.error("name", message)
+    })?;
+    let workload_name = find_name(&workload_names(), &scenario.workload.name).ok_or_else(|| {
+        let message = format!("unknown workload, available are {:?}", workload_names());
+        scenario.workload.error("name", message)
+    })?;
+
+    let strategy = scenario_strategy(&strategy_name, &mut scenario.strategy, scenario.shards)?;
+    scenario.strategy.finish()?;
+    let (mut workload, completions) = workload_with_completions(&workload_name);
+    let params = &mut scenario.workload;
+    if let Some(p) = params.take_f64("whale_probability")? {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(params.error("whale_probability", format!("{p} is not between 0 and 1")));
+        }
+        let multiplier = params.take_u64("whale_gas_multiplier")?.unwrap_or(10);
+        workload = Box::new(WhaleProducer::new(workload, p, multiplier));
+    }
+    if let Some(p) = params.take_f64("refund_callback_probability")? {
+        if !(0.0..=1.0).contains(&p) {
+            let message = format!("{p} is not between 0 and 1");
+            return Err(params.error("refund_callback_probability", message));
+        }
+        workload = Box::new(RefundCallbackProducer::new(workload, p));
+    }
+    if let Some(p) = params.take_f64("oversized_tx_probability")? {
+        if !(0.0..=1.0).contains(&p) {
+            let message = format!("{p} is not between 0 and 1");
+            return Err(params.error("oversized_tx_probability", message));
+        }
+        let tx_size = params.take_u64("oversized_tx_size")?.unwrap_or(4_000_000);
+        workload = Box::new(OversizedTxProducer::new(workload, p, tx_size));
+    }
+    params.finish()?;
+
+    let mut model =
+        Model::new(strategy, workload).with_seed(scenario.seed).with_config(scenario.config);
+    if let Some(completions) = completions {
+        model.on_transaction_complete(completions);
+    }
+    let mut max_queues = ShardQueueLengths::default();
+    for _ in 0..scenario.rounds {
+        model.step();
+        max_queues = max_queues.max_component_wise(&model.max_queue_length());
+    }
+    let burnt_gas_per_chunk =
+        (model.gas_throughput() / scenario.rounds.max(1) / scenario.shards).total;
+    let summary =
+        RunSummary::new(&workload_name, &strategy_name, &model, burnt_gas_per_chunk, &max_queues);
+    summary_table::print_summary_header(format);
+    summary_table::print_summary_row(&summary, format);
+    Ok(())
+}
+
+/// The named strategy for each shard, tuned by the parameters of the
+/// scenario. Strategies without builders take no parameters.
+fn scenario_strategy(
+    strategy_name: &str,
+    params: &mut Component,
     num_shards: usize,
-    num_rounds: usize,
-    num_warmup_rounds: usize,
-    mut stats_writer: StatsWriter,
-    tx_pool_size: usize,
-) {
-    let strategy = strategy(strategy_name, num_shards);
-    let workload = workload(workload_name);
-    let mut model = Model::new(strategy, workload);
+) -> Result<Vec<Box<dyn CongestionStrategy>>, ScenarioError> {
+    let strategy: Box<dyn Fn() -> Box<dyn CongestionStrategy>> = match strategy_name {
+        "NEP" => {
+            let gas_limits = params.take_u64_pair("gas_limits")?;
+            let memory_limits = params.take_u64_pair("memory_limits")?;
+            let global_stop_limit = params.take_f64("global_stop_limit")?;
+            Box::new(move || {
+                let mut nep = NepStrategy::default();
+                if let Some((incoming, outgoing)) = gas_limits {
+                    nep = nep.with_gas_limits(incoming * TGAS, outgoing * TGAS);
+                }
+                if let Some((incoming, outgoing)) = memory_limits {
+                    nep = nep.with_memory_limits(ByteSize::mb(incoming), ByteSize::mb(outgoing));
+                }
+                if let Some(limit) = global_stop_limit {
+                    nep = nep.with_global_stop_limit(limit);
+                }
+                Box::new(nep)
+            })
+        }
+        "Smooth Traffic Light" => {
+            let gas_limits = params.take_u64_pair("gas_limits")?;
+            let tx_reject_threshold = params.take_f64("tx_reject_threshold")?;
+            let smooth_slow_down = params.take_bool("smooth_slow_down")?;
+            Box::new(move || {
+                let mut stl = SmoothTrafficLight::default();
+                if let Some((incoming, outgoing)) = gas_limits {
+                    stl = stl.with_gas_limits(incoming * TGAS, outgoing * TGAS);
+                }
+                if let Some(threshold) = tx_reject_threshold {
+                    stl = stl.with_tx_reject_threshold(threshold);
+                }
+                if let Some(smooth) = smooth_slow_down {
+                    stl = stl.with_smooth_slow_down(smooth);
+                }
+                Box::new(stl)
+            })
+        }
+        "Priority Aging" => {
+            let aging_rate = params.take_f64("aging_rate")?;
+            if let Some(rate) = aging_rate.filter(|&rate| rate < 0.0) {
+                return Err(params.error("aging_rate", format!("{rate} is negative")));
+            }
+            Box::new(move || {
+                let strategy = PriorityAging::default();
+                Box::new(match aging_rate {
+                    Some(rate) => strategy.with_aging_rate(rate),
+                    None => strategy,
+                })
+            })
+        }
+        "Adaptive Threshold" => {
+            let damping = params.take_f64("damping")?;
+            Box::new(move || {
+                let strategy = AdaptiveThreshold::default();
+                Box::new(match damping {
+                    Some(damping) => strategy.with_damping(damping),
+                    None => strategy,
+                })
+            })
+        }
+        _ => return Ok(strategy(strategy_name, num_shards)),
+    };
+    Ok((0..num_shards).map(|_| strategy()).collect())
+}
+
+fn run_model(strategy_name: &str, workload_name: &str, args: &Args, mut stats_writer: StatsWriter) {
+    let num_shards = args.shards;
+    let num_rounds = args.rounds;
+    let num_warmup_rounds = args.warmup;
+
+    let mut model = build_model(strategy_name, workload_name, args);
     let mut max_queues = ShardQueueLengths::default();
 
     // Set the start time to an half hour ago to make it visible by default in
@@ -138,6 +606,7 @@ fn run_model(
     let mut warmup_gas_usage = model.gas_throughput();
 
     model.write_stats_header(&mut stats_writer);
+    let frame_exporter = args.write_frames.as_ref().map(|dir| FrameExporter::new(dir).unwrap());
 
     for round in 0..num_rounds {
         if round == num_warmup_rounds {
@@ -145,19 +614,120 @@ fn run_model(
         }
         model.write_stats_values(&mut stats_writer, start_time, round);
         model.step();
-        model.trim_transaction_pools(tx_pool_size);
+        model.trim_transaction_pools(args.tx_pool_size);
         max_queues = max_queues.max_component_wise(&model.max_queue_length());
+        if let Some(exporter) = &frame_exporter {
+            exporter.write_frame(&model).unwrap();
+        }
+    }
+    let burnt_gas_per_chunk = ((model.gas_throughput() - warmup_gas_usage)
+        / (num_rounds - num_warmup_rounds)
+        / num_shards)
+        .total;
+    let summary =
+        RunSummary::new(workload_name, strategy_name, &model, burnt_gas_per_chunk, &max_queues);
+    summary_table::print_summary_row(&summary, args.format);
+
+    if let Some(path) = &args.write_flow_json {
+        let mut file = std::fs::File::create(path).unwrap();
+        model.write_flow_json(&mut file).unwrap();
+    }
+
+    if args.dump_queues {
+        // keep stdout parseable for scripts
+        let mut out: Box<dyn Write> = match args.format {
+            SummaryFormat::Human => Box::new(std::io::stdout()),
+            SummaryFormat::Json | SummaryFormat::Tsv => Box::new(std::io::stderr()),
+        };
+        writeln!(out, "Remaining queues of {workload_name} with {strategy_name}").unwrap();
+        write_queue_dump(&mut out, &model).unwrap();
+    }
+}
+
+fn run_spotlight(strategy_name: &str, workload_name: &str, args: &Args, shard: usize) {
+    let mut model = build_model(strategy_name, workload_name, args);
+    let shard_id = *model.shard_ids().get(shard).unwrap_or_else(|| {
+        panic!("shard {shard} does not exist, there are only {} shards", args.shards)
+    });
+    println!("{workload_name} with {strategy_name}, showing shard {shard_id}");
+
+    let mut rounds_to_advance = 1;
+    while model.round() < args.rounds as u64 {
+        for _ in 0..rounds_to_advance {
+            model.step();
+            model.trim_transaction_pools(args.tx_pool_size);
+        }
+        if rounds_to_advance > 0 {
+            print_spotlight(&model, shard_id);
+        }
+
+        print!("[enter] next round, [N] advance N rounds, [q] quit > ");
+        std::io::stdout().flush().expect("failed to flush stdout");
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).expect("failed to read stdin") == 0 {
+            // end of input
+            break;
+        }
+        rounds_to_advance = match line.trim() {
+            "" => 1,
+            "q" => break,
+            input => input.parse().unwrap_or_else(|_| {
+                println!("expected a number of rounds or q, got {input:?}");
+                0
+            }),
+        };
     }
-    summary_table::print_summary_row(
-        workload_name,
-        strategy_name,
-        &model.progress(),
-        &((model.gas_throughput() - warmup_gas_usage)
-            / (num_rounds - num_warmup_rounds)
-            / num_shards),
-        &max_queues,
-        &model.user_experience(),
+}
+
+/// Print the state of one shard after the latest round.
+fn print_spotlight(model: &Model, shard_id: ShardId) {
+    // number of receipts listed per queue
+    const MAX_RECEIPTS_SHOWN: usize = 5;
+
+    let round = model.round();
+    println!("ROUND {round}, SHARD {shard_id}");
+    if let Some(stats) = model.last_chunk_stats(shard_id) {
+        println!(
+            "    {:>6} TGas burnt of {} TGas, {} TGas remaining",
+            stats.gas_burnt / TGAS,
+            stats.gas_limit / TGAS,
+            stats.gas_limit.saturating_sub(stats.gas_burnt) / TGAS,
+        );
+        for (receiver, num) in &stats.forwarded_receipts {
+            println!("    {num:>6} receipts forwarded to shard {receiver}");
+        }
+    }
+    println!(
+        "    {:>6} receipts received for next round",
+        model.received_receipts(round, shard_id)
     );
+    println!(
+        "    {:>6} transactions waiting",
+        model.queue_lengths()[&shard_id].unprocessed_incoming_transactions
+    );
+    for queue in model.shard_queues(shard_id) {
+        let stats = queue.stats();
+        println!(
+            "    queue {:?}: {} receipts, {} TGas attached, {}",
+            queue.name(),
+            stats.num,
+            stats.gas / TGAS,
+            ByteSize::b(stats.size),
+        );
+        for receipt in queue.iter().take(MAX_RECEIPTS_SHOWN) {
+            let age = round - receipt.created_at.expect("queued receipts must have been created");
+            println!(
+                "        {:?} to shard {}, {} TGas attached, {}, {age} rounds old",
+                receipt.id,
+                receipt.receiver,
+                receipt.attached_gas / TGAS,
+                ByteSize::b(receipt.size),
+            );
+        }
+        if queue.len() > MAX_RECEIPTS_SHOWN {
+            println!("        ... and {} more", queue.len() - MAX_RECEIPTS_SHOWN);
+        }
+    }
 }
 
 fn normalize_cmdline_arg(value: &str) -> String {
@@ -189,6 +759,12 @@ fn workload(workload_name: &str) -> Box<dyn Producer> {
         "Linear Imbalance" => Box::<LinearImbalanceProducer>::default(),
         "Big Linear Imbalance" => Box::new(LinearImbalanceProducer::big_receipts()),
         "Fairness Test" => Box::<FairnessBenchmarkProducer>::default(),
+        "Balanced With Reads" => {
+            // Each shard additionally executes 20 local reads of 10 TGas per round
+            Box::new(ReadTrafficProducer::new(Box::<BalancedProducer>::default(), 20, 10 * TGAS))
+        }
+        "Account Traffic" => Box::<AccountTrafficProducer>::default(),
+        "Yield Resume" => Box::<YieldResumeProducer>::default(),
         _ => panic!("unknown workload: {}", workload_name),
     }
 }
@@ -233,6 +809,10 @@ fn strategy(strategy_name: &str, num_shards: usize) -> Vec<Box<dyn CongestionStr
                     .with_gas_limits(5 * PGAS, 1 * PGAS)
                     .with_tx_reject_threshold(0.5),
             ),
+            "Adaptive Threshold" => Box::<AdaptiveThreshold>::default(),
+            "Priority Aging" => Box::<PriorityAging>::default(),
+            "Priority Gas" => Box::<PriorityGas>::default(),
+            "Global Nearly Done" => Box::<GlobalNearlyDone>::default(),
             "NEP" => Box::<NepStrategy>::default(),
             "NEP 200MB" => Box::new(
                 NepStrategy::default().with_memory_limits(ByteSize::mb(100), ByteSize::mb(100)),
@@ -320,8 +900,37 @@ fn strategy(strategy_name: &str, num_shards: usize) -> Vec<Box<dyn CongestionStr
     result
 }
 
+/// Parses a ROUND:TGAS pair into a round and a gas limit in GGas.
+fn parse_gas_limit_change(s: &str) -> Result<(u64, u64), String> {
+    let (round, tgas) =
+        s.split_once(':').ok_or_else(|| format!("expected ROUND:TGAS, got {s:?}"))?;
+    let round = round.trim().parse().map_err(|err| format!("invalid round {round:?}: {err}"))?;
+    let tgas: u64 = tgas.trim().parse().map_err(|err| format!("invalid gas {tgas:?}: {err}"))?;
+    Ok((round, tgas * TGAS))
+}
+
+/// Parses a share of the gas limit in (0, 1].
+fn parse_congestion_threshold(s: &str) -> Result<f64, String> {
+    let threshold: f64 = s.parse().map_err(|err| format!("invalid threshold {s:?}: {err}"))?;
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(format!("{threshold} is not more than 0 and at most 1"));
+    }
+    Ok(threshold)
+}
+
 fn parse_workload_names(workload_name: &str) -> Vec<String> {
-    let available: Vec<String> = vec![
+    let available = workload_names();
+    if workload_name == "all" {
+        return available;
+    }
+    match find_name(&available, workload_name) {
+        Some(name) => vec![name],
+        None => panic!("The requested workload name did not match any available workloads. Requested workload name {:?}, The available workloads are: {:?}", workload_name, available),
+    }
+}
+
+fn workload_names() -> Vec<String> {
+    vec![
         "Balanced".to_string(),
         "Increasing Size".to_string(),
         "Extreme Increasing Size".to_string(),
@@ -335,22 +944,26 @@ fn parse_workload_names(workload_name: &str) -> Vec<String> {
         "Linear Imbalance".to_string(),
         "Big Linear Imbalance".to_string(),
         "Fairness Test".to_string(),
-    ];
+        "Balanced With Reads".to_string(),
+        "Account Traffic".to_string(),
+        "Yield Resume".to_string(),
+        "Closed Loop".to_string(),
+    ]
+}
 
-    if workload_name == "all" {
+fn parse_strategy_names(strategy_name: &str) -> Vec<String> {
+    let available = strategy_names();
+    if strategy_name == "all" {
         return available;
     }
-
-    for name in &available {
-        if normalize_cmdline_arg(name.as_ref()) == normalize_cmdline_arg(workload_name) {
-            return vec![name.to_string()];
-        }
+    match find_name(&available, strategy_name) {
+        Some(name) => vec![name],
+        None => panic!("The requested strategy name did not match any available strategies. Requested strategy name {:?}, The available strategies are: {:?}", strategy_name, available),
     }
-    panic!("The requested workload name did not match any available workloads. Requested workload name {:?}, The available workloads are: {:?}", workload_name, available);
 }
 
-fn parse_strategy_names(strategy_name: &str) -> Vec<String> {
-    let available: Vec<String> = vec![
+fn strategy_names() -> Vec<String> {
+    vec![
         "No queues".to_string(),
         "Global TX stop".to_string(),
         "Simple backpressure".to_string(),
@@ -362,6 +975,10 @@ fn parse_strategy_names(strategy_name: &str) -> Vec<String> {
         "STL_HIGH_UTIL".to_string(),
         "STL_MIN_DELAY".to_string(),
         "STL_LOW_DELAY".to_string(),
+        "Adaptive Threshold".to_string(),
+        "Priority Aging".to_string(),
+        "Priority Gas".to_string(),
+        "Global Nearly Done".to_string(),
         "NEP".to_string(),
         "NEP 200MB".to_string(),
         "NEP 450/50MB".to_string(),
@@ -378,18 +995,37 @@ fn parse_strategy_names(strategy_name: &str) -> Vec<String> {
         "NEPv2 less tx".to_string(),
         "NEPv2 more tx".to_string(),
         "NEPv3".to_string(),
-    ];
+    ]
+}
 
-    if strategy_name == "all" {
-        return available;
-    }
+/// The available name matching the requested one, ignoring case and spaces.
+fn find_name(available: &[String], requested: &str) -> Option<String> {
+    let requested = normalize_cmdline_arg(requested);
+    available.iter().find(|name| normalize_cmdline_arg(name) == requested).cloned()
+}
 
-    for name in &available {
-        if normalize_cmdline_arg(name.as_ref()) == normalize_cmdline_arg(strategy_name) {
-            return vec![name.to_string()];
-        }
+/// One row per shard with the receipts in all its queues, sorted by their
+/// attached gas, descending.
+fn write_queue_dump(out: &mut dyn Write, model: &Model) -> std::io::Result<()> {
+    let queues = model.queue_lengths();
+    let max_queue_age = model.max_queue_age(model.round());
+    let mut shard_ids = model.shard_ids().to_vec();
+    shard_ids.sort_by_key(|shard_id| std::cmp::Reverse(queues[shard_id].queued_receipts.gas));
+
+    writeln!(out, "{:<8}{:>12}{:>16}{:>16}{:>12}", "SHARD", "RECEIPTS", "TGAS", "SIZE", "OLDEST")?;
+    for shard_id in shard_ids {
+        let stats = queues[&shard_id].queued_receipts;
+        writeln!(
+            out,
+            "{:<8}{:>12}{:>16}{:>16}{:>12}",
+            shard_id.to_string(),
+            stats.num,
+            stats.gas / TGAS,
+            ByteSize::b(stats.size).to_string(),
+            max_queue_age[&shard_id],
+        )?;
     }
-    panic!("The requested strategy name did not match any available strategies. Requested strategy name {:?}, The available strategies are: {:?}", strategy_name, available);
+    Ok(())
 }
 
 // for looking at more details during execution, call print_report
@@ -398,15 +1034,65 @@ fn print_report(model: &Model) {
     let queues = model.queue_lengths();
     let throughput = model.gas_throughput();
     let progress = model.progress();
+    let max_queue_age = model.max_queue_age(model.round());
+    let forwarding_gas = model.forwarding_gas();
+    let read_write_gas = model.read_write_gas();
+    let evicted = model.evicted_transactions();
+    let rejected = model.rejected_transactions();
+    let ledger = model.gas_ledger();
+    let receipt_latency = model.receipt_latency();
+    let ack_traffic = model.ack_traffic();
+    let amplification = model.amplification_histogram();
+    let refund_callbacks = model.refund_callback_load();
 
     println!("burnt {} PGas", throughput.total / PGAS,);
+    println!("{:>6} PGas attached", ledger.attached / PGAS);
+    println!("{:>6} PGas refunded", ledger.refunded / PGAS);
+    println!("{:>6} TGas burnt for sending receipts", ledger.send_burnt / TGAS);
+    println!("{:>6} TGas burnt as priority gas", ledger.priority_burnt / TGAS);
+    println!("{:>6} PGas attached to queued receipts", ledger.queued / PGAS);
+    if !ledger.is_balanced() {
+        println!("WARNING: gas ledger does not balance: {ledger:?}");
+    }
     println!("{:>6} transactions finished", progress.finished_transactions);
     println!("{:>6} transactions waiting", progress.waiting_transactions);
     println!("{:>6} transactions pending", progress.pending_transactions);
     println!("{:>6} transactions failed", progress.failed_transactions);
+    println!("{:>6.1} % transactions rejected", model.tx_rejection_rate() * 100.0);
+    println!("{:>6.1} transactions in flight on average", model.average_in_flight_transactions());
+    println!("{:>6} rounds p99 receipt latency", receipt_latency.user.percentile(99).unwrap_or(0));
+    println!("{:>6} rounds p99 refund latency", receipt_latency.refund.percentile(99).unwrap_or(0));
+    println!("{:>6} refund callbacks executed", refund_callbacks.executed);
+    println!("{:>6} TGas burnt by refund callbacks", refund_callbacks.gas_burnt / TGAS);
+    println!(
+        "{:>6} receipts executed by p99 transaction",
+        amplification.executed.percentile(99).unwrap_or(0)
+    );
+    println!(
+        "{:>6} receipts dropped by p99 transaction",
+        amplification.dropped.percentile(99).unwrap_or(0)
+    );
+    println!("{:>6} PGas capacity wasted on idle shards", model.wasted_capacity() / PGAS);
+    let witness_bound = model.chunk_utilization().iter().filter(|u| u.witness_bound()).count();
+    println!("{:>6} rounds closer to the witness limit than the gas limit", witness_bound);
+    println!("{:>6} yields waiting for resume", model.pending_yields());
+    println!("{:>6} yields timed out", model.timed_out_yields());
+    println!("{:>6} acks sent", ack_traffic.acks_sent);
+    println!("{:>6} receipts retransmitted", ack_traffic.retransmitted_receipts);
+    println!("{:>6} TGas burnt for acks and retransmissions", ack_traffic.gas / TGAS);
     for shard_id in model.shard_ids() {
         println!("SHARD {shard_id}");
+        println!(
+            "    {:>6} transactions in mempool",
+            queues[shard_id].unprocessed_incoming_transactions
+        );
+        println!("    {:>6} transactions evicted", evicted[shard_id]);
+        println!("    {:>6} transactions rejected", rejected[shard_id]);
         println!("    {:>6} receipts incoming", queues[shard_id].incoming_receipts.num);
         println!("    {:>6} receipts queued", queues[shard_id].queued_receipts.num);
+        println!("    {:>6} rounds oldest receipt age", max_queue_age[shard_id]);
+        println!("    {:>6} TGas burnt for forwarding", forwarding_gas[shard_id] / TGAS);
+        println!("    {:>6} TGas burnt by reads", read_write_gas[shard_id].read / TGAS);
+        println!("    {:>6} TGas burnt by writes", read_write_gas[shard_id].write / TGAS);
     }
 }
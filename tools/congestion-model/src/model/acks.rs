@@ -0,0 +1,194 @@
+use super::{Model, ShardId};
+use crate::{GGas, Receipt, ReceiptId, Round};
+use rand::Rng;
+use std::collections::HashSet;
+
+/// Receipts travelling between shards and their acks, see
+/// [`crate::ModelConfig::acks`].
+#[derive(Default)]
+pub(crate) struct AckState {
+    /// Copies of all receipts forwarded to other shards that have not been
+    /// acked, yet, in the order they were first sent.
+    unacked: Vec<Unacked>,
+    /// Acks to send in the current round, for the receipts that arrived at
+    /// its start, as `(receiver of the receipt, receipt)`.
+    to_send: Vec<(ShardId, ReceiptId)>,
+    /// Acks that arrive at the sender of their receipt at the start of the
+    /// next round.
+    in_flight: HashSet<ReceiptId>,
+    /// Receipts that reached their receiver at least once and wait for an
+    /// ack, to discard further copies. Acked receipts are removed, no copies
+    /// of them are on their way anymore.
+    delivered: HashSet<ReceiptId>,
+    /// Number of receipts that reached their receiver again and were
+    /// discarded.
+    pub(crate) discarded_duplicates: usize,
+}
+
+struct Unacked {
+    sender: ShardId,
+    last_sent: Round,
+    receipt: Receipt,
+}
+
+/// Work a shard does for reliable delivery in one round, before its chunk.
+#[derive(Default)]
+pub(super) struct DeliveryOverhead {
+    pub(super) gas: GGas,
+    pub(super) acks_sent: usize,
+    pub(super) retransmitted_receipts: usize,
+    /// Retransmitted receipts that were not lost again.
+    pub(super) delivered: Vec<Receipt>,
+}
+
+impl AckState {
+    /// Keep a copy of a receipt forwarded to another shard in this round.
+    pub(super) fn sent(&mut self, sender: ShardId, round: Round, receipt: &Receipt) {
+        self.unacked.push(Unacked { sender, last_sent: round, receipt: receipt.clone() });
+    }
+
+    /// Hand a receipt from another shard to its receiver, which acks it in the
+    /// next round. Returns `None` for duplicates.
+    pub(super) fn deliver(&mut self, receipt: Receipt) -> Option<Receipt> {
+        self.to_send.push((receipt.receiver, receipt.id));
+        if self.delivered.insert(receipt.id) {
+            Some(receipt)
+        } else {
+            self.discarded_duplicates += 1;
+            None
+        }
+    }
+
+    /// Number of receipts waiting for an ack and of those remembered as
+    /// delivered.
+    #[cfg(test)]
+    pub(crate) fn tracked_receipts(&self) -> (usize, usize) {
+        (self.unacked.len(), self.delivered.len())
+    }
+
+    /// Whether no receipt waits for an ack and no ack is on its way.
+    pub(super) fn is_empty(&self) -> bool {
+        self.unacked.is_empty() && self.to_send.is_empty() && self.in_flight.is_empty()
+    }
+
+    /// Receipts lost on the way that only exist as the sender's copy, waiting
+    /// for retransmission.
+    pub(crate) fn in_transit(&self) -> impl Iterator<Item = &Receipt> {
+        self.unacked
+            .iter()
+            .map(|unacked| &unacked.receipt)
+            .filter(|receipt| !self.delivered.contains(&receipt.id))
+    }
+
+    /// The sender's copies of all receipts that wait for an ack.
+    pub(super) fn copies_mut(&mut self) -> impl Iterator<Item = &mut Receipt> {
+        self.unacked.iter_mut().map(|unacked| &mut unacked.receipt)
+    }
+}
+
+impl Model {
+    /// Remove the copies of all receipts acked in the previous round.
+    ///
+    /// The last copy of such a receipt was sent no later than the one that
+    /// caused the ack, since the retransmit timeout is longer than an ack
+    /// takes. So it can be forgotten on both sides.
+    pub(super) fn receive_acks(&mut self) {
+        let acked = std::mem::take(&mut self.acks.in_flight);
+        let AckState { unacked, delivered, .. } = &mut self.acks;
+        unacked.retain(|unacked| {
+            let done = acked.contains(&unacked.receipt.id);
+            if done {
+                delivered.remove(&unacked.receipt.id);
+            }
+            !done
+        });
+    }
+
+    /// Send the acks a shard owes and retransmit its receipts that timed out.
+    pub(super) fn send_acks_and_retransmit(&mut self, shard: ShardId) -> DeliveryOverhead {
+        let mut overhead = DeliveryOverhead::default();
+        let Some(ack_config) = self.config.acks.clone() else {
+            return overhead;
+        };
+
+        let (acks, others) = std::mem::take(&mut self.acks.to_send)
+            .into_iter()
+            .partition(|&(from, _)| from == shard);
+        self.acks.to_send = others;
+        for (_, receipt_id) in acks {
+            overhead.gas += ack_config.ack_gas + self.config.forwarding_cost(ack_config.ack_size);
+            overhead.acks_sent += 1;
+            if !self.lose_receipt() {
+                self.acks.in_flight.insert(receipt_id);
+            }
+        }
+
+        for i in 0..self.acks.unacked.len() {
+            let unacked = &mut self.acks.unacked[i];
+            if unacked.sender != shard
+                || self.round - unacked.last_sent < ack_config.retransmit_timeout
+            {
+                continue;
+            }
+            unacked.last_sent = self.round;
+            let receipt = unacked.receipt.clone();
+            overhead.gas = overhead.gas.saturating_add(self.config.forwarding_cost(receipt.size));
+            overhead.retransmitted_receipts += 1;
+            if !self.lose_receipt() {
+                overhead.delivered.push(receipt);
+            }
+        }
+        overhead
+    }
+
+    /// Decide whether a message between shards gets lost, see
+    /// [`crate::ModelConfig::drop_probability`].
+    pub(super) fn lose_receipt(&mut self) -> bool {
+        // Without loss, leave the random stream untouched. This reproduces
+        // lossless executions exactly.
+        let drop_probability = self.config.drop_probability;
+        drop_probability > 0.0 && self.drop_rng.gen_bool(drop_probability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NepStrategy;
+    use crate::workload::BalancedProducer;
+    use crate::{AckConfig, GGas, Model, ModelConfig, TGAS};
+
+    fn run(drop_probability: f64) -> Model {
+        let shards = (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let acks = AckConfig { ack_size: 100, ack_gas: TGAS, retransmit_timeout: 3 };
+        let config = ModelConfig { drop_probability, acks: Some(acks), ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..50 {
+            model.step();
+            let ledger = model.gas_ledger();
+            assert!(ledger.is_balanced(), "{ledger:?}");
+        }
+        model
+    }
+
+    #[test]
+    fn acks_burn_gas_without_loss() {
+        let model = run(0.0);
+        let traffic = model.ack_traffic();
+        assert!(traffic.acks_sent > 0);
+        assert_eq!(traffic.retransmitted_receipts, 0);
+        assert_eq!(traffic.discarded_duplicates, 0);
+        assert!(traffic.gas >= traffic.acks_sent as GGas * TGAS);
+    }
+
+    #[test]
+    fn lost_receipts_and_acks_are_retransmitted() {
+        let model = run(0.2);
+        let traffic = model.ack_traffic();
+        assert!(traffic.retransmitted_receipts > 0, "{traffic:?}");
+        // a discarded duplicate means the receipt arrived before, but its ack
+        // was lost
+        assert!(traffic.discarded_duplicates > 0, "{traffic:?}");
+        assert_eq!(model.progress().failed_transactions, 0);
+        assert!(model.progress().finished_transactions > 0);
+    }
+}
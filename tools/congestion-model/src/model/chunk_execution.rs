@@ -1,40 +1,183 @@
 use super::queue_bundle::QueueBundle;
 use super::transaction_registry::TransactionRegistry;
-use super::BlockInfo;
-use crate::model::transaction::ExecutionResult;
-use crate::{GGas, Queue, QueueId, Receipt, Round, ShardId, TransactionId, GAS_LIMIT};
+use super::{BlockInfo, ModelConfig, ReceiptCapPolicy};
+use crate::model::transaction::{ExecutionResult, RejectionReason};
+use crate::{
+    GGas, Queue, QueueId, Receipt, ReceiptId, ReceiptPriority, Round, ShardId, TransactionId,
+    GAS_LIMIT, TX_GAS_LIMIT,
+};
 use std::collections::{BTreeMap, VecDeque};
 
 /// Transient struct created once for each shard per model execution round,
 /// containing all data required for chunk execution.
 pub struct ChunkExecutionContext<'model> {
+    config: &'model ModelConfig,
     queues: &'model mut QueueBundle,
     transactions: &'model mut TransactionRegistry,
     prev_block_info: &'model BTreeMap<ShardId, BlockInfo>,
 
     round: Round,
     shard: ShardId,
+    gas_limit: GGas,
     gas_burnt: GGas,
+    max_receipt_gas: GGas,
+    mempool_depth: usize,
+    rejected_transactions: usize,
+    rejection_gas: GGas,
+    read_gas_burnt: GGas,
+    forwarding_gas: GGas,
+    send_gas: GGas,
+    account_switches: usize,
+    account_switch_gas: GGas,
+    witness_size: u64,
+    /// Account whose state the last executed receipt loaded.
+    loaded_account: Option<String>,
+    forwarded_receipts: BTreeMap<ShardId, usize>,
     outgoing_receipts: Vec<Receipt>,
+    completed_transactions: Vec<TransactionId>,
+    yielded_receipts: Vec<ReceiptId>,
+    /// Receipts not executed because the witness was full, in the order they
+    /// were passed to `execute_receipt`.
+    deferred_receipts: Vec<Receipt>,
     block_info_output: BlockInfo,
 }
 
+/// Statistics about one executed chunk, recorded by the model for evaluation.
+#[derive(Clone, Debug)]
+pub struct ChunkStats {
+    pub round: Round,
+    pub shard: ShardId,
+    /// Gas limit the chunk was executed with.
+    pub gas_limit: GGas,
+    /// Total gas burnt in the chunk, including transaction conversion.
+    pub gas_burnt: GGas,
+    /// Gas burnt by the most expensive receipt executed in the chunk.
+    pub max_receipt_gas: GGas,
+    /// Gas attached to the receipts in all queues of the shard after the
+    /// chunk executed.
+    pub queued_gas: GGas,
+    /// Number of transactions in the mempool when the chunk started.
+    pub mempool_depth: usize,
+    /// Number of transactions evicted from the mempool in this round.
+    pub evicted_transactions: usize,
+    /// Number of transactions the shard rejected before conversion, see
+    /// [`ModelConfig::max_tx_size`].
+    pub rejected_transactions: usize,
+    /// Gas burnt for rejecting transactions, see
+    /// [`ModelConfig::rejected_tx_cost_fraction`]. Included in `gas_burnt`.
+    pub rejection_gas: GGas,
+    /// Gas burnt by read-only receipts. Included in `gas_burnt`.
+    pub read_gas_burnt: GGas,
+    /// Gas burnt for forwarding receipts to other shards. Included in
+    /// `gas_burnt`.
+    pub forwarding_gas: GGas,
+    /// Gas burnt for the send costs of receipts forwarded to other shards, see
+    /// [`Receipt::send_cost`]. Included in `gas_burnt`, not in
+    /// `forwarding_gas`.
+    pub send_gas: GGas,
+    /// Number of executed receipts that loaded another account than the
+    /// previous one, see [`ModelConfig::account_switch_gas`].
+    pub account_switches: usize,
+    /// Gas burnt for account switches. Included in `gas_burnt`.
+    pub account_switch_gas: GGas,
+    /// Sum of the witness costs of the executed receipts, see
+    /// [`ModelConfig::max_witness_per_chunk`].
+    pub witness_size: u64,
+    /// Number of acks sent, see [`ModelConfig::acks`].
+    pub acks_sent: usize,
+    /// Number of receipts sent again because their ack did not arrive in time.
+    pub retransmitted_receipts: usize,
+    /// Gas burnt for acks and retransmissions. Included in `forwarding_gas`.
+    pub ack_gas: GGas,
+    /// Number of yielded receipts of the shard waiting for resume or timeout
+    /// after the chunk, see [`ModelConfig::yield_resume`]. They do not use
+    /// any gas while they wait.
+    pub pending_yields: usize,
+    /// Number of yielded receipts of the shard that timed out in this round.
+    pub timed_out_yields: usize,
+    /// Number of receipts forwarded in the chunk, per receiver. Includes
+    /// receipts sent to the own shard.
+    pub forwarded_receipts: BTreeMap<ShardId, usize>,
+}
+
+impl ChunkStats {
+    /// Statistics of a chunk in a round skipped by [`super::Model::run`].
+    pub(crate) fn idle(round: Round, shard: ShardId, gas_limit: GGas) -> Self {
+        ChunkStats {
+            round,
+            shard,
+            gas_limit,
+            gas_burnt: 0,
+            max_receipt_gas: 0,
+            queued_gas: 0,
+            mempool_depth: 0,
+            evicted_transactions: 0,
+            rejected_transactions: 0,
+            rejection_gas: 0,
+            read_gas_burnt: 0,
+            forwarding_gas: 0,
+            send_gas: 0,
+            account_switches: 0,
+            account_switch_gas: 0,
+            witness_size: 0,
+            acks_sent: 0,
+            retransmitted_receipts: 0,
+            ack_gas: 0,
+            pending_yields: 0,
+            timed_out_yields: 0,
+            forwarded_receipts: BTreeMap::new(),
+        }
+    }
+}
+
+/// Everything a chunk execution produces for the model.
+pub(crate) struct ChunkOutput {
+    pub(crate) forwarded_receipts: Vec<Receipt>,
+    pub(crate) block_info: BlockInfo,
+    pub(crate) stats: ChunkStats,
+    /// Transactions whose last pending receipt was executed or dropped in
+    /// this chunk.
+    pub(crate) completed_transactions: Vec<TransactionId>,
+    /// Receipts that yielded in this chunk.
+    pub(crate) yielded_receipts: Vec<ReceiptId>,
+}
+
 impl<'model> ChunkExecutionContext<'model> {
     pub(super) fn new(
+        config: &'model ModelConfig,
         queues: &'model mut QueueBundle,
         transactions: &'model mut TransactionRegistry,
         prev_block_info: &'model BTreeMap<ShardId, BlockInfo>,
         round: Round,
         shard_id: ShardId,
+        gas_limit: GGas,
     ) -> Self {
+        let mempool_depth = queues.incoming_transactions(shard_id).len();
         ChunkExecutionContext {
+            config,
             queues,
             transactions,
             prev_block_info,
             round,
             shard: shard_id,
+            gas_limit,
             gas_burnt: 0,
+            max_receipt_gas: 0,
+            mempool_depth,
+            rejected_transactions: 0,
+            rejection_gas: 0,
+            read_gas_burnt: 0,
+            forwarding_gas: 0,
+            send_gas: 0,
+            account_switches: 0,
+            account_switch_gas: 0,
+            witness_size: 0,
+            loaded_account: None,
+            forwarded_receipts: BTreeMap::new(),
             outgoing_receipts: vec![],
+            completed_transactions: vec![],
+            yielded_receipts: vec![],
+            deferred_receipts: vec![],
             block_info_output: BlockInfo::default(),
         }
     }
@@ -68,13 +211,46 @@ impl<'model> ChunkExecutionContext<'model> {
         self.gas_burnt
     }
 
+    /// Maximum gas that can be burnt in this chunk. Defaults to
+    /// [`crate::GAS_LIMIT`] but can change over time, see
+    /// [`ModelConfig::gas_limit_schedule`] and
+    /// [`ModelConfig::gas_limit_jitter`].
+    pub fn gas_limit(&self) -> GGas {
+        self.gas_limit
+    }
+
+    /// Bytes of state witness of the receipts executed so far.
+    pub fn witness_size(&self) -> u64 {
+        self.witness_size
+    }
+
+    /// Whether the executed receipts reached
+    /// [`ModelConfig::max_witness_per_chunk`]. Transactions can still be
+    /// converted, they are not part of the receipt witness.
+    pub fn witness_full(&self) -> bool {
+        self.config.max_witness_per_chunk.is_some_and(|max| self.witness_size >= max)
+    }
+
+    /// Whether both gas and witness leave room for another receipt.
+    /// Strategies should check this rather than the gas limit alone before
+    /// calling [`ChunkExecutionContext::execute_receipt`].
+    pub fn can_execute_receipt(&self) -> bool {
+        self.gas_burnt < self.gas_limit && !self.witness_full()
+    }
+
+    /// The share of [`ChunkExecutionContext::gas_limit`] that
+    /// [`crate::TX_GAS_LIMIT`] reserves for new transactions.
+    pub fn tx_gas_limit(&self) -> GGas {
+        (self.gas_limit as u128 * TX_GAS_LIMIT as u128 / GAS_LIMIT as u128) as GGas
+    }
+
     /// Accept a transaction and convert it to a receipt.
     pub fn accept_transaction(&mut self, tx: TransactionId) -> Receipt {
         // note: Check the total gas limit, not the TX gas limit because we want
         // to allow changes to how the chunk space is split between transactions
         // and receipts.
         assert!(
-            self.gas_burnt < GAS_LIMIT,
+            self.gas_burnt < self.gas_limit,
             "trying to accept more transactions than gas limit allows",
         );
         let ExecutionResult { gas_burnt, mut new_receipts } =
@@ -82,35 +258,214 @@ impl<'model> ChunkExecutionContext<'model> {
         assert_eq!(new_receipts.len(), 1, "transaction should result in exactly one receipt");
 
         self.gas_burnt += gas_burnt;
+        tracing::trace!(target: "model", ?tx, gas_burnt, "transaction converted");
         new_receipts.pop().unwrap()
     }
 
+    /// Execute a receipt and return the receipts it produced.
+    ///
+    /// If the witness is already full, the receipt is not executed and
+    /// nothing is returned. It goes back to the front of the incoming
+    /// receipts when the chunk finishes, to be executed in a later chunk.
     pub fn execute_receipt(&mut self, receipt: Receipt) -> Vec<Receipt> {
         assert!(
-            self.gas_burnt < GAS_LIMIT,
+            self.gas_burnt < self.gas_limit,
             "trying to execute more than receipts than the gas limit allows",
         );
+        if self.witness_full() {
+            tracing::trace!(target: "model", receipt = ?receipt.id, "witness full, receipt deferred");
+            self.deferred_receipts.push(receipt);
+            return vec![];
+        }
         let tx = receipt.transaction_id();
+        let id = receipt.id;
+        let read_only = receipt.read_only;
+        self.load_account(receipt.receiver_account.as_ref());
+        self.witness_size += receipt.witness_cost;
+        if self.transactions[tx].yields(id) {
+            self.yielded_receipts.push(id);
+        }
         let ExecutionResult { gas_burnt, new_receipts } =
             self.transactions[tx].execute_receipt(receipt, self.round);
+        tracing::trace!(
+            target: "model",
+            receipt = ?id,
+            gas_burnt,
+            new_receipts = new_receipts.len(),
+            "receipt executed"
+        );
 
         self.gas_burnt += gas_burnt;
+        self.max_receipt_gas = self.max_receipt_gas.max(gas_burnt);
+        if read_only {
+            self.read_gas_burnt += gas_burnt;
+        }
+        // new receipts are already pending, only a leaf can complete the tx
+        self.check_completed(tx);
         new_receipts
     }
 
+    /// Charge [`ModelConfig::account_switch_gas`] if the account is not
+    /// loaded already.
+    fn load_account(&mut self, account: Option<&String>) {
+        let Some(account) = account else {
+            return;
+        };
+        if self.loaded_account.as_ref() == Some(account) {
+            return;
+        }
+        self.loaded_account = Some(account.clone());
+        self.account_switches += 1;
+        self.gas_burnt += self.config.account_switch_gas;
+        self.account_switch_gas += self.config.account_switch_gas;
+    }
+
     pub fn drop_receipt(&mut self, receipt: Receipt) {
         let tx = receipt.transaction_id();
+        tracing::trace!(target: "model", receipt = ?receipt.id, "receipt dropped");
         self.transactions[tx].drop_receipt(receipt, self.round);
+        self.check_completed(tx);
     }
 
-    pub fn forward_receipt(&mut self, receipt: Receipt) {
+    /// A transaction completes when its last pending receipt is resolved.
+    /// This can only happen once, no receipts are created after that.
+    fn check_completed(&mut self, tx: TransactionId) {
+        if !self.transactions[tx].is_in_flight() {
+            self.completed_transactions.push(tx);
+        }
+    }
+
+    /// Send a receipt to its receiver, to be available there in the next
+    /// round.
+    ///
+    /// Forwarding to another shard burns gas on this shard, as configured in
+    /// [`ModelConfig::forwarding_cost`], plus the [`Receipt::send_cost`] the
+    /// first time.
+    pub fn forward_receipt(&mut self, mut receipt: Receipt) {
+        assert!(
+            !receipt.read_only || receipt.receiver == self.shard,
+            "read-only receipts must not be forwarded to other shards"
+        );
+        if receipt.receiver != self.shard {
+            let gas = self.config.forwarding_cost(receipt.size);
+            self.gas_burnt = self.gas_burnt.saturating_add(gas);
+            self.forwarding_gas = self.forwarding_gas.saturating_add(gas);
+            let send_cost = receipt.unpaid_send_cost();
+            self.gas_burnt += send_cost;
+            self.send_gas += send_cost;
+            self.transactions[receipt.transaction_id()].send_gas_burnt += send_cost;
+            receipt.send_cost_paid = true;
+        }
+        *self.forwarded_receipts.entry(receipt.receiver).or_default() += 1;
         self.outgoing_receipts.push(receipt);
     }
 
+    /// Burn gas the model spends on forwarding outside of the strategy, like
+    /// acks and retransmissions.
+    pub(super) fn burn_forwarding_gas(&mut self, gas: GGas) {
+        self.gas_burnt += gas;
+        self.forwarding_gas += gas;
+    }
+
+    /// Reject all transactions in the mempool that are larger than
+    /// [`ModelConfig::max_tx_size`], or have more receipts than
+    /// [`ModelConfig::max_outstanding_receipts_per_tx`] allows, before the
+    /// strategy sees them. They complete without creating a receipt, see
+    /// [`crate::Rejection`].
+    pub(super) fn reject_invalid_transactions(&mut self) {
+        let config = self.config;
+        let max_receipts = match config.receipt_cap_policy {
+            ReceiptCapPolicy::Reject => config.max_outstanding_receipts_per_tx,
+            // already enforced when building
+            ReceiptCapPolicy::Truncate => None,
+        };
+        if config.max_tx_size.is_none() && max_receipts.is_none() {
+            return;
+        }
+        let transactions = &*self.transactions;
+        let reason = |tx: TransactionId| {
+            let tx = &transactions[tx];
+            // nothing has been created, yet, all receipts are future receipts
+            let receipts = tx.future_receipts.len();
+            match (config.max_tx_size, max_receipts) {
+                (Some(max_tx_size), _) if tx.size > max_tx_size => {
+                    Some(RejectionReason::TooLarge { size: tx.size, max_tx_size })
+                }
+                (_, Some(max_receipts)) if receipts > max_receipts => {
+                    Some(RejectionReason::TooManyReceipts { receipts, max_receipts })
+                }
+                _ => None,
+            }
+        };
+        let mempool = self.queues.incoming_transactions_mut(self.shard);
+        let mut rejected = vec![];
+        mempool.retain(|&tx| match reason(tx) {
+            Some(reason) => {
+                rejected.push((tx, reason));
+                false
+            }
+            None => true,
+        });
+        for (tx, reason) in rejected {
+            let gas_burnt =
+                self.transactions[tx].reject(self.round, reason, config.rejected_tx_cost_fraction);
+            tracing::trace!(target: "model", ?tx, ?reason, gas_burnt, "transaction rejected");
+            self.gas_burnt += gas_burnt;
+            self.rejection_gas += gas_burnt;
+            self.rejected_transactions += 1;
+            self.completed_transactions.push(tx);
+        }
+    }
+
+    /// Regroup the mailbox by account if [`ModelConfig::account_grouping`] is
+    /// set, before the strategy sees it.
+    pub(super) fn group_incoming_receipts(&mut self) {
+        if let Some(grouping) = &self.config.account_grouping {
+            grouping.regroup(self.queues.incoming_receipts_mut(self.shard));
+        }
+    }
+
     /// Finalize the chunk execution and return the output to the model to
     /// integrate with the global execution context.
-    pub(crate) fn finish(self) -> (Vec<Receipt>, BlockInfo) {
-        (self.outgoing_receipts, self.block_info_output)
+    pub(crate) fn finish(mut self) -> ChunkOutput {
+        let incoming = self.queues.incoming_receipts_mut(self.shard);
+        for receipt in self.deferred_receipts.drain(..).rev() {
+            incoming.push_front(receipt);
+        }
+        let stats = ChunkStats {
+            round: self.round,
+            shard: self.shard,
+            gas_limit: self.gas_limit,
+            gas_burnt: self.gas_burnt,
+            max_receipt_gas: self.max_receipt_gas,
+            queued_gas: self.queues.shard_queues(self.shard).map(|q| q.attached_gas()).sum(),
+            mempool_depth: self.mempool_depth,
+            // set by the model, which evicts before the chunk starts
+            evicted_transactions: 0,
+            rejected_transactions: self.rejected_transactions,
+            rejection_gas: self.rejection_gas,
+            read_gas_burnt: self.read_gas_burnt,
+            forwarding_gas: self.forwarding_gas,
+            send_gas: self.send_gas,
+            account_switches: self.account_switches,
+            account_switch_gas: self.account_switch_gas,
+            witness_size: self.witness_size,
+            // set by the model, which handles acks before the chunk starts
+            acks_sent: 0,
+            retransmitted_receipts: 0,
+            ack_gas: 0,
+            // set by the model, which tracks yields across chunks
+            pending_yields: 0,
+            timed_out_yields: 0,
+            forwarded_receipts: self.forwarded_receipts,
+        };
+        ChunkOutput {
+            forwarded_receipts: self.outgoing_receipts,
+            block_info: self.block_info_output,
+            stats,
+            completed_transactions: self.completed_transactions,
+            yielded_receipts: self.yielded_receipts,
+        }
     }
 
     /// A sequence of increasing numbers.
@@ -135,4 +490,15 @@ impl<'model> ChunkExecutionContext<'model> {
     pub fn tx_receiver(&self, id: TransactionId) -> ShardId {
         self.transactions[id].initial_receipt_receiver()
     }
+
+    /// Priority the transaction was submitted with.
+    pub fn tx_priority(&self, id: TransactionId) -> ReceiptPriority {
+        self.transactions[id].priority
+    }
+
+    /// Priority gas attached to each receipt of the transaction, see
+    /// [`crate::TransactionBuilder::with_priority_gas`].
+    pub fn tx_priority_gas(&self, id: TransactionId) -> GGas {
+        self.transactions[id].priority_gas
+    }
 }
@@ -0,0 +1,386 @@
+use super::AccountGroupedQueue;
+use crate::{GGas, Round, ShardId, TransactionBuilder, GAS_LIMIT};
+
+/// Protocol parameters of a model execution that are independent of the
+/// congestion strategy.
+///
+/// The default reproduces the original model behavior.
+#[derive(Clone, Debug, Default)]
+pub struct ModelConfig {
+    /// Gas burnt by the sending shard for each receipt forwarded to another
+    /// shard.
+    ///
+    /// Forwarding occupies chunk space in the real protocol. Charging gas for
+    /// it couples forwarding capacity to the chunk gas budget.
+    pub forward_gas: GGas,
+    /// Gas burnt by the sending shard for each byte of a receipt forwarded to
+    /// another shard. Charged in addition to `forward_gas`.
+    pub forward_gas_per_byte: GGas,
+    /// Changes of the chunk gas limit of all shards, as `(round, gas_limit)`.
+    ///
+    /// Each entry takes effect at the start of its round and stays in effect
+    /// until a later entry replaces it. Before the first entry, shards use
+    /// [`GAS_LIMIT`].
+    pub gas_limit_schedule: Vec<(Round, GGas)>,
+    /// Total gas all chunks of a round may burn together. `None` means
+    /// unlimited.
+    ///
+    /// Shards execute one after another in `shard_order`. Each chunk's gas
+    /// limit is cut to what earlier chunks of the round left over, so shards
+    /// early in the order get served first.
+    pub global_gas_limit: Option<GGas>,
+    /// Order in which the shards execute their chunks within a round.
+    ///
+    /// Only matters for resources the shards share within a round, like
+    /// `global_gas_limit`, lost receipts and the order of receipts from
+    /// different senders in a mailbox. Chunk statistics are recorded by shard
+    /// either way.
+    pub shard_order: ShardOrder,
+    /// Changes of the chunk gas limit of individual shards, as
+    /// `(round, shard, gas_limit)`.
+    ///
+    /// Works like `gas_limit_schedule`. When both schedules change a shard's
+    /// limit in the same round, the per-shard entry wins.
+    pub shard_gas_limit_schedule: Vec<(Round, ShardId, GGas)>,
+    /// Validator groups that take turns producing the chunks of each shard.
+    /// `None` means all chunk producers perform alike.
+    pub epoch_schedule: Option<EpochSchedule>,
+    /// Maximum size in bytes of a single receipt, enforced on all transactions
+    /// built by producers. `None` means unlimited.
+    pub max_receipt_size: Option<u64>,
+    /// Maximum declared size in bytes of a transaction, see
+    /// [`crate::TransactionBuilder::with_size`]. `None` means unlimited.
+    ///
+    /// Unlike `max_receipt_size`, producers may exceed it. The sender shard
+    /// rejects larger transactions when its chunk starts, before the strategy
+    /// sees them, and never creates their first receipt. See
+    /// [`crate::Rejection`].
+    pub max_tx_size: Option<u64>,
+    /// Maximum state witness in bytes of a chunk, the sum of the
+    /// [`crate::Receipt::witness_cost`] of its executed receipts. `None` means
+    /// unlimited.
+    ///
+    /// Works like the gas limit, independent of it: the chunk includes
+    /// receipts until their witness reaches the limit, the receipt crossing
+    /// it included. After that, no more receipts fit, see
+    /// [`super::ChunkExecutionContext::can_execute_receipt`]. Receipts with a
+    /// high witness cost but little gas make it the binding limit. Converting
+    /// transactions adds nothing to the witness and continues.
+    pub max_witness_per_chunk: Option<u64>,
+    /// Share of the conversion cost, in `[0, 1]`, that the sender shard burns
+    /// for checking a transaction it rejects. It counts against the chunk's
+    /// gas limit. 0 burns nothing.
+    pub rejected_tx_cost_fraction: f64,
+    /// Maximum number of receipts in the DAG of a transaction, including the
+    /// first receipt, refunds and the timeout receipts of yields. `None`
+    /// means unlimited.
+    ///
+    /// Limits how much work a single transaction can fan out into. What
+    /// happens to transactions with more receipts depends on
+    /// `receipt_cap_policy`.
+    pub max_outstanding_receipts_per_tx: Option<usize>,
+    /// What to do with transactions above `max_outstanding_receipts_per_tx`.
+    pub receipt_cap_policy: ReceiptCapPolicy,
+    /// Gas burnt by a shard each time it executes a receipt for another
+    /// account than the previous receipt of the chunk, including the first
+    /// one, see [`crate::Receipt::receiver_account`].
+    ///
+    /// Models loading the state of an account before its receipt can execute.
+    /// Receipts without an account neither pay nor change the loaded account.
+    /// Counts against the chunk's gas limit but is not charged to the
+    /// transaction. 0 makes the order of accounts free.
+    pub account_switch_gas: GGas,
+    /// Batch the receipts in each shard's mailbox by account before the
+    /// strategy runs. `None` keeps them in arrival order.
+    pub account_grouping: Option<AccountGroupedQueue>,
+    /// Probability for each receipt forwarded to another shard to be lost on
+    /// the way, regardless of congestion. Models network loss or censorship.
+    ///
+    /// Lost receipts are dropped like receipts dropped by a shard. The
+    /// decisions are drawn from their own random stream, derived from the
+    /// model seed, so they do not change the workload.
+    pub drop_probability: f64,
+    /// Relative variance of each chunk's gas limit, in `[0, 1)`.
+    ///
+    /// Each round, the gas limit of each shard from the schedules is multiplied
+    /// by a factor drawn uniformly from `[1 - jitter, 1 + jitter)`. Every shard
+    /// draws from its own random stream, derived from the model seed. The
+    /// draws are therefore reproducible and independent of the workload and
+    /// of other shards. 0 disables jitter.
+    pub gas_limit_jitter: f64,
+    /// Maximum number of transactions waiting for conversion on each shard.
+    ///
+    /// New transactions enter the mempool at the start of each round. If that
+    /// exceeds the capacity, transactions are evicted according to
+    /// `mempool_eviction` before any chunk executes. Evicted transactions never
+    /// convert and count as failed. `None` means unlimited.
+    pub mempool_capacity: Option<usize>,
+    /// Which transaction to evict from a full mempool.
+    pub mempool_eviction: MempoolEviction,
+    /// Acknowledge receipts forwarded to other shards and retransmit them
+    /// until acknowledged. `None` disables acks, lost receipts are dropped.
+    pub acks: Option<AckConfig>,
+    /// When yielded receipts resume or time out, see
+    /// [`crate::TransactionBuilder::mark_yield`]. Only matters for workloads
+    /// with yields.
+    pub yield_resume: YieldConfig,
+    /// Share of its gas limit a shard must burn in a round to count as
+    /// congested, for [`crate::Model::idle_rounds`]. Only used for
+    /// evaluation. `None` means 0.9.
+    pub congestion_threshold: Option<f64>,
+    /// Apply each chunk one round after it was produced, like nearcore does.
+    ///
+    /// A chunk produced in round R includes the receipts sent to its shard in
+    /// round R - 1, but they only execute when the chunk is applied in round
+    /// R + 1. Every hop between receipts, and the hop from conversion to the
+    /// first receipt, therefore takes two rounds instead of one. This pipeline
+    /// delay adds to any queueing or forwarding delay. With acks, the
+    /// retransmit timeout must be at least 3 rounds.
+    ///
+    /// `false` keeps the original model behavior, where a receipt executes in
+    /// the round after it was sent.
+    pub chunk_apply_lag: bool,
+    /// Remove each transaction from the model at the end of the round in
+    /// which it completes, after the completion callbacks ran.
+    ///
+    /// Long runs keep millions of transactions with all their receipts
+    /// otherwise. The aggregate metrics, like [`crate::Model::progress`],
+    /// [`crate::Model::latency_stats`] or [`crate::Model::gas_ledger`],
+    /// collect what they need before the transaction is removed and report
+    /// the same results either way. Views of pruned transactions are gone,
+    /// [`crate::Model::transaction_views`] skips them and
+    /// [`crate::Model::transaction_view`] panics.
+    ///
+    /// Chunk statistics are only kept for the last
+    /// [`crate::CHUNK_STATS_RETENTION`] rounds. Totals over them, like
+    /// [`crate::Model::forwarding_gas`], stay the same as well.
+    pub prune_completed: bool,
+}
+
+/// Assignment of chunk producers to shards, see
+/// [`ModelConfig::epoch_schedule`].
+///
+/// Validators are split into groups, and each group produces the chunks of
+/// one or more shards for an epoch. At each epoch boundary, the assignment
+/// rotates: in epoch `e`, shard `i` is produced by group `(i + e) % groups`.
+/// A group's performance scales the gas limit from the gas limit schedules,
+/// so slow groups reduce the capacity of the shards they produce. The first
+/// epoch starts in round 1.
+#[derive(Clone, Debug)]
+pub struct EpochSchedule {
+    /// Rounds per epoch, at least 1.
+    pub epoch_length: Round,
+    /// Factor on the gas limit for each validator group, positive. There must
+    /// be at least one group.
+    pub group_performance: Vec<f64>,
+}
+
+/// Reliable delivery of receipts between shards, see [`ModelConfig::acks`].
+///
+/// The sender keeps a copy of each receipt it forwards to another shard. The
+/// receiver sends an ack back in the round after the receipt arrives, and the
+/// ack reaches the sender one round later. Without an ack, the sender forwards
+/// its copy again once `retransmit_timeout` rounds have passed since the last
+/// attempt. Receivers discard duplicates, but ack them again.
+///
+/// Acks and retransmissions burn forwarding gas, see
+/// [`ModelConfig::forwarding_cost`], and acks also burn `ack_gas` on the
+/// receiver. Both are charged before the strategy runs and reduce the gas left
+/// for its chunk. Acks and retransmitted receipts get lost with
+/// [`ModelConfig::drop_probability`], like all other cross-shard receipts.
+#[derive(Clone, Debug)]
+pub struct AckConfig {
+    /// Size in bytes of an ack.
+    pub ack_size: u64,
+    /// Gas the receiver burns for creating an ack, on top of forwarding it.
+    pub ack_gas: GGas,
+    /// Rounds without ack after which a receipt is sent again. Must be at
+    /// least 2, the time for a receipt and its ack to travel.
+    pub retransmit_timeout: Round,
+}
+
+/// Resume and timeout of yielded receipts, see [`ModelConfig::yield_resume`].
+///
+/// A yielded receipt waits outside of all queues, for an external resume that
+/// the model does not simulate. Instead, each round after the yield, the resume
+/// arrives with `resume_probability`, so waiting times are geometrically
+/// distributed. Without a resume, the receipt times out `timeout` rounds after
+/// it yielded. The draws come from their own random stream, derived from the
+/// model seed, so they do not change the workload.
+#[derive(Clone, Debug)]
+pub struct YieldConfig {
+    /// Probability for a waiting receipt to be resumed in each round.
+    pub resume_probability: f64,
+    /// Rounds after which a yielded receipt without resume times out. Must be
+    /// at least 1.
+    pub timeout: Round,
+}
+
+impl Default for YieldConfig {
+    fn default() -> Self {
+        // nearcore times out yields after 200 blocks
+        Self { resume_probability: 0.1, timeout: 200 }
+    }
+}
+
+/// Choice of the transaction to evict from a full mempool, see
+/// [`ModelConfig::mempool_capacity`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MempoolEviction {
+    /// Evict the transaction that waited the longest.
+    #[default]
+    Oldest,
+    /// Evict the transaction with the lowest fee, see
+    /// [`crate::TransactionBuilder::with_fee`]. Among equal fees, the oldest
+    /// is evicted.
+    LowestFee,
+}
+
+/// Order of the chunks within a round, see [`ModelConfig::shard_order`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ShardOrder {
+    /// Shard 0 first.
+    #[default]
+    Ascending,
+    /// The last shard first.
+    Descending,
+    /// Ascending, but round `r` starts with shard `(r - 1) % shards` and wraps
+    /// around, so each shard goes first equally often.
+    Rotating,
+    /// The same order of shard indices every round. Must be a permutation of
+    /// all shards.
+    Fixed(Vec<usize>),
+}
+
+impl ShardOrder {
+    /// Indices of the shards in the order they execute in `round`.
+    pub fn order(&self, round: Round, num_shards: usize) -> Vec<usize> {
+        match self {
+            ShardOrder::Ascending => (0..num_shards).collect(),
+            ShardOrder::Descending => (0..num_shards).rev().collect(),
+            ShardOrder::Rotating => {
+                let first = round.saturating_sub(1) as usize % num_shards.max(1);
+                (0..num_shards).map(|i| (first + i) % num_shards).collect()
+            }
+            ShardOrder::Fixed(order) => order.clone(),
+        }
+    }
+
+    /// Whether the order covers each of `num_shards` shards exactly once.
+    pub(crate) fn is_valid(&self, num_shards: usize) -> bool {
+        match self {
+            ShardOrder::Fixed(order) => {
+                let mut sorted = order.clone();
+                sorted.sort_unstable();
+                sorted.into_iter().eq(0..num_shards)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Parses "ascending", "descending", "rotating" or a comma separated
+/// permutation of shard indices, like "2,0,1".
+impl std::str::FromStr for ShardOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascending" => Ok(ShardOrder::Ascending),
+            "descending" => Ok(ShardOrder::Descending),
+            "rotating" => Ok(ShardOrder::Rotating),
+            _ => s
+                .split(',')
+                .map(|index| index.trim().parse())
+                .collect::<Result<_, _>>()
+                .map(ShardOrder::Fixed)
+                .map_err(|_| {
+                    format!(
+                        "unknown shard order {s:?}, expected ascending, descending, rotating \
+                        or a comma separated list of shard indices"
+                    )
+                }),
+        }
+    }
+}
+
+/// Treatment of transactions with too many receipts, see
+/// [`ModelConfig::max_outstanding_receipts_per_tx`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReceiptCapPolicy {
+    /// The sender shard rejects the transaction before conversion, like an
+    /// oversized one, see [`crate::RejectionReason::TooManyReceipts`].
+    #[default]
+    Reject,
+    /// The DAG is cut down to the allowed number of receipts when the
+    /// transaction is built, see
+    /// [`crate::TransactionBuilder::with_max_receipts`]. The transaction
+    /// executes what is left.
+    Truncate,
+}
+
+impl ModelConfig {
+    /// Gas the sender burns for forwarding a receipt of the given size.
+    ///
+    /// Saturates at `GGas::MAX` for huge receipts, like the ones
+    /// [`crate::workload::WhaleProducer`] creates.
+    pub fn forwarding_cost(&self, receipt_size: u64) -> GGas {
+        self.forward_gas.saturating_add(self.forward_gas_per_byte.saturating_mul(receipt_size))
+    }
+
+    /// Apply the limits that builders enforce to a builder handed out by the
+    /// model.
+    pub(super) fn limit_builder(&self, mut builder: TransactionBuilder) -> TransactionBuilder {
+        if let Some(max) = self.max_receipt_size {
+            builder = builder.with_max_receipt_size(max);
+        }
+        if let (Some(max), ReceiptCapPolicy::Truncate) =
+            (self.max_outstanding_receipts_per_tx, self.receipt_cap_policy)
+        {
+            builder = builder.with_max_receipts(max);
+        }
+        builder
+    }
+
+    /// Chunk gas limit of a shard in the given round, according to the gas
+    /// limit schedules and the performance of its chunk producers.
+    pub fn gas_limit(&self, shard: ShardId, round: Round) -> GGas {
+        let scheduled = self.scheduled_gas_limit(shard, round);
+        match &self.epoch_schedule {
+            Some(epochs) => {
+                let performance = epochs.group_performance[epochs.group(shard, round)];
+                (scheduled as f64 * performance) as GGas
+            }
+            None => scheduled,
+        }
+    }
+
+    fn scheduled_gas_limit(&self, shard: ShardId, round: Round) -> GGas {
+        let global = self.gas_limit_schedule.iter().map(|&(r, gas)| ((r, false), gas));
+        let per_shard = self
+            .shard_gas_limit_schedule
+            .iter()
+            .filter(|&&(_, s, _)| s == shard)
+            .map(|&(r, _, gas)| ((r, true), gas));
+        // Among the entries already in effect, the latest wins. Ties go to the
+        // per-shard entry, then to the entry listed last.
+        global
+            .chain(per_shard)
+            .filter(|&((r, _), _)| r <= round)
+            .max_by_key(|&(key, _)| key)
+            .map_or(GAS_LIMIT, |(_, gas)| gas)
+    }
+}
+
+impl EpochSchedule {
+    /// Epoch of the given round, starting with epoch 0 in round 1.
+    pub fn epoch(&self, round: Round) -> u64 {
+        round.saturating_sub(1) / self.epoch_length
+    }
+
+    /// Validator group that produces the shard's chunk in the given round.
+    pub fn group(&self, shard: ShardId, round: Round) -> usize {
+        let groups = self.group_performance.len() as u64;
+        ((shard.0 as u64 + self.epoch(round)) % groups) as usize
+    }
+}
@@ -1,23 +1,40 @@
+mod account_grouping;
+mod acks;
 mod block_info;
 mod chunk_execution;
+mod config;
 mod queue;
 mod queue_bundle;
+mod resharding;
+mod round_policy;
 mod transaction;
 mod transaction_registry;
+mod yield_resume;
 
+pub use account_grouping::AccountGroupedQueue;
 pub use block_info::BlockInfo;
 pub use chunk_execution::*;
+pub use config::{
+    AckConfig, EpochSchedule, MempoolEviction, ModelConfig, ReceiptCapPolicy, ShardOrder,
+    YieldConfig,
+};
 pub use queue::*;
 pub use queue_bundle::*;
-pub use transaction::Receipt;
+pub use round_policy::{EventDriven, FixedStep, ModelState, RoundPolicy};
+pub use transaction::{ExecutionMode, Receipt, ReceiptPriority, Rejection, RejectionReason};
 pub use transaction_registry::TransactionId;
 
 pub(crate) use transaction::Transaction;
 
+use crate::evaluation::{RetiredChunks, RetiredTransactions};
 use crate::workload::Producer;
-use crate::{CongestionStrategy, Round};
+use crate::{CongestionStrategy, GGas, Round, TransactionBuilder};
+use acks::AckState;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::collections::BTreeMap;
 use transaction_registry::TransactionRegistry;
+use yield_resume::YieldState;
 
 pub struct Model {
     /// Model execution round incremental value.
@@ -26,6 +43,8 @@ pub struct Model {
     /// execution round. (round = 0 means execution hasn't started, yet)
     pub(crate) round: Round,
 
+    pub(crate) config: ModelConfig,
+
     // Congestion strategy state
     pub(crate) shards: Vec<Box<dyn CongestionStrategy>>,
     pub(crate) shard_ids: Vec<ShardId>,
@@ -35,6 +54,41 @@ pub struct Model {
     // Workload state
     pub(crate) transactions: TransactionRegistry,
     pub(crate) producer: Box<dyn Producer>,
+    /// Source of all randomness in the workload.
+    pub(crate) rng: ChaCha8Rng,
+    /// Source of randomness for lost receipts, see
+    /// [`ModelConfig::drop_probability`].
+    drop_rng: ChaCha8Rng,
+    /// One source of randomness per shard for
+    /// [`ModelConfig::gas_limit_jitter`].
+    jitter_rngs: Vec<ChaCha8Rng>,
+    /// Receipts between shards waiting for acks, see [`ModelConfig::acks`].
+    pub(crate) acks: AckState,
+    /// Executed receipts waiting for resume, see [`ModelConfig::yield_resume`].
+    yields: YieldState,
+    /// Source of randomness for resuming yielded receipts.
+    yield_rng: ChaCha8Rng,
+    /// Transactions added with [`Model::submit`], by the round in which they
+    /// enter the mempool.
+    scheduled: BTreeMap<Round, Vec<TransactionId>>,
+    /// Receipts sent in the previous round, with their sender, waiting for the
+    /// chunk that includes them to be applied, see
+    /// [`ModelConfig::chunk_apply_lag`].
+    pub(crate) unapplied: Vec<(ShardId, Receipt)>,
+
+    // Evaluation state
+    /// Statistics of all chunks produced so far, in order of execution, one
+    /// per shard and round. With [`ModelConfig::prune_completed`], only those
+    /// of the last [`CHUNK_STATS_RETENTION`] rounds.
+    pub(crate) chunk_stats: Vec<ChunkStats>,
+    /// Contributions of pruned transactions to the metrics, see
+    /// [`ModelConfig::prune_completed`].
+    pub(crate) retired: RetiredTransactions,
+    /// Contributions of pruned chunk statistics to the metrics.
+    pub(crate) retired_chunks: RetiredChunks,
+
+    // External observers
+    completion_callbacks: Vec<Box<dyn FnMut(TransactionId, Round)>>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -56,54 +110,346 @@ impl Model {
         producer.init(&shard_ids);
 
         Self {
+            config: ModelConfig::default(),
             shards,
             shard_ids,
             block_info: Default::default(),
             transactions: Default::default(),
             producer,
+            rng: ChaCha8Rng::seed_from_u64(0),
+            drop_rng: rng_stream(0, DROP_STREAM),
+            jitter_rngs: jitter_rngs(0, num_shards),
+            acks: AckState::default(),
+            yields: YieldState::default(),
+            yield_rng: rng_stream(0, YIELD_STREAM),
+            scheduled: BTreeMap::new(),
+            unapplied: vec![],
+            chunk_stats: vec![],
+            retired: RetiredTransactions::default(),
+            retired_chunks: RetiredChunks::default(),
             round: 0,
             queues,
+            completion_callbacks: vec![],
+        }
+    }
+
+    /// Seed the random number generator used by the workload.
+    ///
+    /// Two model executions with the same strategies, workload and seed produce
+    /// exactly the same results.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.drop_rng = rng_stream(seed, DROP_STREAM);
+        self.jitter_rngs = jitter_rngs(seed, self.shard_ids.len());
+        self.yield_rng = rng_stream(seed, YIELD_STREAM);
+        self
+    }
+
+    /// Change protocol parameters of the model execution.
+    pub fn with_config(mut self, config: ModelConfig) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&config.drop_probability),
+            "drop probability must be between 0 and 1"
+        );
+        assert!(
+            (0.0..1.0).contains(&config.gas_limit_jitter),
+            "gas limit jitter must be at least 0 and less than 1"
+        );
+        if let Some(acks) = &config.acks {
+            let min_timeout = if config.chunk_apply_lag { 3 } else { 2 };
+            assert!(
+                acks.retransmit_timeout >= min_timeout,
+                "retransmit timeout must be at least {min_timeout} rounds"
+            );
         }
+        assert!(
+            (0.0..=1.0).contains(&config.yield_resume.resume_probability),
+            "resume probability must be between 0 and 1"
+        );
+        assert!(
+            (0.0..=1.0).contains(&config.rejected_tx_cost_fraction),
+            "rejected transaction cost fraction must be between 0 and 1"
+        );
+        assert!(
+            config.max_outstanding_receipts_per_tx != Some(0),
+            "transactions must be allowed at least one receipt"
+        );
+        if let Some(grouping) = &config.account_grouping {
+            assert!(grouping.lookahead >= 1, "account grouping needs a lookahead of at least 1");
+        }
+        assert!(
+            config.shard_order.is_valid(self.shard_ids.len()),
+            "fixed shard order must list each of the {} shards once",
+            self.shard_ids.len()
+        );
+        assert!(config.yield_resume.timeout >= 1, "yield timeout must be at least 1 round");
+        if let Some(epochs) = &config.epoch_schedule {
+            assert!(epochs.epoch_length >= 1, "epoch length must be at least 1 round");
+            assert!(!epochs.group_performance.is_empty(), "epoch schedule needs a validator group");
+            assert!(
+                epochs.group_performance.iter().all(|&performance| performance > 0.0),
+                "validator group performance must be positive"
+            );
+        }
+        self.config = config;
+        self
+    }
+
+    /// Reset the model to the state right after construction, using a new
+    /// seed.
+    ///
+    /// Strategies and the producer are initialized again. Queues and other
+    /// buffers keep their allocations, which makes repeated model executions
+    /// cheaper than constructing a new model for each. A reset model produces
+    /// exactly the same results as a newly constructed model with the same
+    /// seed.
+    pub fn reset(&mut self, seed: u64) {
+        self.round = 0;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.drop_rng = rng_stream(seed, DROP_STREAM);
+        self.jitter_rngs = jitter_rngs(seed, self.shard_ids.len());
+        self.acks = AckState::default();
+        self.yields = YieldState::default();
+        self.yield_rng = rng_stream(seed, YIELD_STREAM);
+        self.scheduled.clear();
+        self.unapplied.clear();
+        self.block_info.clear();
+        self.transactions.clear();
+        self.chunk_stats.clear();
+        self.retired = RetiredTransactions::default();
+        self.retired_chunks = RetiredChunks::default();
+
+        self.queues.reset(&self.shard_ids);
+        for (shard, &id) in self.shards.iter_mut().zip(&self.shard_ids) {
+            shard.init(id, &self.shard_ids, &mut self.queues);
+        }
+        self.queues.drop_unregistered_queues();
+
+        self.producer.init(&self.shard_ids);
     }
 
     /// execute one round of the model
+    ///
+    /// Each round runs in a `round` span and each chunk in a nested `chunk`
+    /// span, both at debug level with target `model`. Events for single
+    /// receipts and transactions use trace level, so they can be filtered out
+    /// separately. Without a subscriber, none of them costs more than a check
+    /// of a cached flag.
     pub fn step(&mut self) {
         self.round += 1;
+        let _round_span =
+            tracing::debug_span!(target: "model", "round", round = self.round).entered();
+
+        if let Some(map) = self.producer.resharding(self.round).cloned() {
+            self.reroute_receipts(&map);
+        }
 
         // Generate new transactions and place them in the per-shard transaction queues.
-        let new_transactions = self.generate_tx_for_round();
+        let mut new_transactions = self.generate_tx_for_round();
+        new_transactions.extend(self.scheduled.remove(&self.round).unwrap_or_default());
         for tx_id in new_transactions {
             let shard_id = self.transactions[tx_id].sender_shard;
             self.queues.incoming_transactions_mut(shard_id).push_back(tx_id);
         }
+        let mut completed = vec![];
+        let mut evicted = vec![0; self.shard_ids.len()];
+        for shard_id in self.shard_ids.clone() {
+            for tx_id in self.evict_transactions(shard_id) {
+                self.transactions[tx_id].evicted_at = Some(self.round);
+                completed.push(tx_id);
+                evicted[shard_id.0] += 1;
+            }
+            if evicted[shard_id.0] > 0 {
+                tracing::debug!(
+                    target: "model",
+                    shard = shard_id.0,
+                    evicted = evicted[shard_id.0],
+                    "transactions evicted"
+                );
+            }
+        }
+
+        self.receive_acks();
+        let yields = self.resume_yields();
+        completed.extend(yields.completed);
 
         // Give each shard a chance to their computations and buffer all created outputs.
+        // Outgoing receipts are buffered with their sender.
         let mut outgoing = vec![];
         let mut next_block = BTreeMap::new();
-        for (i, shard) in self.shards.iter_mut().enumerate() {
+        let mut round_stats = Vec::with_capacity(self.shards.len());
+        let mut global_gas_left = self.config.global_gas_limit;
+        for i in self.config.shard_order.order(self.round, self.shards.len()) {
             let id = ShardId(i);
+            let _chunk_span = tracing::debug_span!(target: "model", "chunk", shard = i).entered();
+            let overhead = self.send_acks_and_retransmit(id);
+            outgoing.extend(overhead.delivered.into_iter().map(|receipt| (id, receipt)));
+            let mut gas_limit = self.chunk_gas_limit(i);
+            if let Some(left) = global_gas_left {
+                gas_limit = gas_limit.min(left);
+            }
             let mut ctx = ChunkExecutionContext::new(
+                &self.config,
                 &mut self.queues,
                 &mut self.transactions,
                 &self.block_info,
                 self.round,
                 ShardId(i),
+                gas_limit,
             );
-            shard.compute_chunk(&mut ctx);
-            let (mut forwarded_receipts, shared_block_info) = ctx.finish();
+            ctx.burn_forwarding_gas(overhead.gas);
+            ctx.reject_invalid_transactions();
+            ctx.group_incoming_receipts();
+            self.shards[i].compute_chunk(&mut ctx);
+            let mut output = ctx.finish();
 
-            outgoing.append(&mut forwarded_receipts);
-            next_block.insert(id, shared_block_info);
+            let acks_enabled = self.config.acks.is_some();
+            for receipt in output.forwarded_receipts {
+                let cross_shard = receipt.receiver != id;
+                if cross_shard && acks_enabled {
+                    self.acks.sent(id, self.round, &receipt);
+                }
+                let lost = cross_shard && self.lose_receipt();
+                if !lost {
+                    outgoing.push((id, receipt));
+                } else if !acks_enabled {
+                    let tx = receipt.transaction_id();
+                    tracing::trace!(target: "model", receipt = ?receipt.id, "receipt lost");
+                    self.transactions[tx].drop_receipt(receipt, self.round);
+                    if !self.transactions[tx].is_in_flight() {
+                        output.completed_transactions.push(tx);
+                    }
+                }
+                // With acks, the sender's copy is retransmitted later.
+            }
+            completed.append(&mut output.completed_transactions);
+            output.stats.evicted_transactions = evicted[i];
+            output.stats.acks_sent = overhead.acks_sent;
+            output.stats.retransmitted_receipts = overhead.retransmitted_receipts;
+            output.stats.ack_gas = overhead.gas;
+            self.yields.yielded(id, self.round, output.yielded_receipts);
+            output.stats.pending_yields = self.yields.pending_on(id);
+            output.stats.timed_out_yields = yields.timed_out[i];
+            next_block.insert(id, output.block_info);
+            tracing::debug!(
+                target: "model",
+                gas_burnt = output.stats.gas_burnt,
+                gas_limit = output.stats.gas_limit,
+                queued_gas = output.stats.queued_gas,
+                "chunk executed"
+            );
+            if let Some(left) = &mut global_gas_left {
+                *left = left.saturating_sub(output.stats.gas_burnt);
+            }
+            round_stats.push(output.stats);
         }
+        round_stats.sort_by_key(|stats| stats.shard);
+        self.record_chunk_stats(round_stats);
 
         // Propagate outputs from this round to inputs for the next round.
+        // With the apply lag, receipts of this round wait one more round.
         self.block_info = next_block;
-        for receipt in outgoing {
+        if self.config.chunk_apply_lag {
+            outgoing = std::mem::replace(&mut self.unapplied, outgoing);
+        }
+        for (sender, receipt) in outgoing {
+            let receipt = if self.config.acks.is_some() && receipt.receiver != sender {
+                match self.acks.deliver(receipt) {
+                    Some(receipt) => receipt,
+                    None => continue,
+                }
+            } else {
+                receipt
+            };
             // TODO: Deal with postponed receipts. There should be a separate
             // queue where they are kept until all dependencies have been
             // resolved. But for now, there is no producer of such workload.
             self.queues.incoming_receipts_mut(receipt.receiver).push_back(receipt);
         }
+
+        for tx_id in completed {
+            tracing::trace!(target: "model", tx = ?tx_id, "transaction completed");
+            for callback in &mut self.completion_callbacks {
+                callback(tx_id, self.round);
+            }
+            if self.config.prune_completed {
+                let tx = self.transactions.prune(tx_id);
+                self.retired.retire(&tx, &self.config, self.round);
+            }
+        }
+    }
+
+    /// Keep the statistics of the chunks of one round. With pruning, the
+    /// oldest round beyond the retention window goes to the retired metrics.
+    pub(super) fn record_chunk_stats(&mut self, round_stats: Vec<ChunkStats>) {
+        self.chunk_stats.extend(round_stats);
+        if !self.config.prune_completed {
+            return;
+        }
+        let num_shards = self.shard_ids.len();
+        let excess = self.chunk_stats.len().saturating_sub(CHUNK_STATS_RETENTION * num_shards);
+        for round in self.chunk_stats[..excess].chunks(num_shards) {
+            self.retired_chunks.retire_round(round, &self.config);
+        }
+        self.chunk_stats.drain(..excess);
+    }
+
+    /// Gas limit of the shard's chunk in the current round, drawing its
+    /// jitter.
+    pub(super) fn chunk_gas_limit(&mut self, shard_index: usize) -> GGas {
+        let gas_limit = self.config.gas_limit(ShardId(shard_index), self.round);
+        let jitter = self.config.gas_limit_jitter;
+        if jitter == 0.0 {
+            return gas_limit;
+        }
+        let factor = self.jitter_rngs[shard_index].gen_range(1.0 - jitter..1.0 + jitter);
+        (gas_limit as f64 * factor) as GGas
+    }
+
+    /// Register a callback that is called once for every transaction when it
+    /// completes, with the round it completed in.
+    ///
+    /// A transaction completes when none of its receipts is pending anymore,
+    /// either because all of them executed or because some were dropped. A
+    /// transaction evicted from a mempool or rejected by its sender shard
+    /// completes in the round it is evicted or rejected.
+    /// Callbacks run at the end of [`Model::step`], in the order the
+    /// transactions completed. They stay registered across [`Model::reset`].
+    pub fn on_transaction_complete(&mut self, f: impl FnMut(TransactionId, Round) + 'static) {
+        self.completion_callbacks.push(Box::new(f));
+    }
+
+    /// Start building a transaction by hand, to add it with [`Model::submit`].
+    ///
+    /// The builder enforces [`ModelConfig::max_receipt_size`] and, if it
+    /// truncates, [`ModelConfig::max_outstanding_receipts_per_tx`], like the
+    /// builders handed to producers.
+    pub fn new_transaction(&mut self, sender: ShardId) -> TransactionBuilder {
+        let builder = self.transactions.new_transaction_builder(sender, self.round + 1);
+        self.config.limit_builder(builder)
+    }
+
+    /// Add a transaction built with [`Model::new_transaction`], to enter the
+    /// mempool of its sender at the start of `round`, after the transactions
+    /// of the producer.
+    ///
+    /// Panics if the round has already started, if the transaction has no
+    /// receipts, if a receipt goes to a shard the model does not have or if
+    /// the receipt dependencies contain a cycle. The transaction is visible
+    /// right away, as waiting for conversion.
+    pub fn submit(&mut self, mut tx: TransactionBuilder, round: Round) -> TransactionId {
+        assert!(round > self.round, "round {round} has already started");
+        tx.validate(&self.shard_ids);
+        tx.set_round(round);
+        let id = self.transactions.build_transaction(tx);
+        self.scheduled.entry(round).or_default().push(id);
+        id
+    }
+
+    /// First round after the current one with a transaction added by
+    /// [`Model::submit`].
+    pub(super) fn next_scheduled_round(&self) -> Option<Round> {
+        self.scheduled.keys().next().copied()
     }
 
     fn generate_tx_for_round(&mut self) -> Vec<TransactionId> {
@@ -112,10 +458,17 @@ impl Model {
         // model to control how these are created and registered.
         // Hence, we inject a factory as a dependency and collect the created
         // builders as the output.
-        let mut tx_factory =
-            |shard_id| self.transactions.new_transaction_builder(shard_id, self.round);
-        let tx_builders =
-            self.producer.produce_transactions(self.round, &self.shard_ids, &mut tx_factory);
+        let config = &self.config;
+        let mut tx_factory = |shard_id| {
+            let builder = self.transactions.new_transaction_builder(shard_id, self.round);
+            config.limit_builder(builder)
+        };
+        let tx_builders = self.producer.produce_transactions(
+            self.round,
+            &self.shard_ids,
+            &mut tx_factory,
+            &mut self.rng,
+        );
 
         // Now we take all created transactions and register them properly. Return tx ids.
         tx_builders
@@ -124,6 +477,31 @@ impl Model {
             .collect()
     }
 
+    /// Remove transactions from a full mempool, according to
+    /// [`ModelConfig::mempool_capacity`].
+    fn evict_transactions(&mut self, shard_id: ShardId) -> Vec<TransactionId> {
+        let Some(capacity) = self.config.mempool_capacity else {
+            return vec![];
+        };
+        let mempool = self.queues.incoming_transactions_mut(shard_id);
+        let num_evicted = mempool.len().saturating_sub(capacity);
+        match self.config.mempool_eviction {
+            MempoolEviction::Oldest => mempool.drain(..num_evicted).collect(),
+            MempoolEviction::LowestFee => {
+                let transactions = &self.transactions;
+                // stable sort, so the oldest go first among equal fees
+                let mut by_fee: Vec<(usize, TransactionId)> =
+                    mempool.iter().copied().enumerate().collect();
+                by_fee.sort_by_key(|&(_, tx_id)| transactions[tx_id].fee);
+                let mut evict: Vec<usize> =
+                    by_fee[..num_evicted].iter().map(|&(index, _)| index).collect();
+                // remove from the back to keep the remaining indices valid
+                evict.sort_unstable();
+                evict.iter().rev().map(|&index| mempool.remove(index).unwrap()).collect()
+            }
+        }
+    }
+
     pub fn trim_transaction_pools(&mut self, max_len: usize) {
         for &shard_id in &self.shard_ids {
             let len = self.queues.incoming_transactions(shard_id).len();
@@ -137,6 +515,17 @@ impl Model {
         self.shards[id.0].as_mut()
     }
 
+    /// Internal state of the strategy on the shard, see
+    /// [`CongestionStrategy::debug_state`].
+    pub fn strategy_debug_state(&self, id: ShardId) -> serde_json::Value {
+        self.shards[id.0].debug_state()
+    }
+
+    /// The last executed round, or 0 if the model has not been stepped, yet.
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
     /// Ordered list of shard IDs
     pub fn shard_ids(&self) -> &[ShardId] {
         &self.shard_ids
@@ -147,6 +536,29 @@ impl Model {
     }
 }
 
+/// Rounds of chunk statistics the model keeps with
+/// [`ModelConfig::prune_completed`]. Metrics over single chunks or rounds,
+/// like [`Model::chunk_utilization`] or [`Model::onset_recovery`], only see
+/// these rounds then.
+pub const CHUNK_STATS_RETENTION: usize = 64;
+
+/// Stream of the random number generator for lost receipts. The workload
+/// uses stream 0, jitter uses one stream per shard after this.
+const DROP_STREAM: u64 = 1;
+/// Stream for resuming yielded receipts, far after the jitter streams.
+const YIELD_STREAM: u64 = u64::MAX;
+
+/// Random number generator seeded with the model seed, on a separate stream.
+fn rng_stream(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+fn jitter_rngs(seed: u64, num_shards: usize) -> Vec<ChaCha8Rng> {
+    (0..num_shards).map(|i| rng_stream(seed, DROP_STREAM + 1 + i as u64)).collect()
+}
+
 impl std::fmt::Display for ShardId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -160,3 +572,394 @@ impl std::ops::Deref for ShardId {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{NepStrategy, NoQueueShard};
+    use crate::workload::{BalancedProducer, WhaleProducer};
+    use crate::{ReceiptDefinition, TransactionBuilder, GAS_LIMIT, TGAS};
+    use rand::RngCore;
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    fn new_model() -> Model {
+        let shards = (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let producer = WhaleProducer::new(Box::<BalancedProducer>::default(), 0.05, 20);
+        Model::new(shards, Box::new(producer))
+    }
+
+    /// Summarize the model state in a string that can be compared for equality.
+    fn fingerprint(model: &Model) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?}",
+            model.progress(),
+            model.gas_throughput(),
+            model.max_queue_length(),
+            model.user_experience(),
+            model.chunk_stats,
+        )
+    }
+
+    fn run(model: &mut Model, rounds: usize) -> Vec<String> {
+        (0..rounds)
+            .map(|_| {
+                model.step();
+                fingerprint(model)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reset_model_matches_new_model() {
+        let fresh = run(&mut new_model().with_seed(42), 50);
+
+        let mut reused = new_model().with_seed(7);
+        run(&mut reused, 80);
+        reused.reset(42);
+
+        assert_eq!(fresh, run(&mut reused, 50));
+    }
+
+    #[test]
+    fn receipt_loss_is_deterministic() {
+        let lossy = || ModelConfig { drop_probability: 0.1, ..ModelConfig::default() };
+        let lossless = ModelConfig { drop_probability: 0.0, ..ModelConfig::default() };
+
+        let baseline = run(&mut new_model().with_seed(42), 50);
+        assert_eq!(baseline, run(&mut new_model().with_seed(42).with_config(lossless), 50));
+
+        let mut model = new_model().with_seed(42).with_config(lossy());
+        let lost = run(&mut model, 50);
+        assert_ne!(baseline, lost);
+        assert!(model.progress().failed_transactions > 0);
+        assert!(model.gas_ledger().is_balanced());
+        assert_eq!(lost, run(&mut new_model().with_seed(42).with_config(lossy()), 50));
+        model.reset(42);
+        assert_eq!(lost, run(&mut model, 50));
+    }
+
+    #[test]
+    fn gas_limit_jitter_is_isolated_per_shard() {
+        let gas_limits = |num_shards: usize, seed: u64| {
+            let shards = (0..num_shards).map(|_| Box::<NepStrategy>::default() as _).collect();
+            let config = ModelConfig { gas_limit_jitter: 0.2, ..ModelConfig::default() };
+            let mut model = Model::new(shards, Box::<BalancedProducer>::default())
+                .with_seed(seed)
+                .with_config(config);
+            for _ in 0..50 {
+                model.step();
+            }
+            let shard_0: Vec<GGas> = model
+                .chunk_stats
+                .iter()
+                .filter(|stats| stats.shard == ShardId(0))
+                .map(|stats| stats.gas_limit)
+                .collect();
+            shard_0
+        };
+
+        let limits = gas_limits(4, 1);
+        assert!(limits.iter().all(|&gas| gas >= GAS_LIMIT * 8 / 10 && gas < GAS_LIMIT * 12 / 10));
+        assert!(limits.iter().any(|&gas| gas != limits[0]));
+        // other shards and the workload don't influence the draws of shard 0
+        assert_eq!(limits, gas_limits(2, 1));
+        assert_ne!(limits, gas_limits(4, 2));
+    }
+
+    #[test]
+    fn gas_limit_schedule_changes_throughput() {
+        let shards = (0..4).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let config = ModelConfig {
+            gas_limit_schedule: vec![(20, 2 * GAS_LIMIT)],
+            shard_gas_limit_schedule: vec![(20, ShardId(0), GAS_LIMIT / 2)],
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..30 {
+            model.step();
+        }
+
+        let burnt = |round, shard| {
+            let stats =
+                model.chunk_stats.iter().find(|s| s.round == round && s.shard == ShardId(shard));
+            stats.unwrap().gas_burnt
+        };
+        for round in 10..20 {
+            for shard in 0..4 {
+                assert!(burnt(round, shard) <= GAS_LIMIT + GAS_LIMIT / 10);
+            }
+        }
+        for round in 20..30 {
+            assert!(burnt(round, 0) <= GAS_LIMIT / 2 + GAS_LIMIT / 10);
+            for shard in 1..4 {
+                assert!(burnt(round, shard) > GAS_LIMIT + GAS_LIMIT / 2);
+            }
+        }
+    }
+
+    /// Smallest over largest total gas burnt by a shard in 40 rounds with a
+    /// global gas limit of one and a half chunks, where both shards want more.
+    fn service_fairness(shard_order: ShardOrder) -> f64 {
+        let shards = (0..2).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let config = ModelConfig {
+            global_gas_limit: Some(GAS_LIMIT * 3 / 2),
+            shard_order,
+            ..ModelConfig::default()
+        };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..40 {
+            model.step();
+        }
+        let mut burnt = [0; 2];
+        for stats in &model.chunk_stats {
+            burnt[stats.shard.0] += stats.gas_burnt;
+        }
+        burnt[0].min(burnt[1]) as f64 / burnt[0].max(burnt[1]) as f64
+    }
+
+    #[test]
+    fn rotating_shard_order_shares_a_global_gas_limit_fairly() {
+        let ascending = service_fairness(ShardOrder::Ascending);
+        let descending = service_fairness(ShardOrder::Descending);
+        let rotating = service_fairness(ShardOrder::Rotating);
+        assert!(ascending < 0.7, "{ascending}");
+        assert!(descending < 0.7, "{descending}");
+        assert!(rotating > 0.95, "{rotating}");
+    }
+
+    #[test]
+    fn shard_orders() {
+        assert_eq!(ShardOrder::Descending.order(1, 3), [2, 1, 0]);
+        assert_eq!(ShardOrder::Rotating.order(1, 3), [0, 1, 2]);
+        assert_eq!(ShardOrder::Rotating.order(5, 3), [1, 2, 0]);
+        assert_eq!("2,0,1".parse(), Ok(ShardOrder::Fixed(vec![2, 0, 1])));
+        assert!(ShardOrder::Fixed(vec![2, 0, 1]).is_valid(3));
+        assert!(!ShardOrder::Fixed(vec![0, 0, 1]).is_valid(3));
+        assert!("sideways".parse::<ShardOrder>().is_err());
+    }
+
+    #[test]
+    fn epoch_schedule_changes_capacity_at_epoch_boundaries() {
+        let shards = (0..3).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let epochs = EpochSchedule { epoch_length: 10, group_performance: vec![1.0, 0.5] };
+        let config = ModelConfig { epoch_schedule: Some(epochs), ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default()).with_config(config);
+        for _ in 0..35 {
+            model.step();
+        }
+
+        for stats in &model.chunk_stats {
+            let epoch = (stats.round - 1) / 10;
+            let fast = (stats.shard.0 as u64 + epoch) % 2 == 0;
+            let expected = if fast { GAS_LIMIT } else { GAS_LIMIT / 2 };
+            assert_eq!(stats.gas_limit, expected, "round {} shard {}", stats.round, stats.shard);
+        }
+        // within an epoch, the limit stays put, across a boundary it changes
+        let limit = |round, shard| {
+            let stats =
+                model.chunk_stats.iter().find(|s| s.round == round && s.shard == ShardId(shard));
+            stats.unwrap().gas_limit
+        };
+        for round in 2..=35 {
+            let boundary = round % 10 == 1;
+            assert_eq!(limit(round, 0) != limit(round - 1, 0), boundary, "round {round}");
+        }
+    }
+
+    /// Produces a single chain of receipts with growing sizes.
+    struct GrowingChain {
+        factor: f64,
+    }
+
+    impl Producer for GrowingChain {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let receipt = |size| ReceiptDefinition {
+                receiver: shards[0],
+                size,
+                attached_gas: TGAS,
+                execution_gas: TGAS,
+            };
+            let mut tx = tx_factory(shards[0]).with_size_growth(self.factor);
+            let mut prev = tx.add_first_receipt(receipt(100), 0);
+            for _ in 0..3 {
+                prev = tx.new_outgoing_receipt(prev, receipt(1));
+            }
+            vec![tx]
+        }
+    }
+
+    fn receipt_sizes(factor: f64, max_receipt_size: Option<u64>) -> Vec<u64> {
+        let shards = vec![Box::new(NoQueueShard {}) as _];
+        let config = ModelConfig { max_receipt_size, ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::new(GrowingChain { factor })).with_config(config);
+        let tx = model.generate_tx_for_round()[0];
+        let mut receipts: Vec<_> = model.transactions[tx].future_receipts.values().collect();
+        receipts.sort_by_key(|receipt| receipt.size);
+        receipts.iter().map(|receipt| receipt.size).collect()
+    }
+
+    #[test]
+    fn receipt_size_growth() {
+        assert_eq!(receipt_sizes(2.0, None), [100, 200, 400, 800]);
+        assert_eq!(receipt_sizes(0.5, None), [12, 25, 50, 100]);
+        assert_eq!(receipt_sizes(2.0, Some(300)), [100, 200, 300, 300]);
+        assert_eq!(receipt_sizes(1e30, None), [100, u64::MAX, u64::MAX, u64::MAX]);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds max receipt size")]
+    fn receipt_above_max_size_panics() {
+        receipt_sizes(1.0, Some(50));
+    }
+
+    /// Submits one transaction with a single receipt from shard 0 to shard 1,
+    /// in the first round only.
+    struct SingleTransaction;
+
+    impl Producer for SingleTransaction {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round > 1 {
+                return vec![];
+            }
+            let receipt = ReceiptDefinition {
+                receiver: shards[1],
+                size: 100,
+                attached_gas: TGAS,
+                execution_gas: TGAS,
+            };
+            let mut tx = tx_factory(shards[0]);
+            tx.add_first_receipt(receipt, TGAS);
+            vec![tx]
+        }
+    }
+
+    fn single_transaction_completion(chunk_apply_lag: bool) -> Round {
+        let shards = (0..2).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let config = ModelConfig { chunk_apply_lag, ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::new(SingleTransaction)).with_config(config);
+        let completed = Rc::new(RefCell::new(None));
+        let sink = completed.clone();
+        model.on_transaction_complete(move |_, round| *sink.borrow_mut() = Some(round));
+        for _ in 0..5 {
+            model.step();
+            assert!(model.gas_ledger().is_balanced());
+        }
+        let round = completed.borrow().expect("transaction completed");
+        round
+    }
+
+    #[test]
+    fn chunk_apply_lag_delays_receipts_by_one_round() {
+        // converted in round 1, executed in the next round
+        assert_eq!(single_transaction_completion(false), 2);
+        // included by the receiver in round 2, applied in round 3
+        assert_eq!(single_transaction_completion(true), 3);
+    }
+
+    /// Submits nothing.
+    struct NoTransactions;
+
+    impl Producer for NoTransactions {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            _shards: &[ShardId],
+            _tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            vec![]
+        }
+    }
+
+    fn idle_model() -> Model {
+        let shards = (0..2).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        Model::new(shards, Box::new(NoTransactions))
+    }
+
+    fn receipt_to(receiver: ShardId) -> ReceiptDefinition {
+        ReceiptDefinition { receiver, size: 100, attached_gas: TGAS, execution_gas: TGAS }
+    }
+
+    #[test]
+    fn submitted_transaction_runs_at_its_round() {
+        let mut model = idle_model();
+        model.step();
+        let mut tx = model.new_transaction(ShardId(0));
+        let first = tx.add_first_receipt(receipt_to(ShardId(1)), TGAS);
+        tx.new_outgoing_receipt(first, receipt_to(ShardId(0)));
+        let id = model.submit(tx, 3);
+        assert_eq!(model.transaction_view(id).submitted_at(), 3);
+
+        let completed = Rc::new(RefCell::new(vec![]));
+        let sink = completed.clone();
+        model.on_transaction_complete(move |tx, round| sink.borrow_mut().push((tx, round)));
+        for _ in 0..5 {
+            model.step();
+        }
+        // converted in round 3, then one round per receipt
+        assert_eq!(*completed.borrow(), [(id, 5)]);
+        assert_eq!(model.transaction_view(id).receipt_count(), 2);
+        assert!(model.gas_ledger().is_balanced());
+    }
+
+    #[test]
+    #[should_panic(expected = "contain a cycle")]
+    fn submit_rejects_cycles() {
+        let mut model = idle_model();
+        let mut tx = model.new_transaction(ShardId(0));
+        let first = tx.add_first_receipt(receipt_to(ShardId(1)), TGAS);
+        let a = tx.new_outgoing_receipt(first, receipt_to(ShardId(0)));
+        let b = tx.new_outgoing_receipt(a, receipt_to(ShardId(1)));
+        tx.new_dependency(b, a);
+        model.submit(tx, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown receiver shard 7")]
+    fn submit_rejects_unknown_shards() {
+        let mut model = idle_model();
+        let mut tx = model.new_transaction(ShardId(0));
+        tx.add_first_receipt(receipt_to(ShardId(7)), TGAS);
+        model.submit(tx, 1);
+    }
+
+    #[test]
+    fn completion_callback_fires_once_per_transaction() {
+        let completed = Rc::new(RefCell::new(vec![]));
+        let mut model = new_model();
+        let sink = completed.clone();
+        model.on_transaction_complete(move |tx, round| sink.borrow_mut().push((tx, round)));
+
+        for round in 1..=100 {
+            model.step();
+            assert!(completed.borrow().iter().all(|&(_, r)| r <= round));
+        }
+
+        let completed = completed.borrow();
+        let unique: HashSet<_> = completed.iter().map(|(tx, _)| tx).collect();
+        assert_eq!(unique.len(), completed.len());
+        let progress = model.progress();
+        assert!(progress.finished_transactions > 0);
+        assert_eq!(completed.len(), progress.finished_transactions + progress.failed_transactions);
+    }
+}
@@ -13,6 +13,14 @@ impl Queue {
         Self { shard, name: name.to_string(), messages: VecDeque::new() }
     }
 
+    /// Empty the queue and assign it to a new owner, keeping its allocation.
+    pub(crate) fn reset(&mut self, shard: ShardId, name: &str) {
+        self.shard = shard;
+        self.name.clear();
+        self.name.push_str(name);
+        self.messages.clear();
+    }
+
     pub fn size(&self) -> u64 {
         self.messages.iter().map(|receipt| receipt.size).sum()
     }
@@ -5,6 +5,8 @@ use std::collections::{HashMap, VecDeque};
 /// A bag of all queues in the system, bundled in a single struct.
 pub struct QueueBundle {
     receipt_queues: Vec<Queue>,
+    /// Number of queues registered since creation or the last reset.
+    num_registered_queues: usize,
     transaction_queues: HashMap<ShardId, VecDeque<TransactionId>>,
 
     /// Maps shards to their implicit incoming receipts queue.
@@ -18,25 +20,50 @@ impl QueueBundle {
     pub fn new(shards: &[ShardId]) -> Self {
         let mut this = Self {
             receipt_queues: Default::default(),
+            num_registered_queues: 0,
             transaction_queues: Default::default(),
             shard_mailbox: Default::default(),
         };
-
-        for &shard in shards {
-            let mailbox = this.new_queue(shard, "mailbox");
-            this.shard_mailbox.insert(shard, mailbox);
-            this.transaction_queues.insert(shard, VecDeque::new());
-        }
-
+        this.register_mailboxes(shards);
         this
     }
 
     pub fn new_queue(&mut self, shard_id: ShardId, name: &str) -> QueueId {
-        let id = self.receipt_queues.len();
-        self.receipt_queues.push(Queue::new(shard_id, name));
+        let id = self.num_registered_queues;
+        self.num_registered_queues += 1;
+        if let Some(queue) = self.receipt_queues.get_mut(id) {
+            // reuse a queue left over from before the last reset
+            queue.reset(shard_id, name);
+        } else {
+            self.receipt_queues.push(Queue::new(shard_id, name));
+        }
         QueueId(id)
     }
 
+    /// Empty all queues and start over with registering queues, as if the
+    /// bundle was newly created.
+    ///
+    /// Existing allocations are reused for queues registered again after the
+    /// reset. Call [`QueueBundle::drop_unregistered_queues`] once all queues
+    /// have been registered again.
+    pub fn reset(&mut self, shards: &[ShardId]) {
+        self.num_registered_queues = 0;
+        self.register_mailboxes(shards);
+    }
+
+    /// Remove queues that were not registered again after a reset.
+    pub fn drop_unregistered_queues(&mut self) {
+        self.receipt_queues.truncate(self.num_registered_queues);
+    }
+
+    fn register_mailboxes(&mut self, shards: &[ShardId]) {
+        for &shard in shards {
+            let mailbox = self.new_queue(shard, "mailbox");
+            self.shard_mailbox.insert(shard, mailbox);
+            self.transaction_queues.entry(shard).or_default().clear();
+        }
+    }
+
     pub fn queue(&self, id: QueueId) -> &Queue {
         &self.receipt_queues[id.0]
     }
@@ -68,6 +95,11 @@ impl QueueBundle {
     pub fn shard_queues(&self, shard_id: ShardId) -> impl Iterator<Item = &Queue> {
         self.receipt_queues.iter().filter(move |q| q.shard() == shard_id)
     }
+
+    /// All registered queues of all shards.
+    pub(crate) fn all_queues_mut(&mut self) -> impl Iterator<Item = &mut Queue> {
+        self.receipt_queues[..self.num_registered_queues].iter_mut()
+    }
 }
 
 impl QueueFactory for QueueBundle {
@@ -0,0 +1,150 @@
+use super::{Model, Receipt};
+use crate::workload::AccountShardMap;
+use std::collections::VecDeque;
+
+impl Model {
+    /// Move every receipt that has not executed, yet, to the shard of its
+    /// receiver account under the new map, see
+    /// [`crate::workload::Producer::resharding`].
+    ///
+    /// Receipts without an account keep their receiver. A receipt queued on
+    /// its receiver, in the mailbox or in a queue of the strategy, moves to the
+    /// mailbox of the new receiver, after the receipts already there. That is
+    /// how the split parent hands its delayed receipts to the children. A
+    /// receipt queued on its sender keeps its place and is forwarded to the
+    /// new receiver. Transactions in a mempool stay where they are.
+    pub(super) fn reroute_receipts(&mut self, map: &AccountShardMap) {
+        assert_eq!(
+            map.num_shards(),
+            self.shard_ids.len(),
+            "account map must have one range per shard"
+        );
+        let shard_ids = &self.shard_ids;
+        let reroute = |receipt: &mut Receipt| {
+            if let Some(account) = &receipt.receiver_account {
+                receipt.receiver = shard_ids[map.shard_index(account)];
+            }
+        };
+
+        for tx in self.transactions.all_transactions_mut() {
+            tx.future_receipts.values_mut().for_each(reroute);
+        }
+        let mut moved = vec![];
+        for queue in self.queues.all_queues_mut() {
+            let owner = queue.shard();
+            let mut kept = VecDeque::with_capacity(queue.len());
+            for mut receipt in queue.drain(..) {
+                let incoming = receipt.receiver == owner;
+                reroute(&mut receipt);
+                if incoming && receipt.receiver != owner {
+                    moved.push(receipt);
+                } else {
+                    kept.push_back(receipt);
+                }
+            }
+            **queue = kept;
+        }
+        for (_, receipt) in &mut self.unapplied {
+            reroute(receipt);
+        }
+        self.acks.copies_mut().for_each(reroute);
+
+        tracing::debug!(target: "model", moved = moved.len(), "receipts rerouted after resharding");
+        for receipt in moved {
+            self.queues.incoming_receipts_mut(receipt.receiver).push_back(receipt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ShardId;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::{AccountTrafficProducer, Producer, ReadTrafficProducer};
+    use crate::{CongestionStrategy, ReceiptDefinition, TGAS};
+
+    #[test]
+    fn in_flight_receipts_follow_their_account_after_a_split() {
+        // shard 0 holds everything below "8" and splits at "4" in round 3
+        let before = AccountShardMap::new(vec!["8".to_string()]);
+        let mut after = before.clone();
+        after.split_shard(0, "4".to_string());
+        let producer =
+            AccountTrafficProducer::new(50, 3, 10).with_map(before).with_split(3, 0, "4".into());
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..3).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(shards, Box::new(producer));
+
+        // arrives in the mailbox of shard 0 at the end of round 2, its
+        // children are created after the split
+        let def = |receiver| ReceiptDefinition {
+            receiver,
+            size: 100,
+            attached_gas: TGAS,
+            execution_gas: TGAS,
+        };
+        let mut tx = model.new_transaction(ShardId(2));
+        let first = tx.add_first_receipt(def(ShardId(0)), TGAS);
+        tx.set_receiver_account(first, "6.near".to_string());
+        let upper = tx.new_outgoing_receipt(first, def(ShardId(0)));
+        tx.set_receiver_account(upper, "7.near".to_string());
+        let lower = tx.new_outgoing_receipt(first, def(ShardId(0)));
+        tx.set_receiver_account(lower, "2.near".to_string());
+        let id = model.submit(tx, 2);
+        for _ in 0..6 {
+            model.step();
+        }
+
+        let executed = &model.transactions[id].executed_receipts;
+        assert_eq!(executed[&first].receiver, ShardId(1));
+        assert_eq!(executed[&first].executed_at, Some(3));
+        assert_eq!(executed[&upper].receiver, ShardId(1));
+        assert_eq!(executed[&lower].receiver, ShardId(0));
+
+        let mut checked = 0;
+        for tx in model.transactions.all_transactions() {
+            for receipt in tx.executed_receipts.values() {
+                let account = receipt.receiver_account.as_deref().unwrap();
+                if receipt.executed_at.unwrap() >= 3 {
+                    assert_eq!(receipt.receiver, model.shard_ids[after.shard_index(account)]);
+                } else {
+                    assert_ne!(receipt.receiver, ShardId(1), "{account} before the split");
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 50, "only {checked} receipts checked");
+    }
+
+    #[test]
+    fn wrapped_producers_pass_on_the_split() {
+        let before = AccountShardMap::new(vec!["8".to_string()]);
+        let inner =
+            AccountTrafficProducer::new(50, 3, 10).with_map(before).with_split(3, 0, "4".into());
+        let producer = ReadTrafficProducer::new(Box::new(inner), 1, TGAS);
+        assert!(producer.resharding(2).is_none());
+        assert!(producer.resharding(3).is_some());
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..3).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(shards, Box::new(producer));
+
+        // in the mailbox of shard 0 during the split
+        let mut tx = model.new_transaction(ShardId(2));
+        let def = ReceiptDefinition {
+            receiver: ShardId(0),
+            size: 100,
+            attached_gas: TGAS,
+            execution_gas: TGAS,
+        };
+        let first = tx.add_first_receipt(def, TGAS);
+        tx.set_receiver_account(first, "6.near".to_string());
+        let id = model.submit(tx, 2);
+        for _ in 0..4 {
+            model.step();
+        }
+        let executed = &model.transactions[id].executed_receipts;
+        assert_eq!(executed[&first].receiver, ShardId(1));
+        assert_eq!(executed[&first].executed_at, Some(3));
+    }
+}
@@ -0,0 +1,168 @@
+use super::{ChunkStats, Model, ShardId};
+use crate::Round;
+
+/// Decides which round [`Model::run`] executes next.
+pub trait RoundPolicy {
+    /// The next round to execute, after the current round of the state.
+    /// `None` stops the run.
+    ///
+    /// Returning a round more than one ahead skips the rounds in between,
+    /// which is only allowed while [`ModelState::is_idle`].
+    fn next_round(&mut self, state: &ModelState) -> Option<Round>;
+}
+
+/// Read-only view of the model between two rounds, for a [`RoundPolicy`].
+pub struct ModelState<'model> {
+    model: &'model Model,
+}
+
+/// Execute every round up to and including `last_round`, the same as calling
+/// [`Model::step`] in a loop.
+pub struct FixedStep {
+    pub last_round: Round,
+}
+
+/// Skip rounds in which nothing can happen, up to and including `last_round`.
+///
+/// While the model is idle, it jumps straight to the next round in which the
+/// producer submits transactions, see
+/// [`crate::workload::Producer::next_submission_round`]. This speeds up
+/// sparse workloads and gives the same results as [`FixedStep`], unless a
+/// strategy changes its state in rounds without any traffic.
+pub struct EventDriven {
+    pub last_round: Round,
+}
+
+impl RoundPolicy for FixedStep {
+    fn next_round(&mut self, state: &ModelState) -> Option<Round> {
+        (state.round() < self.last_round).then(|| state.round() + 1)
+    }
+}
+
+impl RoundPolicy for EventDriven {
+    fn next_round(&mut self, state: &ModelState) -> Option<Round> {
+        if state.round() >= self.last_round {
+            return None;
+        }
+        if !state.is_idle() {
+            return Some(state.round() + 1);
+        }
+        Some(state.next_submission_round().min(self.last_round))
+    }
+}
+
+impl ModelState<'_> {
+    /// The last executed round, 0 before the first.
+    pub fn round(&self) -> Round {
+        self.model.round
+    }
+
+    /// Whether a round without new transactions would do nothing.
+    ///
+    /// That is the case when no transaction waits for conversion, no receipt
+    /// is queued, travels between shards or waits for its chunk to be applied
+    /// and no yield waits for resume.
+    pub fn is_idle(&self) -> bool {
+        let model = self.model;
+        model.shard_ids.iter().all(|&shard| {
+            model.queues.incoming_transactions(shard).is_empty()
+                && model.queues.shard_queues(shard).all(|queue| queue.is_empty())
+        }) && model.acks.is_empty()
+            && model.unapplied.is_empty()
+            && model.yields.len() == 0
+    }
+
+    /// First round after the current one in which the producer may submit
+    /// transactions, or a transaction added with [`Model::submit`] enters the
+    /// mempool.
+    pub fn next_submission_round(&self) -> Round {
+        let produced = self.model.producer.next_submission_round(self.model.round);
+        self.model.next_scheduled_round().map_or(produced, |scheduled| scheduled.min(produced))
+    }
+}
+
+impl Model {
+    /// Run the model for as many rounds as the policy decides.
+    ///
+    /// Rounds that the policy skips still count. The clock moves on, so all
+    /// later rounds carry the same numbers as without skipping, and each
+    /// shard records an empty chunk for every skipped round. Strategies and
+    /// the producer are not called for skipped rounds.
+    ///
+    /// Panics if the policy goes back in time or skips rounds while the model
+    /// is not idle.
+    pub fn run(&mut self, policy: &mut dyn RoundPolicy) {
+        while let Some(next) = policy.next_round(&self.state()) {
+            assert!(next > self.round, "round policy went back from {} to {next}", self.round);
+            if next > self.round + 1 {
+                assert!(self.state().is_idle(), "round policy skipped rounds with pending work");
+            }
+            while self.round + 1 < next {
+                self.skip_idle_round();
+            }
+            self.step();
+        }
+    }
+
+    pub fn state(&self) -> ModelState<'_> {
+        ModelState { model: self }
+    }
+
+    /// Advance the clock by one round in which nothing happens.
+    fn skip_idle_round(&mut self) {
+        self.round += 1;
+        let round_stats = (0..self.shard_ids.len())
+            .map(|i| ChunkStats::idle(self.round, ShardId(i), self.chunk_gas_limit(i)))
+            .collect();
+        self.record_chunk_stats(round_stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::{BalancedProducer, ScaledProducer};
+    use crate::{CongestionStrategy, ReceiptRecord};
+
+    fn sparse_model() -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let workload = ScaledProducer::new(Box::<BalancedProducer>::default(), 0.02);
+        Model::new(shards, Box::new(workload)).with_seed(3)
+    }
+
+    /// Counts the rounds the inner policy lets the model execute.
+    struct Counting<P>(P, usize);
+
+    impl<P: RoundPolicy> RoundPolicy for Counting<P> {
+        fn next_round(&mut self, state: &ModelState) -> Option<Round> {
+            let next = self.0.next_round(state);
+            self.1 += next.is_some() as usize;
+            next
+        }
+    }
+
+    fn timestamps(model: &Model) -> Vec<(Round, Vec<ReceiptRecord>)> {
+        model.transaction_views().map(|view| (view.submitted_at(), view.receipts())).collect()
+    }
+
+    #[test]
+    fn event_driven_matches_fixed_step() {
+        let mut fixed = sparse_model();
+        let mut fixed_policy = Counting(FixedStep { last_round: 300 }, 0);
+        fixed.run(&mut fixed_policy);
+        let mut skipping = sparse_model();
+        let mut event_policy = Counting(EventDriven { last_round: 300 }, 0);
+        skipping.run(&mut event_policy);
+
+        assert_eq!(fixed_policy.1, 300);
+        assert!(event_policy.1 < 150, "executed {} rounds", event_policy.1);
+        assert_eq!(skipping.round, 300);
+        assert_eq!(skipping.chunk_stats.len(), fixed.chunk_stats.len());
+        assert_eq!(skipping.latency_stats(), fixed.latency_stats());
+        assert_eq!(skipping.gas_throughput().total, fixed.gas_throughput().total);
+        assert_eq!(skipping.congestion_index(), fixed.congestion_index());
+        assert_eq!(timestamps(&skipping), timestamps(&fixed));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{GGas, ReceiptId, Round, ShardId, TransactionId};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Model-internal representation of a transaction, as in, the entire graph of
 /// receipts generated by one transaction submitted to the chain.
@@ -22,6 +22,34 @@ pub(crate) struct Transaction {
     pub(crate) tx_conversion_cost: GGas,
     /// Gas attached to the first receipt.
     pub(crate) initial_receipt_gas: GGas,
+    /// Priority of the first receipt, inherited by all receipts after it.
+    pub(crate) priority: ReceiptPriority,
+    pub(crate) execution_mode: ExecutionMode,
+    /// With [`ExecutionMode::Sequential`], receipts whose predecessor
+    /// executed but which wait for the pending receipt to finish. In the
+    /// order they will be created.
+    pub(crate) sequential_backlog: VecDeque<ReceiptId>,
+    /// What the sender pays for inclusion, only used to pick transactions for
+    /// mempool eviction.
+    pub(crate) fee: u64,
+    /// Set if the transaction was evicted from the mempool before conversion.
+    pub(crate) evicted_at: Option<Round>,
+    /// Declared size in bytes, checked against
+    /// [`crate::ModelConfig::max_tx_size`] at conversion.
+    pub(crate) size: u64,
+    /// Set if the sender shard refused to convert the transaction.
+    pub(crate) rejection: Option<Rejection>,
+    /// Receipts removed from the DAG when the transaction was built, see
+    /// [`crate::TransactionBuilder::with_max_receipts`].
+    pub(crate) truncated_receipts: usize,
+    /// Conversion cost plus the gas attached to all receipts of the
+    /// transaction and their send costs, fixed at submission.
+    pub(crate) total_attached_gas: GGas,
+    /// Send costs paid so far, see [`Receipt::send_cost`].
+    pub(crate) send_gas_burnt: GGas,
+    /// Priority gas attached to each receipt, see
+    /// [`Receipt::attached_priority_gas`].
+    pub(crate) priority_gas: GGas,
 
     /// Definition of directed edges of the DAG.
     pub(crate) outgoing: HashMap<ReceiptId, Vec<ReceiptId>>,
@@ -43,6 +71,9 @@ pub(crate) struct Transaction {
     pub(crate) dropped_receipts: HashMap<ReceiptId, Receipt>,
     /// Receipts that have finished execution.
     pub(crate) executed_receipts: HashMap<ReceiptId, Receipt>,
+    /// Receipts marked with [`crate::TransactionBuilder::mark_yield`], with
+    /// the receipts they create on timeout instead of their outgoing ones.
+    pub(crate) yield_timeouts: HashMap<ReceiptId, Vec<ReceiptId>>,
 }
 
 #[must_use = "Forward, explicitly drop, or put receipts in a queue."]
@@ -53,13 +84,99 @@ pub struct Receipt {
     pub dropped_at: Option<Round>,
     pub executed_at: Option<Round>,
     pub receiver: ShardId,
+    /// Sender shard of the transaction that spawned this receipt, the same
+    /// for all receipts of a transaction.
+    pub origin_shard: ShardId,
     pub size: u64,
     pub attached_gas: GGas,
+    /// Set when the receipt is created, from the transaction for the first
+    /// receipt and from the executed predecessor for all others.
+    pub priority: ReceiptPriority,
+    /// Read-only receipts burn all their attached gas, create no other
+    /// receipts and never leave the shard that created them. See
+    /// [`crate::TransactionBuilder::mark_read_only`].
+    pub read_only: bool,
+    /// Set for receipts created with
+    /// [`crate::TransactionBuilder::new_refund_receipt`]. The model treats
+    /// refunds like all other receipts.
+    pub refund: bool,
+    /// Set for receipts created with
+    /// [`crate::TransactionBuilder::new_refund_callback`], which a refund
+    /// creates when it executes.
+    pub refund_callback: bool,
+    /// Account the receipt is addressed to, if the workload assigns accounts.
+    /// Its shard is [`Receipt::receiver`]. See
+    /// [`crate::TransactionBuilder::set_receiver_account`].
+    pub receiver_account: Option<String>,
+    /// Gas the sender burns when it forwards the receipt to another shard,
+    /// for serializing it. Charged once, in addition to
+    /// [`crate::ModelConfig::forwarding_cost`], and paid from the gas of the
+    /// transaction. Refunded if the receipt never leaves its shard. See
+    /// [`crate::TransactionBuilder::set_send_cost`].
+    pub send_cost: GGas,
+    /// Bytes the receipt adds to the state witness of the chunk that executes
+    /// it, see [`crate::ModelConfig::max_witness_per_chunk`]. Defaults to
+    /// `size`, see [`crate::TransactionBuilder::set_witness_cost`].
+    pub witness_cost: u64,
+    /// Whether the sender burnt the send cost.
+    pub(crate) send_cost_paid: bool,
+    /// Gas paid on top of the attached gas to be executed earlier, see
+    /// [`crate::TransactionBuilder::with_priority_gas`]. Burnt when the
+    /// receipt executes, never refunded, and not counted against the chunk's
+    /// gas limit.
+    pub attached_priority_gas: GGas,
 
     // private to the shards until after the execution
     execution_gas: GGas,
 }
 
+/// How urgently a receipt should be executed. Strategies decide what to do
+/// with it, the model only passes it down the receipt DAG.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReceiptPriority {
+    #[default]
+    Normal,
+    High,
+}
+
+/// A sender shard refusing to convert a transaction, see
+/// [`crate::ModelConfig::max_tx_size`] and
+/// [`crate::ModelConfig::max_outstanding_receipts_per_tx`].
+///
+/// A rejected transaction never creates its first receipt and counts as
+/// failed. It completes in the round it is rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rejection {
+    pub round: Round,
+    pub reason: RejectionReason,
+    /// Share of the conversion cost burnt by the sender shard, see
+    /// [`crate::ModelConfig::rejected_tx_cost_fraction`].
+    pub gas_burnt: GGas,
+}
+
+/// Why a sender shard rejected a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The declared size exceeds [`crate::ModelConfig::max_tx_size`].
+    TooLarge { size: u64, max_tx_size: u64 },
+    /// The DAG has more receipts than
+    /// [`crate::ModelConfig::max_outstanding_receipts_per_tx`].
+    TooManyReceipts { receipts: usize, max_receipts: usize },
+}
+
+/// How the receipts of a transaction are scheduled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// All receipts created by an execution are sent out at once and can
+    /// execute in the same round.
+    #[default]
+    Parallel,
+    /// Only one receipt of the transaction is pending at any time, like a
+    /// chain of calls. Receipts are created in depth-first order of the DAG,
+    /// each one after the previous one executed.
+    Sequential,
+}
+
 pub(crate) struct ExecutionResult {
     pub gas_burnt: GGas,
     pub new_receipts: Vec<Receipt>,
@@ -70,33 +187,102 @@ impl Transaction {
         let receipt = self
             .activate_receipt(self.initial_receipt, round)
             .expect("should not start the same transaction twice");
+        let receipt = Receipt { priority: self.priority, ..receipt };
         self.pending_receipts.insert(self.initial_receipt);
         ExecutionResult { gas_burnt: self.tx_conversion_cost, new_receipts: vec![receipt] }
     }
 
+    /// Refuse to convert the transaction, burning `cost_fraction` of its
+    /// conversion cost. Returns the gas burnt.
+    pub(crate) fn reject(
+        &mut self,
+        round: Round,
+        reason: RejectionReason,
+        cost_fraction: f64,
+    ) -> GGas {
+        assert!(self.rejection.is_none(), "should not reject the same transaction twice");
+        let gas_burnt = (self.tx_conversion_cost as f64 * cost_fraction) as GGas;
+        self.rejection = Some(Rejection { round, reason, gas_burnt });
+        gas_burnt
+    }
+
     pub(crate) fn execute_receipt(
         &mut self,
         mut receipt: Receipt,
         round: Round,
     ) -> ExecutionResult {
-        let outgoing_ids = self.outgoing[&receipt.id].clone();
-        let new_receipts = outgoing_ids
-            .into_iter()
-            .map(|receipt_id| {
-                self.activate_receipt(receipt_id, round)
-                    .expect("must not create the same receipt multiple times")
-            })
-            .collect();
-
+        let id = receipt.id;
         let gas_burnt = receipt.execution_gas;
+        let priority = receipt.priority;
         receipt.executed_at = Some(round);
+        self.executed_receipts.insert(id, receipt);
 
-        self.pending_receipts.remove(&receipt.id);
-        self.executed_receipts.insert(receipt.id, receipt);
-
+        if self.yields(id) {
+            // stays pending until resumed or timed out
+            return ExecutionResult { gas_burnt, new_receipts: vec![] };
+        }
+        self.pending_receipts.remove(&id);
+        let outgoing_ids = self.outgoing[&id].clone();
+        let new_receipts = self.create_children(outgoing_ids, round, priority);
         ExecutionResult { gas_burnt, new_receipts }
     }
 
+    /// Whether the receipt yields when it executes, see
+    /// [`crate::TransactionBuilder::mark_yield`].
+    pub(crate) fn yields(&self, receipt_id: ReceiptId) -> bool {
+        self.yield_timeouts.contains_key(&receipt_id)
+    }
+
+    /// Continue after an executed yield, with its outgoing receipts if it was
+    /// resumed or with its timeout receipts if it timed out.
+    pub(crate) fn resume(
+        &mut self,
+        receipt_id: ReceiptId,
+        round: Round,
+        timed_out: bool,
+    ) -> Vec<Receipt> {
+        assert!(self.pending_receipts.remove(&receipt_id), "receipt {receipt_id:?} is not yielded");
+        let priority = self.executed_receipts[&receipt_id].priority;
+        let children = if timed_out {
+            self.yield_timeouts[&receipt_id].clone()
+        } else {
+            self.outgoing[&receipt_id].clone()
+        };
+        self.create_children(children, round, priority)
+    }
+
+    /// Create the receipts that follow an executed receipt.
+    fn create_children(
+        &mut self,
+        children: Vec<ReceiptId>,
+        round: Round,
+        priority: ReceiptPriority,
+    ) -> Vec<Receipt> {
+        let new_receipts: Vec<Receipt> = match self.execution_mode {
+            ExecutionMode::Parallel => children
+                .into_iter()
+                .map(|receipt_id| {
+                    self.activate_receipt(receipt_id, round)
+                        .expect("must not create the same receipt multiple times")
+                })
+                .collect(),
+            ExecutionMode::Sequential => {
+                // depth-first: the children of this receipt go next
+                for &receipt_id in children.iter().rev() {
+                    self.sequential_backlog.push_front(receipt_id);
+                }
+                // Receipts reachable on multiple paths are only created once.
+                let mut next = None;
+                while next.is_none() {
+                    let Some(receipt_id) = self.sequential_backlog.pop_front() else { break };
+                    next = self.activate_receipt(receipt_id, round);
+                }
+                next.into_iter().collect()
+            }
+        };
+        new_receipts.into_iter().map(|new_receipt| Receipt { priority, ..new_receipt }).collect()
+    }
+
     pub(crate) fn drop_receipt(&mut self, mut receipt: Receipt, round: Round) {
         self.pending_receipts.remove(&receipt.id);
         receipt.dropped_at = Some(round);
@@ -114,6 +300,51 @@ impl Transaction {
         Some(receipt)
     }
 
+    /// Fewest rounds from submission to completion without any congestion.
+    ///
+    /// Each receipt executes in the round after it was created, so this is
+    /// the number of receipts on the longest path through the DAG, or the
+    /// number of all receipts for [`ExecutionMode::Sequential`].
+    pub(crate) fn min_delay(&self) -> Round {
+        match self.execution_mode {
+            ExecutionMode::Sequential => self.depth_first_receipts().len() as Round,
+            ExecutionMode::Parallel => {
+                let mut depths = HashMap::new();
+                self.depth(self.initial_receipt, &mut depths)
+            }
+        }
+    }
+
+    fn depth(&self, id: ReceiptId, known: &mut HashMap<ReceiptId, Round>) -> Round {
+        if let Some(&depth) = known.get(&id) {
+            return depth;
+        }
+        let children = self.outgoing[&id].iter();
+        let depth = 1 + children.map(|&child| self.depth(child, known)).max().unwrap_or(0);
+        known.insert(id, depth);
+        depth
+    }
+
+    /// All receipts in the order sequential execution creates them, see
+    /// [`ExecutionMode::Sequential`].
+    pub(crate) fn depth_first_receipts(&self) -> Vec<ReceiptId> {
+        let mut order = vec![];
+        let mut visited = HashSet::new();
+        let mut stack = vec![self.initial_receipt];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            order.push(id);
+            // the timeout branch of a yield goes after the resume branch
+            if let Some(timeouts) = self.yield_timeouts.get(&id) {
+                stack.extend(timeouts.iter().rev());
+            }
+            stack.extend(self.outgoing[&id].iter().rev());
+        }
+        order
+    }
+
     pub(crate) fn initial_receipt_receiver(&self) -> ShardId {
         self.initial_receipt_receiver
     }
@@ -127,6 +358,7 @@ impl Receipt {
     pub fn new_future_receipt(
         id: ReceiptId,
         receiver: ShardId,
+        origin_shard: ShardId,
         size: u64,
         attached_gas: GGas,
         execution_gas: GGas,
@@ -137,8 +369,18 @@ impl Receipt {
             dropped_at: None,
             executed_at: None,
             receiver,
+            origin_shard,
             size,
             attached_gas,
+            priority: ReceiptPriority::Normal,
+            read_only: false,
+            refund: false,
+            refund_callback: false,
+            receiver_account: None,
+            send_cost: 0,
+            witness_cost: size,
+            send_cost_paid: false,
+            attached_priority_gas: 0,
             execution_gas,
         }
     }
@@ -147,6 +389,20 @@ impl Receipt {
         self.id.transaction_id()
     }
 
+    /// Gas the receipt burns when executed. Not visible to strategies.
+    pub(crate) fn execution_gas(&self) -> GGas {
+        self.execution_gas
+    }
+
+    /// Send cost that was not burnt, yet.
+    pub(crate) fn unpaid_send_cost(&self) -> GGas {
+        if self.send_cost_paid {
+            0
+        } else {
+            self.send_cost
+        }
+    }
+
     pub(crate) fn gas_burnt(&self) -> GGas {
         if self.executed_at.is_some() {
             self.execution_gas
@@ -155,3 +411,153 @@ impl Receipt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ChunkExecutionContext;
+    use crate::strategy::QueueFactory;
+    use crate::workload::Producer;
+    use crate::{CongestionStrategy, Model, ReceiptDefinition, TransactionBuilder, TGAS};
+    use rand::RngCore;
+
+    /// Converts all transactions and executes high priority receipts first.
+    struct HighPriorityFirst;
+
+    impl CongestionStrategy for HighPriorityFirst {
+        fn init(&mut self, _id: ShardId, _other: &[ShardId], _queues: &mut dyn QueueFactory) {}
+
+        fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+            while let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let receipt = ctx.accept_transaction(tx);
+                ctx.forward_receipt(receipt);
+            }
+            // stable sort keeps FIFO order within a priority
+            ctx.incoming_receipts()
+                .make_contiguous()
+                .sort_by_key(|r| std::cmp::Reverse(r.priority));
+            while ctx.gas_burnt() < ctx.gas_limit() {
+                let Some(receipt) = ctx.incoming_receipts().pop_front() else { break };
+                for receipt in ctx.execute_receipt(receipt) {
+                    ctx.forward_receipt(receipt);
+                }
+            }
+        }
+    }
+
+    /// Overloads every shard with local receipts and submits a single high
+    /// priority transaction in round 3 that hops over all shards.
+    struct BackgroundAndChain;
+
+    impl Producer for BackgroundAndChain {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let receipt = |receiver, gas| ReceiptDefinition {
+                receiver,
+                size: 100,
+                attached_gas: gas,
+                execution_gas: gas,
+            };
+            let mut out = vec![];
+            for &shard in shards {
+                for _ in 0..30 {
+                    let mut tx = tx_factory(shard);
+                    tx.add_first_receipt(receipt(shard, 100 * TGAS), 0);
+                    out.push(tx);
+                }
+            }
+            if round == 3 {
+                let mut tx = tx_factory(shards[0]).with_priority(ReceiptPriority::High);
+                let mut prev = tx.add_first_receipt(receipt(shards[0], TGAS), 0);
+                for &shard in &shards[1..] {
+                    prev = tx.new_outgoing_receipt(prev, receipt(shard, TGAS));
+                }
+                out.push(tx);
+            }
+            out
+        }
+    }
+
+    #[test]
+    fn children_inherit_priority() {
+        let shards = (0..3).map(|_| Box::new(HighPriorityFirst) as _).collect();
+        let mut model = Model::new(shards, Box::new(BackgroundAndChain));
+        for _ in 0..10 {
+            model.step();
+        }
+
+        let (high, normal): (Vec<_>, Vec<_>) = model
+            .transactions
+            .all_transactions()
+            .filter(|tx| tx.submitted_at == 3)
+            .partition(|tx| tx.priority == ReceiptPriority::High);
+        let high = high[0];
+
+        // every hop executes in the round after its receipt was created
+        assert_eq!(high.executed_receipts.len(), 3);
+        for receipt in high.executed_receipts.values() {
+            assert_eq!(receipt.priority, ReceiptPriority::High);
+            assert_eq!(receipt.executed_at, receipt.created_at.map(|round| round + 1));
+        }
+        assert_eq!(high.delay(), 3);
+        assert!(normal.iter().all(|tx| tx.executed_receipts.is_empty() || tx.delay() > 3));
+    }
+
+    /// Submits one transaction in round 1 whose first receipt fans out to
+    /// two receipts on different shards.
+    struct FanOut(ExecutionMode);
+
+    impl Producer for FanOut {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round != 1 {
+                return vec![];
+            }
+            let receipt = |receiver| ReceiptDefinition {
+                receiver,
+                size: 100,
+                attached_gas: TGAS,
+                execution_gas: TGAS,
+            };
+            let mut tx = tx_factory(shards[0]).with_execution_mode(self.0);
+            let first = tx.add_first_receipt(receipt(shards[0]), 0);
+            tx.new_outgoing_receipt(first, receipt(shards[0]));
+            tx.new_outgoing_receipt(first, receipt(shards[1]));
+            vec![tx]
+        }
+    }
+
+    #[test]
+    fn sequential_receipts_execute_one_per_round() {
+        let delay = |mode| {
+            let shards = (0..2).map(|_| Box::new(HighPriorityFirst) as _).collect();
+            let mut model = Model::new(shards, Box::new(FanOut(mode)));
+            for _ in 0..6 {
+                model.step();
+                let tx = model.transactions.all_transactions().next().unwrap();
+                let max_pending = if mode == ExecutionMode::Sequential { 1 } else { 2 };
+                assert!(tx.pending_receipts.len() <= max_pending);
+            }
+            let tx = model.transactions.all_transactions().next().unwrap();
+            assert_eq!(tx.executed_receipts.len(), 3);
+            assert_eq!(model.optimal_completion_bound(), tx.last_change());
+            tx.delay()
+        };
+        assert_eq!(delay(ExecutionMode::Parallel), 2);
+        assert_eq!(delay(ExecutionMode::Sequential), 3);
+    }
+}
@@ -1,23 +1,36 @@
 use crate::{Round, ShardId, Transaction, TransactionBuilder};
+use std::collections::VecDeque;
 
 #[derive(Default)]
 /// Stores all transactions of a model execution and assigns TransactionIds.
 pub(crate) struct TransactionRegistry {
-    /// Append-only list of all transactions in the system. More are added every
-    /// round.
+    /// List of all transactions in the system. More are added every round.
     ///
-    /// An `Option::None` value means the transaction ID has been reserved but
-    /// the transaction was not built, yet.
+    /// Transactions are only removed by [`TransactionRegistry::prune`]. Pruned
+    /// transactions at the front are popped, the others leave a
+    /// [`Slot::Pruned`] behind, until all transactions before them are gone.
     ///
     /// Invariant: Can be indexed by any TransactionId created for this
-    /// TransactionRegistry.
+    /// TransactionRegistry, minus `first`.
     ///
     /// Note: This code could be simplified by using a `HashMap<TransactionId,
     /// Transaction>` instead. But we avoid expensive rehashing as we add more
     /// transactions. And we avoid pointer indirection by keeping the memory
     /// continuous. This optimization seems justified since there could be
     /// millions, if not billions, of transactions in a model execution.
-    transactions: Vec<Option<Transaction>>,
+    transactions: VecDeque<Slot>,
+    /// Number of pruned transactions popped from the front of `transactions`.
+    first: usize,
+}
+
+// Boxing would add the pointer indirection the registry avoids, see above.
+#[allow(clippy::large_enum_variant)]
+enum Slot {
+    /// The transaction ID has been reserved but the transaction was not
+    /// built, yet.
+    Reserved,
+    Built(Transaction),
+    Pruned,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -38,19 +51,55 @@ impl TransactionRegistry {
 
     pub(crate) fn build_transaction(&mut self, builder: TransactionBuilder) -> TransactionId {
         let id = builder.id;
-        assert!(self.transactions[id.0].is_none(), "transaction should only be built once");
-        self.transactions[id.0] = Some(builder.build());
+        let slot = &mut self.transactions[id.0 - self.first];
+        assert!(matches!(slot, Slot::Reserved), "transaction should only be built once");
+        *slot = Slot::Built(builder.build());
         id
     }
 
     fn next_id(&mut self) -> TransactionId {
-        let index = self.transactions.len();
-        self.transactions.push(None);
+        let index = self.first + self.transactions.len();
+        self.transactions.push_back(Slot::Reserved);
         TransactionId(index)
     }
 
+    /// Remove a completed transaction for good and return it.
+    ///
+    /// Accessing the transaction afterwards panics.
+    pub(crate) fn prune(&mut self, id: TransactionId) -> Transaction {
+        let slot = std::mem::replace(self.slot_mut(id), Slot::Pruned);
+        let Slot::Built(tx) = slot else { panic!("tried to prune unfinished transaction") };
+        while matches!(self.transactions.front(), Some(Slot::Pruned)) {
+            self.transactions.pop_front();
+            self.first += 1;
+        }
+        tx
+    }
+
+    /// Remove all transactions, keeping the allocated capacity.
+    pub(crate) fn clear(&mut self) {
+        self.transactions.clear();
+        self.first = 0;
+    }
+
+    /// All transactions that have been built and not pruned.
     pub(crate) fn all_transactions(&self) -> impl Iterator<Item = &Transaction> {
-        self.transactions.iter().filter_map(|id| id.as_ref())
+        self.transactions.iter().filter_map(|slot| match slot {
+            Slot::Built(tx) => Some(tx),
+            Slot::Reserved | Slot::Pruned => None,
+        })
+    }
+
+    pub(crate) fn all_transactions_mut(&mut self) -> impl Iterator<Item = &mut Transaction> {
+        self.transactions.iter_mut().filter_map(|slot| match slot {
+            Slot::Built(tx) => Some(tx),
+            Slot::Reserved | Slot::Pruned => None,
+        })
+    }
+
+    fn slot_mut(&mut self, id: TransactionId) -> &mut Slot {
+        assert!(id.0 >= self.first, "tried to access pruned transaction");
+        &mut self.transactions[id.0 - self.first]
     }
 }
 
@@ -58,14 +107,23 @@ impl std::ops::Index<TransactionId> for TransactionRegistry {
     type Output = Transaction;
 
     fn index(&self, index: TransactionId) -> &Self::Output {
-        // the model should always finish creating transactions before it starts executing them
-        self.transactions[index.0].as_ref().expect("tried to access unfinished transaction")
+        assert!(index.0 >= self.first, "tried to access pruned transaction");
+        match &self.transactions[index.0 - self.first] {
+            Slot::Built(tx) => tx,
+            // the model should always finish creating transactions before it starts executing them
+            Slot::Reserved => panic!("tried to access unfinished transaction"),
+            Slot::Pruned => panic!("tried to access pruned transaction"),
+        }
     }
 }
 
 impl std::ops::IndexMut<TransactionId> for TransactionRegistry {
     fn index_mut(&mut self, index: TransactionId) -> &mut Self::Output {
-        // the model should always finish creating transactions before it starts executing them
-        self.transactions[index.0].as_mut().expect("tried to access unfinished transaction")
+        match self.slot_mut(index) {
+            Slot::Built(tx) => tx,
+            // the model should always finish creating transactions before it starts executing them
+            Slot::Reserved => panic!("tried to access unfinished transaction"),
+            Slot::Pruned => panic!("tried to access pruned transaction"),
+        }
     }
 }
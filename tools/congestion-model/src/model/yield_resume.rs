@@ -0,0 +1,183 @@
+use super::{Model, ShardId, YieldConfig};
+use crate::{ReceiptId, Round, TransactionId};
+use rand::Rng;
+
+/// Executed receipts waiting for resume or timeout, see
+/// [`crate::ModelConfig::yield_resume`].
+#[derive(Default)]
+pub(crate) struct YieldState {
+    /// In the order the receipts yielded.
+    waiting: Vec<Yielded>,
+}
+
+struct Yielded {
+    receipt: ReceiptId,
+    shard: ShardId,
+    yielded_at: Round,
+}
+
+impl YieldState {
+    /// Start waiting for receipts that yielded on the shard in this round.
+    pub(super) fn yielded(&mut self, shard: ShardId, round: Round, receipts: Vec<ReceiptId>) {
+        let new = receipts.into_iter().map(|receipt| Yielded { receipt, shard, yielded_at: round });
+        self.waiting.extend(new);
+    }
+
+    /// Number of receipts waiting on the shard.
+    pub(super) fn pending_on(&self, shard: ShardId) -> usize {
+        self.waiting.iter().filter(|y| y.shard == shard).count()
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.waiting.len()
+    }
+}
+
+/// What happened to yielded receipts at the start of a round.
+#[derive(Default)]
+pub(super) struct YieldOutcome {
+    /// Transactions without receipts left after their yield ended.
+    pub(super) completed: Vec<TransactionId>,
+    /// Number of receipts that timed out, per shard.
+    pub(super) timed_out: Vec<usize>,
+}
+
+impl Model {
+    /// Resume or time out yielded receipts, before the chunks of the round.
+    ///
+    /// Both branches continue on the shard of the yield, so the created
+    /// receipts go straight to its incoming queue and can execute in this
+    /// round.
+    pub(super) fn resume_yields(&mut self) -> YieldOutcome {
+        let mut outcome =
+            YieldOutcome { completed: vec![], timed_out: vec![0; self.shard_ids.len()] };
+        if self.yields.waiting.is_empty() {
+            return outcome;
+        }
+        let YieldConfig { resume_probability, timeout } = self.config.yield_resume.clone();
+        let waiting = std::mem::take(&mut self.yields.waiting);
+        for y in waiting {
+            let timed_out = self.round - y.yielded_at >= timeout;
+            if !timed_out && !self.yield_rng.gen_bool(resume_probability) {
+                self.yields.waiting.push(y);
+                continue;
+            }
+            if timed_out {
+                outcome.timed_out[y.shard.0] += 1;
+            }
+            let tx = y.receipt.transaction_id();
+            for receipt in self.transactions[tx].resume(y.receipt, self.round, timed_out) {
+                self.queues.incoming_receipts_mut(receipt.receiver).push_back(receipt);
+            }
+            if !self.transactions[tx].is_in_flight() {
+                outcome.completed.push(tx);
+            }
+        }
+        outcome
+    }
+
+    /// Number of yielded receipts currently waiting, on all shards.
+    pub fn pending_yields(&self) -> usize {
+        self.yields.len()
+    }
+
+    /// Number of yielded receipts that timed out so far, on all shards.
+    pub fn timed_out_yields(&self) -> usize {
+        let live: usize = self.chunk_stats.iter().map(|stats| stats.timed_out_yields).sum();
+        self.retired_chunks.timed_out_yields + live
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::TransactionStatus;
+    use crate::strategy::NoQueueShard;
+    use crate::workload::Producer;
+    use crate::{
+        CongestionStrategy, Model, ModelConfig, ReceiptDefinition, ReceiptState, Round, ShardId,
+        TransactionBuilder, YieldConfig, TGAS,
+    };
+    use rand::RngCore;
+
+    /// A single yield on one shard in the first round, with a 20 TGas
+    /// callback on resume and a 5 TGas callback on timeout.
+    struct OneYield;
+
+    impl Producer for OneYield {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            if round > 1 {
+                return vec![];
+            }
+            let receipt = |execution_gas| ReceiptDefinition {
+                receiver: shards[0],
+                size: 100,
+                attached_gas: 100 * TGAS,
+                execution_gas,
+            };
+            let mut tx = tx_factory(shards[0]);
+            let call = tx.add_first_receipt(receipt(10 * TGAS), 0);
+            tx.new_outgoing_receipt(call, receipt(20 * TGAS));
+            tx.new_timeout_receipt(call, receipt(5 * TGAS));
+            vec![tx]
+        }
+    }
+
+    fn run(resume_probability: f64) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> = vec![Box::new(NoQueueShard {})];
+        let yield_resume = YieldConfig { resume_probability, timeout: 5 };
+        let config = ModelConfig { yield_resume, ..ModelConfig::default() };
+        let mut model = Model::new(shards, Box::new(OneYield)).with_config(config);
+        for _ in 0..10 {
+            model.step();
+        }
+        model
+    }
+
+    /// Callback receipts of the only transaction, as resume and timeout state.
+    fn branches(model: &Model) -> (ReceiptState, ReceiptState) {
+        let view = model.transaction_views().next().unwrap();
+        assert_eq!(view.status(), TransactionStatus::FinishedSuccess);
+        let receipts = view.receipts();
+        (receipts[1].state(), receipts[2].state())
+    }
+
+    #[test]
+    fn yield_waits_without_gas_until_timeout() {
+        let model = run(0.0);
+        let stats = &model.chunk_stats;
+        // converted in round 1, yields in round 2
+        assert_eq!(stats[1].gas_burnt, 10 * TGAS);
+        for waiting in &stats[1..6] {
+            assert_eq!(waiting.pending_yields, 1);
+            assert_eq!(waiting.timed_out_yields, 0);
+        }
+        for waiting in &stats[2..6] {
+            assert_eq!(waiting.gas_burnt, 0);
+            assert_eq!(waiting.queued_gas, 0);
+        }
+        // times out 5 rounds after the yield
+        assert_eq!(stats[6].timed_out_yields, 1);
+        assert_eq!(stats[6].pending_yields, 0);
+        assert_eq!(stats[6].gas_burnt, 5 * TGAS);
+        assert_eq!(model.timed_out_yields(), 1);
+        assert_eq!(branches(&model), (ReceiptState::Future, ReceiptState::Executed));
+    }
+
+    #[test]
+    fn resume_creates_the_callback() {
+        let model = run(1.0);
+        let stats = &model.chunk_stats;
+        assert_eq!(stats[2].gas_burnt, 20 * TGAS);
+        assert_eq!(stats[2].pending_yields, 0);
+        assert_eq!(model.timed_out_yields(), 0);
+        assert_eq!(branches(&model), (ReceiptState::Executed, ReceiptState::Future));
+    }
+}
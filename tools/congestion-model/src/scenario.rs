@@ -0,0 +1,558 @@
+use crate::{
+    AccountGroupedQueue, AckConfig, EpochSchedule, GGas, MempoolEviction, ModelConfig,
+    ReceiptCapPolicy, Round, YieldConfig, TGAS,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Scenario format version read by this build.
+pub const SCENARIO_VERSION: u64 = 1;
+
+/// A complete experiment in one file: shards, model parameters, strategy,
+/// workload, seed and number of rounds.
+///
+/// Scenarios are TOML files.
+///
+/// ```toml
+/// version = 1
+/// shards = 4
+/// rounds = 1000
+/// seed = 7
+///
+/// [model]
+/// gas_limit = 1000                        # TGas per chunk
+/// gas_limit_schedule = [[500, 1500]]      # [round, TGas] pairs
+/// drop_probability = 0.01
+///
+/// [strategy]
+/// name = "NEP"
+/// global_stop_limit = 0.9
+///
+/// [workload]
+/// name = "Balanced"
+/// whale_probability = 0.05
+/// ```
+///
+/// `version` must be [`SCENARIO_VERSION`], `rounds` and the `name` of
+/// strategy and workload are required. All other keys default like the
+/// command line arguments of the same name. The keys of `[model]` correspond
+/// to [`ModelConfig`] and use the same units as the command line, GGas for
+/// gas costs and TGas for gas limits. Unknown keys are rejected, so typos do
+/// not silently fall back to defaults.
+///
+/// Which strategies and workloads exist, and which further keys they take,
+/// is up to the caller building them. It can read those keys with
+/// [`Component::take_f64`] and friends, and reject the rest with
+/// [`Component::finish`].
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub shards: usize,
+    pub rounds: usize,
+    pub seed: u64,
+    pub config: ModelConfig,
+    pub strategy: Component,
+    pub workload: Component,
+}
+
+/// A strategy or workload chosen by name, with the remaining keys of its
+/// section as parameters.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Component {
+    pub name: String,
+    #[serde(flatten)]
+    params: BTreeMap<String, toml::Value>,
+    /// Section the component was read from, for error messages.
+    #[serde(skip)]
+    section: &'static str,
+}
+
+/// Problem with a scenario file, pointing at the offending position or field
+/// where possible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScenarioError {
+    /// Line in the file, starting at 1.
+    pub line: Option<usize>,
+    /// Column in the line, starting at 1.
+    pub column: Option<usize>,
+    /// Dotted path of the field, like `model.drop_probability`.
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// Only the version, read before the rest so that files of other versions
+/// fail on it rather than on keys this build does not know.
+#[derive(Deserialize)]
+struct Versioned {
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    #[allow(dead_code)]
+    version: u64,
+    #[serde(default = "default_shards")]
+    shards: usize,
+    rounds: usize,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    model: ModelSection,
+    strategy: Component,
+    workload: Component,
+}
+
+fn default_shards() -> usize {
+    4
+}
+
+/// The `[model]` section, before validation. Gas limits are in TGas.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ModelSection {
+    forward_gas: Option<GGas>,
+    forward_gas_per_byte: Option<GGas>,
+    gas_limit: Option<u64>,
+    gas_limit_schedule: Vec<(Round, u64)>,
+    global_gas_limit: Option<u64>,
+    shard_order: Option<String>,
+    max_receipt_size: Option<u64>,
+    max_tx_size: Option<u64>,
+    max_witness_per_chunk: Option<u64>,
+    rejected_tx_cost_fraction: Option<f64>,
+    max_outstanding_receipts_per_tx: Option<usize>,
+    receipt_cap_policy: Option<String>,
+    account_switch_gas: Option<GGas>,
+    account_grouping_lookahead: Option<usize>,
+    drop_probability: Option<f64>,
+    gas_limit_jitter: Option<f64>,
+    mempool_capacity: Option<usize>,
+    mempool_eviction: Option<String>,
+    chunk_apply_lag: bool,
+    prune_completed: bool,
+    congestion_threshold: Option<f64>,
+    ack_size: Option<u64>,
+    ack_gas: Option<GGas>,
+    retransmit_timeout: Option<Round>,
+    resume_probability: Option<f64>,
+    yield_timeout: Option<Round>,
+    epoch_length: Option<Round>,
+    validator_group_performance: Option<Vec<f64>>,
+}
+
+impl Scenario {
+    /// Read and validate a scenario file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|err| ScenarioError {
+            line: None,
+            column: None,
+            field: None,
+            message: format!("cannot read {}: {err}", path.display()),
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Parse and validate the contents of a scenario file.
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        let Versioned { version } = toml::from_str(text)?;
+        let Some(version) = version else {
+            return Err(invalid("version", "missing".to_owned()));
+        };
+        if version != SCENARIO_VERSION {
+            let message = format!("unsupported version {version}, expected {SCENARIO_VERSION}");
+            return Err(invalid("version", message));
+        }
+
+        let mut file: ScenarioFile = toml::from_str(text)?;
+        if file.shards == 0 {
+            return Err(invalid("shards", "must be at least 1".to_owned()));
+        }
+        let config = model_config(file.model, file.shards)?;
+        file.strategy.section = "strategy";
+        file.workload.section = "workload";
+        Ok(Self {
+            shards: file.shards,
+            rounds: file.rounds,
+            seed: file.seed,
+            config,
+            strategy: file.strategy,
+            workload: file.workload,
+        })
+    }
+}
+
+impl Component {
+    /// Remove a number parameter, integers are accepted as well.
+    pub fn take_f64(&mut self, key: &str) -> Result<Option<f64>, ScenarioError> {
+        self.take(key, "a number", as_f64)
+    }
+
+    /// Remove a non-negative integer parameter.
+    pub fn take_u64(&mut self, key: &str) -> Result<Option<u64>, ScenarioError> {
+        self.take(key, "a non-negative integer", as_u64)
+    }
+
+    pub fn take_bool(&mut self, key: &str) -> Result<Option<bool>, ScenarioError> {
+        self.take(key, "a boolean", toml::Value::as_bool)
+    }
+
+    /// Remove a parameter given as an array of two non-negative integers.
+    pub fn take_u64_pair(&mut self, key: &str) -> Result<Option<(u64, u64)>, ScenarioError> {
+        self.take(key, "an array of two non-negative integers", |value| match value {
+            toml::Value::Array(items) if items.len() == 2 => {
+                Some((as_u64(&items[0])?, as_u64(&items[1])?))
+            }
+            _ => None,
+        })
+    }
+
+    /// Error about one of the component's fields, `name` included.
+    pub fn error(&self, key: &str, message: String) -> ScenarioError {
+        invalid(&format!("{}.{key}", self.section), message)
+    }
+
+    /// Fail if any parameter was not taken, since the component does not know
+    /// it.
+    pub fn finish(&self) -> Result<(), ScenarioError> {
+        match self.params.keys().next() {
+            Some(key) => Err(self.error(key, format!("unknown key for {}", self.name))),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove a parameter, converting its value or failing with the expected
+    /// type.
+    fn take<T>(
+        &mut self,
+        key: &str,
+        expected: &str,
+        convert: impl FnOnce(&toml::Value) -> Option<T>,
+    ) -> Result<Option<T>, ScenarioError> {
+        let Some(value) = self.params.remove(key) else { return Ok(None) };
+        match convert(&value) {
+            Some(converted) => Ok(Some(converted)),
+            None => {
+                let message = format!("expected {expected}, got {} {value}", value.type_str());
+                Err(self.error(key, message))
+            }
+        }
+    }
+}
+
+fn as_u64(value: &toml::Value) -> Option<u64> {
+    value.as_integer().and_then(|n| u64::try_from(n).ok())
+}
+
+fn as_f64(value: &toml::Value) -> Option<f64> {
+    match value {
+        toml::Value::Integer(n) => Some(*n as f64),
+        toml::Value::Float(x) => Some(*x),
+        _ => None,
+    }
+}
+
+impl From<toml::de::Error> for ScenarioError {
+    fn from(err: toml::de::Error) -> Self {
+        let position = err.line_col().map(|(line, column)| (line + 1, column + 1));
+        let mut message = err.to_string();
+        // The message ends in the position, which has fields of its own here.
+        if let Some((line, column)) = position {
+            let suffix = format!(" at line {line} column {column}");
+            if let Some(stripped) = message.strip_suffix(&suffix) {
+                message.truncate(stripped.len());
+            }
+        }
+        ScenarioError {
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            field: None,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+            (Some(line), None) => write!(f, "line {line}: ")?,
+            _ => {}
+        }
+        if let Some(field) = &self.field {
+            write!(f, "`{field}`: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+/// Error about a field that parsed but does not make sense.
+fn invalid(field: &str, message: String) -> ScenarioError {
+    ScenarioError { line: None, column: None, field: Some(field.to_owned()), message }
+}
+
+fn model_config(model: ModelSection, shards: usize) -> Result<ModelConfig, ScenarioError> {
+    let error = |key: &str, message: String| invalid(&format!("model.{key}"), message);
+    let missing = |key: &str| error(key, "missing".to_owned());
+    let to_gas = |key: &str, tgas: u64| {
+        tgas.checked_mul(TGAS).ok_or_else(|| error(key, format!("{tgas} TGas is out of range")))
+    };
+
+    let mut config = ModelConfig::default();
+    if let Some(gas) = model.forward_gas {
+        config.forward_gas = gas;
+    }
+    if let Some(gas) = model.forward_gas_per_byte {
+        config.forward_gas_per_byte = gas;
+    }
+    if let Some(tgas) = model.gas_limit {
+        config.gas_limit_schedule.push((0, to_gas("gas_limit", tgas)?));
+    }
+    for (round, tgas) in model.gas_limit_schedule {
+        config.gas_limit_schedule.push((round, to_gas("gas_limit_schedule", tgas)?));
+    }
+    if let Some(tgas) = model.global_gas_limit {
+        config.global_gas_limit = Some(to_gas("global_gas_limit", tgas)?);
+    }
+    if let Some(order) = model.shard_order {
+        config.shard_order = order.parse().map_err(|message| error("shard_order", message))?;
+        if !config.shard_order.is_valid(shards) {
+            let message = format!("must list each of the {shards} shards once");
+            return Err(error("shard_order", message));
+        }
+    }
+    config.max_receipt_size = model.max_receipt_size;
+    config.max_tx_size = model.max_tx_size;
+    config.max_witness_per_chunk = model.max_witness_per_chunk;
+    if let Some(fraction) = model.rejected_tx_cost_fraction {
+        if !(0.0..=1.0).contains(&fraction) {
+            let message = format!("{fraction} is not between 0 and 1");
+            return Err(error("rejected_tx_cost_fraction", message));
+        }
+        config.rejected_tx_cost_fraction = fraction;
+    }
+    if let Some(max) = model.max_outstanding_receipts_per_tx {
+        if max == 0 {
+            let message = "must be at least 1 receipt".to_owned();
+            return Err(error("max_outstanding_receipts_per_tx", message));
+        }
+        config.max_outstanding_receipts_per_tx = Some(max);
+    }
+    if let Some(policy) = model.receipt_cap_policy {
+        config.receipt_cap_policy = match policy.as_str() {
+            "reject" => ReceiptCapPolicy::Reject,
+            "truncate" => ReceiptCapPolicy::Truncate,
+            _ => {
+                let message =
+                    format!("unknown policy {policy:?}, expected \"reject\" or \"truncate\"");
+                return Err(error("receipt_cap_policy", message));
+            }
+        };
+    }
+    if let Some(gas) = model.account_switch_gas {
+        config.account_switch_gas = gas;
+    }
+    if let Some(lookahead) = model.account_grouping_lookahead {
+        if lookahead == 0 {
+            let message = "must be at least 1 receipt".to_owned();
+            return Err(error("account_grouping_lookahead", message));
+        }
+        config.account_grouping = Some(AccountGroupedQueue::new(lookahead));
+    }
+    if let Some(p) = model.drop_probability {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(error("drop_probability", format!("{p} is not between 0 and 1")));
+        }
+        config.drop_probability = p;
+    }
+    if let Some(jitter) = model.gas_limit_jitter {
+        if !(0.0..1.0).contains(&jitter) {
+            let message = format!("{jitter} is not at least 0 and less than 1");
+            return Err(error("gas_limit_jitter", message));
+        }
+        config.gas_limit_jitter = jitter;
+    }
+    config.mempool_capacity = model.mempool_capacity;
+    if let Some(eviction) = model.mempool_eviction {
+        config.mempool_eviction = match eviction.as_str() {
+            "oldest" => MempoolEviction::Oldest,
+            "lowest fee" => MempoolEviction::LowestFee,
+            _ => {
+                let message =
+                    format!("unknown eviction {eviction:?}, expected \"oldest\" or \"lowest fee\"");
+                return Err(error("mempool_eviction", message));
+            }
+        };
+    }
+    config.chunk_apply_lag = model.chunk_apply_lag;
+    config.prune_completed = model.prune_completed;
+    if let Some(threshold) = model.congestion_threshold {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            let message = format!("{threshold} is not more than 0 and at most 1");
+            return Err(error("congestion_threshold", message));
+        }
+        config.congestion_threshold = Some(threshold);
+    }
+
+    if let Some(ack_size) = model.ack_size {
+        let retransmit_timeout = model.retransmit_timeout.unwrap_or(4);
+        let min_timeout = if config.chunk_apply_lag { 3 } else { 2 };
+        if retransmit_timeout < min_timeout {
+            let message = format!("must be at least {min_timeout} rounds");
+            return Err(error("retransmit_timeout", message));
+        }
+        let ack_gas = model.ack_gas.unwrap_or(0);
+        config.acks = Some(AckConfig { ack_size, ack_gas, retransmit_timeout });
+    } else if model.ack_gas.is_some() || model.retransmit_timeout.is_some() {
+        let key = if model.ack_gas.is_some() { "ack_gas" } else { "retransmit_timeout" };
+        return Err(error(key, "only applies with `ack_size`".to_owned()));
+    }
+
+    let mut yield_resume = YieldConfig::default();
+    if let Some(p) = model.resume_probability {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(error("resume_probability", format!("{p} is not between 0 and 1")));
+        }
+        yield_resume.resume_probability = p;
+    }
+    if let Some(timeout) = model.yield_timeout {
+        if timeout == 0 {
+            return Err(error("yield_timeout", "must be at least 1 round".to_owned()));
+        }
+        yield_resume.timeout = timeout;
+    }
+    config.yield_resume = yield_resume;
+
+    match (model.epoch_length, model.validator_group_performance) {
+        (Some(0), _) => {
+            return Err(error("epoch_length", "must be at least 1 round".to_owned()));
+        }
+        (Some(epoch_length), Some(group_performance)) => {
+            if group_performance.is_empty() {
+                let message = "needs at least one validator group".to_owned();
+                return Err(error("validator_group_performance", message));
+            }
+            if let Some(p) = group_performance.iter().find(|&&p| p <= 0.0) {
+                let message = format!("performance {p} is not positive");
+                return Err(error("validator_group_performance", message));
+            }
+            config.epoch_schedule = Some(EpochSchedule { epoch_length, group_performance });
+        }
+        (Some(_), None) => return Err(missing("validator_group_performance")),
+        (None, Some(_)) => return Err(missing("epoch_length")),
+        (None, None) => {}
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        # the example from the docs
+        version = 1
+        shards = 4
+        rounds = 1000
+        seed = 7
+
+        [model]
+        gas_limit = 1000                        # TGas per chunk
+        gas_limit_schedule = [[500, 1500]]      # [round, TGas] pairs
+        drop_probability = 0.01
+
+        [strategy]
+        name = "NEP"
+        global_stop_limit = 0.9
+
+        [workload]
+        name = "Balanced"
+        whale_probability = 0.05
+    "#;
+
+    fn error(text: &str) -> ScenarioError {
+        Scenario::parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn parses_example() {
+        let mut scenario = Scenario::parse(EXAMPLE).unwrap();
+        assert_eq!((scenario.shards, scenario.rounds, scenario.seed), (4, 1000, 7));
+        assert_eq!(scenario.config.gas_limit_schedule, [(0, 1000 * TGAS), (500, 1500 * TGAS)]);
+        assert_eq!(scenario.config.drop_probability, 0.01);
+        assert_eq!(scenario.strategy.name, "NEP");
+        assert_eq!(scenario.strategy.take_f64("global_stop_limit").unwrap(), Some(0.9));
+        scenario.strategy.finish().unwrap();
+        assert_eq!(scenario.workload.name, "Balanced");
+        let err = scenario.workload.finish().unwrap_err();
+        assert_eq!(err.to_string(), "`workload.whale_probability`: unknown key for Balanced");
+    }
+
+    #[test]
+    fn accepts_any_toml_syntax() {
+        let text = r#"
+            version = 1
+            rounds = 10
+            strategy = { name = "NEP", gas_limits = [100, 200] }
+            workload = { name = "Balanced" }
+
+            [model]
+            gas_limit_schedule = [
+                [500, 1500],   # first raise
+                [900, 2000],
+            ]
+        "#;
+        let mut scenario = Scenario::parse(text).unwrap();
+        assert_eq!(scenario.shards, 4);
+        assert_eq!(scenario.config.gas_limit_schedule, [(500, 1500 * TGAS), (900, 2000 * TGAS)]);
+        assert_eq!(scenario.strategy.take_u64_pair("gas_limits").unwrap(), Some((100, 200)));
+        scenario.strategy.finish().unwrap();
+    }
+
+    #[test]
+    fn errors_point_at_the_field() {
+        let with = |old: &str, new: &str| error(&EXAMPLE.replace(old, new));
+        assert_eq!(
+            with("drop_probability = 0.01", "drop_probability = 1.5").to_string(),
+            "`model.drop_probability`: 1.5 is not between 0 and 1"
+        );
+        let err = with("rounds = 1000", "rounds = \"many\"");
+        assert!(err.message.contains("invalid type: string \"many\""), "{err}");
+        let err = with("drop_probability", "drop_probabilty");
+        assert!(err.message.contains("unknown field `drop_probabilty`"), "{err}");
+        let err = with("name = \"NEP\"", "");
+        assert!(err.message.contains("missing field `name`"), "{err}");
+        let huge = u64::MAX / TGAS + 1;
+        assert_eq!(
+            with("gas_limit = 1000", &format!("gas_limit = {huge}")).to_string(),
+            format!("`model.gas_limit`: {huge} TGas is out of range")
+        );
+        let err = with("[500, 1500]]  ", &format!("[500, {huge}]]"));
+        assert_eq!(err.field.as_deref(), Some("model.gas_limit_schedule"), "{err}");
+        let err = with("gas_limit = 1000", &format!("global_gas_limit = {huge}"));
+        assert_eq!(err.field.as_deref(), Some("model.global_gas_limit"), "{err}");
+        assert_eq!(
+            with("drop_probability = 0.01", "congestion_threshold = 0.0").to_string(),
+            "`model.congestion_threshold`: 0 is not more than 0 and at most 1"
+        );
+        let err = with("seed = 7", "seed = = 7");
+        assert_eq!(err.line, Some(6), "{err}");
+
+        let mut scenario = Scenario::parse(EXAMPLE).unwrap();
+        assert_eq!(
+            scenario.workload.take_u64("whale_probability").unwrap_err().to_string(),
+            "`workload.whale_probability`: expected a non-negative integer, got float 0.05"
+        );
+    }
+
+    #[test]
+    fn format_is_versioned() {
+        assert_eq!(error(&EXAMPLE.replace("version = 1\n", "")).to_string(), "`version`: missing");
+        let newer = EXAMPLE.replace("version = 1", "version = 2\nnew_key = true");
+        assert_eq!(error(&newer).to_string(), "`version`: unsupported version 2, expected 1");
+    }
+}
@@ -0,0 +1,196 @@
+use crate::model::ChunkExecutionContext;
+use crate::strategy::QueueFactory;
+use crate::{GGas, PGAS, TX_GAS_LIMIT};
+
+/// A self-tuning strategy that adjusts how much gas it spends on new
+/// transactions, aiming to keep queue lengths near a target.
+///
+/// Each shard shares the length of its incoming receipts queue, measured in
+/// attached gas. (Counting receipts does not work well, the count is dominated
+/// by cheap refunds that drain in bulk.) Every round, all shards look at the
+/// longest queue of the previous round and nudge their transaction gas
+/// threshold, like a simple PD controller:
+///
+/// ```ignore
+/// error = (queue_gas - target) / target
+/// trend = (queue_gas - prev_queue_gas) / target
+/// threshold -= learning_rate * (error + damping * trend) * TX_GAS_LIMIT
+/// ```
+///
+/// The error term pulls the queue length towards the target, the trend term
+/// reacts to whether queues are growing or shrinking and damps oscillation.
+/// Since all shards observe the same value, they all apply the same threshold.
+pub struct AdaptiveThreshold {
+    // config
+    pub target_queue_gas: GGas,
+    pub learning_rate: f64,
+    pub damping: f64,
+    pub min_tx_gas: GGas,
+    pub max_tx_gas: GGas,
+
+    // state
+    /// Current gas threshold for accepting new transactions. Kept as a float
+    /// to allow adjustments smaller than one unit of gas per round.
+    pub tx_gas_threshold: f64,
+    prev_queue_gas: Option<GGas>,
+}
+
+struct QueueLengthInfo {
+    incoming_receipts_gas: GGas,
+}
+
+impl Default for AdaptiveThreshold {
+    fn default() -> Self {
+        Self {
+            target_queue_gas: 10 * PGAS,
+            learning_rate: 0.05,
+            damping: 4.0,
+            min_tx_gas: 0,
+            max_tx_gas: TX_GAS_LIMIT,
+
+            // overwritten at init
+            tx_gas_threshold: TX_GAS_LIMIT as f64,
+            prev_queue_gas: None,
+        }
+    }
+}
+
+impl crate::CongestionStrategy for AdaptiveThreshold {
+    fn init(
+        &mut self,
+        _id: crate::ShardId,
+        _other_shards: &[crate::ShardId],
+        _queue_factory: &mut dyn QueueFactory,
+    ) {
+        self.tx_gas_threshold = self.max_tx_gas as f64;
+        self.prev_queue_gas = None;
+    }
+
+    fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+        self.update_threshold(ctx);
+
+        let tx_gas_limit = (self.tx_gas_threshold as GGas).min(ctx.gas_limit());
+        while ctx.gas_burnt() < tx_gas_limit {
+            let Some(tx) = ctx.incoming_transactions().pop_front() else {
+                // no more transaction incoming
+                break;
+            };
+            let outgoing = ctx.accept_transaction(tx);
+            ctx.forward_receipt(outgoing);
+        }
+
+        while ctx.can_execute_receipt() {
+            let Some(receipt) = ctx.incoming_receipts().pop_front() else {
+                // no more receipts to execute
+                break;
+            };
+            let outgoing = ctx.execute_receipt(receipt);
+            for receipt in outgoing {
+                ctx.forward_receipt(receipt);
+            }
+        }
+
+        let info =
+            QueueLengthInfo { incoming_receipts_gas: ctx.incoming_receipts().attached_gas() };
+        ctx.current_block_info().insert(info);
+    }
+
+    fn debug_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "tx_gas_threshold": self.tx_gas_threshold,
+            "prev_queue_gas": self.prev_queue_gas,
+        })
+    }
+}
+
+impl AdaptiveThreshold {
+    pub fn new(target_queue_gas: GGas, learning_rate: f64) -> Self {
+        assert!(target_queue_gas > 0, "target queue length must be positive");
+        assert!(learning_rate >= 0.0, "learning rate must not be negative");
+        Self { target_queue_gas, learning_rate, ..Self::default() }
+    }
+
+    /// How strongly to react to growing or shrinking queues, relative to the
+    /// distance from the target.
+    pub fn with_damping(mut self, damping: f64) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    fn update_threshold(&mut self, ctx: &mut ChunkExecutionContext) {
+        let Some(queue_gas) = ctx
+            .prev_block_info()
+            .values()
+            .filter_map(|info| info.get::<QueueLengthInfo>())
+            .map(|info| info.incoming_receipts_gas)
+            .max()
+        else {
+            // first round, nothing observed, yet
+            return;
+        };
+        let prev_queue_gas = self.prev_queue_gas.replace(queue_gas);
+
+        let target = self.target_queue_gas as f64;
+        let error = (queue_gas as f64 - target) / target;
+        let trend = prev_queue_gas.map_or(0.0, |prev| (queue_gas as f64 - prev as f64) / target);
+
+        let step = self.learning_rate * (error + self.damping * trend) * TX_GAS_LIMIT as f64;
+        self.tx_gas_threshold =
+            (self.tx_gas_threshold - step).clamp(self.min_tx_gas as f64, self.max_tx_gas as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::BalancedProducer;
+    use crate::Model;
+
+    fn max_queue_gas_per_round(rounds: usize) -> Vec<GGas> {
+        let shards = (0..4).map(|_| Box::<AdaptiveThreshold>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        (0..rounds)
+            .map(|_| {
+                model.step();
+                model.trim_transaction_pools(1000);
+                model.max_queue_length().incoming_receipts.gas
+            })
+            .collect()
+    }
+
+    #[test]
+    fn converges_to_target_without_oscillating() {
+        let target = AdaptiveThreshold::default().target_queue_gas as f64;
+        let queue_gas = max_queue_gas_per_round(400);
+        let steady_state = &queue_gas[300..];
+
+        // Queues are measured after receipts of the round have been delivered,
+        // so they are a bit above what the strategy observes.
+        let avg = steady_state.iter().sum::<GGas>() as f64 / steady_state.len() as f64;
+        assert!(avg > 0.8 * target && avg < 1.5 * target, "avg {avg} too far from {target}");
+
+        let min = *steady_state.iter().min().unwrap() as f64;
+        let max = *steady_state.iter().max().unwrap() as f64;
+        assert!(max - min < 0.5 * avg, "oscillating between {min} and {max}");
+
+        // deterministic, no randomness involved
+        assert_eq!(queue_gas, max_queue_gas_per_round(400));
+    }
+
+    #[test]
+    fn debug_state_follows_the_threshold() {
+        let shards = (0..2).map(|_| Box::<AdaptiveThreshold>::default() as _).collect();
+        let mut model = Model::new(shards, Box::<BalancedProducer>::default());
+        let shard = model.shard_ids()[0];
+        let state = model.strategy_debug_state(shard);
+        assert_eq!(state["tx_gas_threshold"].as_f64(), Some(TX_GAS_LIMIT as f64));
+        assert!(state["prev_queue_gas"].is_null());
+
+        for _ in 0..50 {
+            model.step();
+        }
+        let state = model.strategy_debug_state(shard);
+        assert!(state["tx_gas_threshold"].as_f64().unwrap() < TX_GAS_LIMIT as f64, "{state}");
+        assert!(state["prev_queue_gas"].as_u64().is_some(), "{state}");
+    }
+}
@@ -118,10 +118,10 @@ impl FancyGlobalTransactionStop {
     }
 
     // Process the incoming receipts. Always process as many receipts as allowed
-    // by the GAS_LIMIT. The outgoing receipts are handled the same way as in
-    // `process_outgoing_receipts`.
+    // by the gas and witness limits. The outgoing receipts are handled the same
+    // way as in `process_outgoing_receipts`.
     fn process_incoming_receipts(&mut self, ctx: &mut ChunkExecutionContext<'_>) {
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             let Some(receipt) = ctx.incoming_receipts().pop_front() else {
                 break;
             };
@@ -175,7 +175,7 @@ impl FancyGlobalTransactionStop {
 
     // Returns the new transactions gas limit. This is the limit on the chunk
     // gas beyond which new transactions won't be included. By default it is
-    // GAS_LIMIT but it can be reduced if the outgoing receipt queues are
+    // the chunk gas limit but it can be reduced if the outgoing receipt queues are
     // filling up. Keep in mind that by default incoming receipts are always
     // included first so if the incoming receipts queue is full then no new
     // transactions will be included anyway.
@@ -190,15 +190,15 @@ impl FancyGlobalTransactionStop {
         // If the fullest queue is less than half full we want to process a full chunk.
         let half_max = self.outgoing_attached_gas_limit / 2;
         if remaining > half_max {
-            return GAS_LIMIT;
+            return ctx.gas_limit();
         }
 
-        // If the buffer is more than half full scale the GAS_LIMIT.
+        // If the buffer is more than half full scale the gas limit.
         // It's linear with respect to `remaining` with the borders at
         // * remaining == 0        -> gas limit == 0
-        // * remaining == half_max -> gas_limit == GAS_LIMIT
+        // * remaining == half_max -> gas_limit == chunk gas limit
         // TODO overflows?
-        GAS_LIMIT * remaining / half_max
+        ctx.gas_limit() * remaining / half_max
     }
 
     // Returns the outgoing gas limit. This is how much attached gas we allow
@@ -1,6 +1,5 @@
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::{GAS_LIMIT, TX_GAS_LIMIT};
 
 /// Stop all shards from accepting new transactions when a limit of delayed
 /// receipts is reached in any shard.
@@ -32,7 +31,7 @@ impl crate::CongestionStrategy for GlobalTxStopShard {
 
         // stop accepting transacions when any shard is congested
         if !any_shard_congested {
-            while ctx.gas_burnt() < TX_GAS_LIMIT {
+            while ctx.gas_burnt() < ctx.tx_gas_limit() {
                 if let Some(tx) = ctx.incoming_transactions().pop_front() {
                     let outgoing = ctx.accept_transaction(tx);
                     ctx.forward_receipt(outgoing);
@@ -44,7 +43,7 @@ impl crate::CongestionStrategy for GlobalTxStopShard {
         }
 
         // keep executing existing receipts even when a shard is congested
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             if let Some(receipt) = ctx.incoming_receipts().pop_front() {
                 let outgoing = ctx.execute_receipt(receipt);
                 for receipt in outgoing {
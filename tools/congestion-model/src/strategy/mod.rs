@@ -1,20 +1,29 @@
 use crate::model::ChunkExecutionContext;
-use crate::{QueueId, ShardId};
+use crate::{GGas, QueueId, ShardId};
+use std::collections::BTreeMap;
 
+pub use adaptive_threshold::AdaptiveThreshold;
 pub use fancy_global_transaction_stop::FancyGlobalTransactionStop;
+pub use global_nearly_done::GlobalNearlyDone;
 pub use global_tx_stop::GlobalTxStopShard;
 pub use nep::NepStrategy;
 pub use new_tx_last::NewTxLast;
 pub use no_queues::NoQueueShard;
+pub use priority_aging::PriorityAging;
+pub use priority_gas::PriorityGas;
 pub use simple_backpressure::SimpleBackpressure;
 pub use smooth_traffic_light::SmoothTrafficLight;
 pub use traffic_light::TrafficLight;
 
+mod adaptive_threshold;
 mod fancy_global_transaction_stop;
+mod global_nearly_done;
 mod global_tx_stop;
 mod nep;
 mod new_tx_last;
 mod no_queues;
+mod priority_aging;
+mod priority_gas;
 mod simple_backpressure;
 mod smooth_traffic_light;
 mod traffic_light;
@@ -26,10 +35,32 @@ mod traffic_light;
 /// the same code on each shard.
 pub trait CongestionStrategy {
     /// Initial state and register all necessary queues for one shard.
+    ///
+    /// This is called again when the model is reset, with queues registered
+    /// in the same order receiving the same IDs. It must reset all state from
+    /// previous rounds.
     fn init(&mut self, id: ShardId, other_shards: &[ShardId], queue_factory: &mut dyn QueueFactory);
 
     /// Decide which receipts to execute, which to delay, and which to forward.
     fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext);
+
+    /// Internal state of the strategy on its shard, like thresholds or send
+    /// budgets, to understand its decisions when debugging.
+    ///
+    /// The model writes it to the per-round stats after each round, see
+    /// [`crate::Model::strategy_debug_state`]. Strategies without interesting
+    /// state keep the default, null.
+    fn debug_state(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+}
+
+/// Gas per shard as a JSON object keyed by shard, for
+/// [`CongestionStrategy::debug_state`].
+fn gas_per_shard<'a>(gas: impl IntoIterator<Item = (&'a ShardId, &'a GGas)>) -> serde_json::Value {
+    let gas: BTreeMap<String, GGas> =
+        gas.into_iter().map(|(shard, gas)| (shard.to_string(), *gas)).collect();
+    serde_json::json!(gas)
 }
 
 /// Use this to create queues.
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::{GGas, QueueId, Receipt, ShardId, TransactionId, GAS_LIMIT, PGAS, TGAS};
+use crate::{GGas, QueueId, Receipt, ShardId, TransactionId, PGAS, TGAS};
 
 pub struct NepStrategy {
     pub shard_id: Option<ShardId>,
@@ -86,6 +86,10 @@ impl crate::CongestionStrategy for NepStrategy {
 
         self.update_block_info(ctx);
     }
+
+    fn debug_state(&self) -> serde_json::Value {
+        serde_json::json!({ "outgoing_gas_limit": super::gas_per_shard(&self.outgoing_gas_limit) })
+    }
 }
 
 impl NepStrategy {
@@ -134,7 +138,8 @@ impl NepStrategy {
     // The outgoing receipts are processed as in `process_outgoing_receipts`.
     fn process_new_transactions(&mut self, ctx: &mut ChunkExecutionContext<'_>) {
         let incoming_congestion = self.get_incoming_congestion(ctx);
-        let tx_limit = mix(self.max_tx_gas, self.min_tx_gas, incoming_congestion);
+        let tx_limit =
+            mix(self.max_tx_gas, self.min_tx_gas, incoming_congestion).min(ctx.gas_limit());
 
         while ctx.gas_burnt() < tx_limit {
             let Some(tx) = ctx.incoming_transactions().pop_front() else {
@@ -199,11 +204,11 @@ impl NepStrategy {
     // In the model there is no distinction between local, delayed and incoming.
     // All of those are stored in the incoming queue so we just process that.
     //
-    // Always process as many receipts as allowed by the GAS_LIMIT.
+    // Always process as many receipts as allowed by the gas and witness limits.
     //
     // The outgoing receipts are processed as in `process_outgoing_receipts`.
     fn process_incoming_receipts(&mut self, ctx: &mut ChunkExecutionContext<'_>) {
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             let Some(receipt) = ctx.incoming_receipts().pop_front() else {
                 break;
             };
@@ -1,6 +1,5 @@
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::GAS_LIMIT;
 
 #[derive(Default)]
 /// No queues, no backpressure. But always prioritize existing receipts over new
@@ -20,7 +19,7 @@ impl crate::CongestionStrategy for NewTxLast {
     fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
         // Start with receipts and reserve no chunk space to new transactions.
         // In contrast to nearcore today, which gives new transactions priority with up to halve the chunks space.
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             if let Some(receipt) = ctx.incoming_receipts().pop_front() {
                 let outgoing = ctx.execute_receipt(receipt);
                 for receipt in outgoing {
@@ -31,7 +30,7 @@ impl crate::CongestionStrategy for NewTxLast {
                 break;
             }
         }
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.gas_burnt() < ctx.gas_limit() {
             if let Some(tx) = ctx.incoming_transactions().pop_front() {
                 let outgoing = ctx.accept_transaction(tx);
                 ctx.forward_receipt(outgoing);
@@ -1,6 +1,5 @@
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::{GAS_LIMIT, TX_GAS_LIMIT};
 
 pub struct NoQueueShard {}
 
@@ -14,7 +13,7 @@ impl crate::CongestionStrategy for NoQueueShard {
     }
 
     fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
-        while ctx.gas_burnt() < TX_GAS_LIMIT {
+        while ctx.gas_burnt() < ctx.tx_gas_limit() {
             if let Some(tx) = ctx.incoming_transactions().pop_front() {
                 let outgoing = ctx.accept_transaction(tx);
                 ctx.forward_receipt(outgoing);
@@ -23,7 +22,7 @@ impl crate::CongestionStrategy for NoQueueShard {
                 break;
             }
         }
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             if let Some(receipt) = ctx.incoming_receipts().pop_front() {
                 let outgoing = ctx.execute_receipt(receipt);
                 for receipt in outgoing {
@@ -0,0 +1,156 @@
+use crate::model::ChunkExecutionContext;
+use crate::strategy::QueueFactory;
+use crate::{Receipt, ReceiptPriority, Round};
+
+/// No backpressure, but executes incoming receipts by priority, with receipts
+/// gaining priority the longer they wait.
+///
+/// Each receipt has an effective priority of
+///
+/// ```ignore
+/// effective = base_priority - age * aging_rate
+/// ```
+///
+/// where the base priority is 0 for [`ReceiptPriority::High`] and 1 for
+/// [`ReceiptPriority::Normal`], and the age is the number of rounds since the
+/// receipt was created. The receipt with the lowest effective priority
+/// executes first, ties go to the receipt that arrived first.
+///
+/// With an aging rate of 0, this is pure priority scheduling and a steady
+/// stream of high priority receipts starves the normal ones forever. With a
+/// positive rate, a normal receipt overtakes high priority receipts that are
+/// `1 / aging_rate` rounds younger, which bounds how long it waits.
+pub struct PriorityAging {
+    pub aging_rate: f64,
+}
+
+impl Default for PriorityAging {
+    fn default() -> Self {
+        Self { aging_rate: 0.1 }
+    }
+}
+
+impl crate::CongestionStrategy for PriorityAging {
+    fn init(
+        &mut self,
+        _id: crate::ShardId,
+        _other_shards: &[crate::ShardId],
+        _queue_factory: &mut dyn QueueFactory,
+    ) {
+    }
+
+    fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+        while ctx.gas_burnt() < ctx.tx_gas_limit() {
+            if let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let outgoing = ctx.accept_transaction(tx);
+                ctx.forward_receipt(outgoing);
+            } else {
+                // no more transaction incoming
+                break;
+            }
+        }
+        // All receipts age at the same pace, so their order is fixed within a
+        // chunk. The sort is stable and keeps FIFO order for ties.
+        let round = ctx.block_height();
+        ctx.incoming_receipts().make_contiguous().sort_by(|a, b| {
+            self.effective_priority(a, round).total_cmp(&self.effective_priority(b, round))
+        });
+        while ctx.can_execute_receipt() {
+            if let Some(receipt) = ctx.incoming_receipts().pop_front() {
+                let outgoing = ctx.execute_receipt(receipt);
+                for receipt in outgoing {
+                    ctx.forward_receipt(receipt);
+                }
+            } else {
+                // no more receipts to execute
+                break;
+            }
+        }
+    }
+}
+
+impl PriorityAging {
+    /// How much priority receipts gain per round of waiting. Set to 0 to
+    /// disable aging.
+    pub fn with_aging_rate(mut self, aging_rate: f64) -> Self {
+        assert!(aging_rate.is_finite() && aging_rate >= 0.0, "invalid aging rate {aging_rate}");
+        self.aging_rate = aging_rate;
+        self
+    }
+
+    fn effective_priority(&self, receipt: &Receipt, round: Round) -> f64 {
+        let base = match receipt.priority {
+            ReceiptPriority::High => 0.0,
+            ReceiptPriority::Normal => 1.0,
+        };
+        let created_at = receipt.created_at.expect("queued receipts must have been created");
+        let age = round.saturating_sub(created_at);
+        base - age as f64 * self.aging_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Producer;
+    use crate::{Model, ReceiptDefinition, ShardId, TransactionBuilder, TGAS};
+    use rand::RngCore;
+
+    /// More high priority work than the shard can execute, every round, and a
+    /// single normal priority transaction in round 1.
+    struct HighPriorityFlood;
+
+    impl Producer for HighPriorityFlood {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            let receipt = ReceiptDefinition {
+                receiver: shards[0],
+                size: 100,
+                attached_gas: 100 * TGAS,
+                execution_gas: 100 * TGAS,
+            };
+            let mut out = vec![];
+            for _ in 0..12 {
+                let mut tx = tx_factory(shards[0]).with_priority(ReceiptPriority::High);
+                tx.add_first_receipt(receipt.clone(), 0);
+                out.push(tx);
+            }
+            if round == 1 {
+                let mut tx = tx_factory(shards[0]);
+                tx.add_first_receipt(receipt, 0);
+                out.push(tx);
+            }
+            out
+        }
+    }
+
+    /// Rounds the normal priority transaction took, `None` if it is not done
+    /// after 100 rounds.
+    fn normal_delay(aging_rate: f64) -> Option<Round> {
+        let strategy = PriorityAging::default().with_aging_rate(aging_rate);
+        let mut model = Model::new(vec![Box::new(strategy)], Box::new(HighPriorityFlood));
+        for _ in 0..100 {
+            model.step();
+        }
+        let normal = model
+            .transactions
+            .all_transactions()
+            .find(|tx| tx.priority == ReceiptPriority::Normal)
+            .unwrap();
+        (!normal.executed_receipts.is_empty()).then(|| normal.delay())
+    }
+
+    #[test]
+    fn aging_serves_starved_receipt() {
+        assert_eq!(normal_delay(0.0), None);
+        let delay = normal_delay(0.1).unwrap();
+        assert!(delay < 30, "{delay}");
+    }
+}
@@ -0,0 +1,143 @@
+use crate::model::ChunkExecutionContext;
+use crate::strategy::QueueFactory;
+use std::cmp::Reverse;
+
+/// No backpressure, but lets senders pay for being executed earlier.
+///
+/// Transactions and receipts are sorted by the priority gas attached to them
+/// (see [`crate::TransactionBuilder::with_priority_gas`]), highest first, before
+/// the shard converts and executes as many as its gas limits allow. Ties keep
+/// FIFO order. Without contention everything executes in the same chunk either
+/// way, so the order only matters when the shard is congested.
+#[derive(Default)]
+pub struct PriorityGas {}
+
+impl crate::CongestionStrategy for PriorityGas {
+    fn init(
+        &mut self,
+        _id: crate::ShardId,
+        _other_shards: &[crate::ShardId],
+        _queue_factory: &mut dyn QueueFactory,
+    ) {
+    }
+
+    fn compute_chunk(&mut self, ctx: &mut ChunkExecutionContext) {
+        let mut transactions: Vec<_> = ctx.incoming_transactions().drain(..).collect();
+        transactions.sort_by_key(|&tx| Reverse(ctx.tx_priority_gas(tx)));
+        ctx.incoming_transactions().extend(transactions);
+        while ctx.gas_burnt() < ctx.tx_gas_limit() {
+            if let Some(tx) = ctx.incoming_transactions().pop_front() {
+                let outgoing = ctx.accept_transaction(tx);
+                ctx.forward_receipt(outgoing);
+            } else {
+                // no more transaction incoming
+                break;
+            }
+        }
+
+        // The sort is stable and keeps FIFO order for ties.
+        ctx.incoming_receipts()
+            .make_contiguous()
+            .sort_by_key(|receipt| Reverse(receipt.attached_priority_gas));
+        while ctx.can_execute_receipt() {
+            if let Some(receipt) = ctx.incoming_receipts().pop_front() {
+                let outgoing = ctx.execute_receipt(receipt);
+                for receipt in outgoing {
+                    ctx.forward_receipt(receipt);
+                }
+            } else {
+                // no more receipts to execute
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workload::Producer;
+    use crate::{
+        GGas, Model, ReceiptDefinition, Round, ShardId, TransactionBuilder, TransactionId, TGAS,
+    };
+    use rand::RngCore;
+
+    fn receipt(receiver: ShardId, gas: GGas) -> ReceiptDefinition {
+        ReceiptDefinition { receiver, size: 100, attached_gas: gas, execution_gas: gas }
+    }
+
+    /// More work without priority gas than the shard can execute, every round.
+    struct Flood;
+
+    impl Producer for Flood {
+        fn init(&mut self, _shards: &[ShardId]) {}
+
+        fn produce_transactions(
+            &mut self,
+            _round: Round,
+            shards: &[ShardId],
+            tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+            _rng: &mut dyn RngCore,
+        ) -> Vec<TransactionBuilder> {
+            (0..20)
+                .map(|_| {
+                    let mut tx = tx_factory(shards[0]);
+                    tx.add_first_receipt(receipt(shards[0], 100 * TGAS), 0);
+                    tx
+                })
+                .collect()
+        }
+    }
+
+    fn flooded_model() -> Model {
+        let mut model = Model::new(vec![Box::<PriorityGas>::default()], Box::new(Flood));
+        for _ in 0..20 {
+            model.step();
+        }
+        model
+    }
+
+    fn submit(model: &mut Model, gas: GGas, priority_gas: GGas) -> TransactionId {
+        let shard = model.shard_ids()[0];
+        let mut tx = model.new_transaction(shard).with_priority_gas(priority_gas);
+        tx.add_first_receipt(receipt(shard, gas), 0);
+        let round = model.round() + 1;
+        model.submit(tx, round)
+    }
+
+    fn run_until_done(model: &mut Model, ids: &[TransactionId]) {
+        for _ in 0..100 {
+            if ids.iter().all(|&id| !model.transactions[id].executed_receipts.is_empty()) {
+                return;
+            }
+            model.step();
+        }
+        panic!("transactions did not execute within 100 rounds");
+    }
+
+    #[test]
+    fn priority_gas_skips_the_queue() {
+        let mut model = flooded_model();
+        let paying = submit(&mut model, 100 * TGAS, TGAS);
+        let free = submit(&mut model, 100 * TGAS, 0);
+        run_until_done(&mut model, &[paying, free]);
+        let (paying_delay, free_delay) =
+            (model.transactions[paying].delay(), model.transactions[free].delay());
+        assert!(paying_delay <= 2, "{paying_delay}");
+        assert!(free_delay > 10, "{free_delay}");
+    }
+
+    #[test]
+    fn more_priority_gas_executes_first() {
+        // each receipt fills a whole chunk, so they execute one per round
+        let mut model = flooded_model();
+        let ids: Vec<_> =
+            [TGAS, 5 * TGAS, 3 * TGAS].map(|gas| submit(&mut model, 1000 * TGAS, gas)).into();
+        run_until_done(&mut model, &ids);
+        let executed_at = |id: TransactionId| {
+            model.transactions[id].executed_receipts.values().next().unwrap().executed_at.unwrap()
+        };
+        assert!(executed_at(ids[1]) < executed_at(ids[2]));
+        assert!(executed_at(ids[2]) < executed_at(ids[0]));
+    }
+}
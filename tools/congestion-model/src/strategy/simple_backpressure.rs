@@ -1,6 +1,6 @@
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::{QueueId, Receipt, ShardId, GAS_LIMIT, TX_GAS_LIMIT};
+use crate::{QueueId, Receipt, ShardId};
 
 /// Have a fixed max queue size per shard and apply backpressure by stop
 /// forwarding receipts when a receiving shard has reached its limit.
@@ -35,7 +35,7 @@ impl crate::CongestionStrategy for SimpleBackpressure {
         }
 
         // stop accepting transactions our local queue capacities are exhausted
-        while !self.congested(ctx) && ctx.gas_burnt() < TX_GAS_LIMIT {
+        while !self.congested(ctx) && ctx.gas_burnt() < ctx.tx_gas_limit() {
             if let Some(tx) = ctx.incoming_transactions().pop_front() {
                 let outgoing = ctx.accept_transaction(tx);
                 self.forward_or_buffer(outgoing, ctx);
@@ -46,7 +46,7 @@ impl crate::CongestionStrategy for SimpleBackpressure {
         }
 
         // keep executing existing receipts even when shards are congested
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             if let Some(receipt) = ctx.incoming_receipts().pop_front() {
                 let outgoing = ctx.execute_receipt(receipt);
                 for receipt in outgoing {
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use crate::model::ChunkExecutionContext;
 use crate::strategy::QueueFactory;
-use crate::{GGas, QueueId, Receipt, ShardId, TransactionId, GAS_LIMIT, PGAS, TGAS};
+use crate::{GGas, QueueId, Receipt, ShardId, TransactionId, PGAS, TGAS};
 
 pub struct SmoothTrafficLight {
     pub shard_id: Option<ShardId>,
@@ -96,6 +96,12 @@ impl crate::CongestionStrategy for SmoothTrafficLight {
 
         self.update_block_info(ctx);
     }
+
+    fn debug_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "outgoing_gas_allowance": super::gas_per_shard(&self.outgoing_gas_allowance),
+        })
+    }
 }
 
 impl SmoothTrafficLight {
@@ -155,7 +161,8 @@ impl SmoothTrafficLight {
     // The outgoing receipts are processed as in `process_outgoing_receipts`.
     fn process_new_transactions(&mut self, ctx: &mut ChunkExecutionContext<'_>) {
         let incoming_congestion = self.incoming_gas_congestion(ctx);
-        let tx_allowance = mix(self.max_tx_gas, self.min_tx_gas, incoming_congestion);
+        let tx_allowance =
+            mix(self.max_tx_gas, self.min_tx_gas, incoming_congestion).min(ctx.gas_limit());
 
         while ctx.gas_burnt() < tx_allowance {
             let Some(tx) = ctx.incoming_transactions().pop_front() else {
@@ -189,11 +196,11 @@ impl SmoothTrafficLight {
     // In the model there is no distinction between local, delayed and incoming.
     // All of those are stored in the incoming queue so we just process that.
     //
-    // Always process as many receipts as allowed by the GAS_LIMIT.
+    // Always process as many receipts as allowed by the gas and witness limits.
     //
     // The outgoing receipts are processed as in `process_outgoing_receipts`.
     fn process_incoming_receipts(&mut self, ctx: &mut ChunkExecutionContext<'_>) {
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             let Some(receipt) = ctx.incoming_receipts().pop_front() else {
                 break;
             };
@@ -217,7 +224,7 @@ impl SmoothTrafficLight {
         let info = if red {
             CongestedShardsInfo {
                 congestion_level: 1.0,
-                allowed_shard: Some(self.round_robin_shard(ctx.block_height() as usize)),
+                allowed_shard: self.round_robin_shard(ctx.block_height() as usize),
             }
         } else if !self.smooth_slow_down {
             // Initial traffic light:
@@ -236,13 +243,17 @@ impl SmoothTrafficLight {
         ctx.current_block_info().insert(info);
     }
 
-    fn round_robin_shard(&mut self, seed: usize) -> ShardId {
+    /// Picks one of the other shards, or none if there are no other shards.
+    fn round_robin_shard(&mut self, seed: usize) -> Option<ShardId> {
         let num_other_shards = self.all_shards.len() - 1;
+        if num_other_shards == 0 {
+            return None;
+        }
         let mut index = (seed + *self.shard_id.unwrap()) % num_other_shards;
         if self.all_shards[index] == self.shard_id() {
             index = self.all_shards.len() - 1;
         }
-        self.all_shards[index]
+        Some(self.all_shards[index])
     }
 
     fn memory_congestion(&self, ctx: &mut ChunkExecutionContext) -> f64 {
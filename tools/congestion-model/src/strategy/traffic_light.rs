@@ -1,8 +1,6 @@
 use super::QueueFactory;
 use crate::model::ChunkExecutionContext;
-use crate::{
-    CongestionStrategy, GGas, QueueId, Receipt, Round, ShardId, GAS_LIMIT, TGAS, TX_GAS_LIMIT,
-};
+use crate::{CongestionStrategy, GGas, QueueId, Receipt, Round, ShardId, TGAS, TX_GAS_LIMIT};
 use std::collections::HashMap;
 
 /// A congestion strategy featuring 3 levels of traffic control.
@@ -104,7 +102,8 @@ impl CongestionStrategy for TrafficLight {
             TrafficLightStatus::Green => self.green_new_tx_gas,
             TrafficLightStatus::Amber => self.amber_new_tx_gas,
             TrafficLightStatus::Red { .. } => self.red_new_tx_gas,
-        };
+        }
+        .min(ctx.gas_limit());
         self.reset_outgoing_gas_allowance(ctx);
 
         // try forwarding buffered outgoing receipts
@@ -136,7 +135,7 @@ impl CongestionStrategy for TrafficLight {
             }
         }
 
-        while ctx.gas_burnt() < GAS_LIMIT {
+        while ctx.can_execute_receipt() {
             if let Some(receipt) = ctx.incoming_receipts().pop_front() {
                 let outgoing = ctx.execute_receipt(receipt);
                 for receipt in outgoing {
@@ -151,6 +150,12 @@ impl CongestionStrategy for TrafficLight {
         let my_status = self.my_new_status(ctx);
         ctx.current_block_info().insert(my_status);
     }
+
+    fn debug_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "outgoing_gas_allowance": super::gas_per_shard(&self.outgoing_gas_allowance),
+        })
+    }
 }
 
 impl TrafficLight {
@@ -0,0 +1,129 @@
+/// Assigns accounts to shards the way a NEAR shard layout does.
+///
+/// The sorted boundary accounts split the account space into contiguous
+/// ranges, one per shard. Shard 0 holds everything below the first boundary,
+/// shard `i` everything from boundary `i - 1` (inclusive) up to boundary `i`
+/// (exclusive) and the last shard everything from the last boundary on. With
+/// `n` boundaries there are `n + 1` shards.
+///
+/// Resharding changes the boundaries, see [`AccountShardMap::split_shard`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountShardMap {
+    boundaries: Vec<String>,
+}
+
+impl AccountShardMap {
+    pub fn new(boundaries: Vec<String>) -> Self {
+        assert!(
+            boundaries.windows(2).all(|w| w[0] < w[1]),
+            "boundary accounts must be sorted and unique"
+        );
+        Self { boundaries }
+    }
+
+    /// Split the space of [`hashed_account_id`] accounts evenly across the
+    /// given number of shards.
+    pub fn uniform(num_shards: usize) -> Self {
+        assert!(num_shards > 0, "must have at least one shard");
+        let step = u64::MAX / num_shards as u64;
+        let boundaries = (1..num_shards as u64).map(|i| format!("{:016x}", i * step)).collect();
+        Self::new(boundaries)
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.boundaries.len() + 1
+    }
+
+    pub fn boundaries(&self) -> &[String] {
+        &self.boundaries
+    }
+
+    /// Index of the shard an account belongs to, within `0..num_shards()`.
+    pub fn shard_index(&self, account: &str) -> usize {
+        self.boundaries.partition_point(|boundary| boundary.as_str() <= account)
+    }
+
+    /// Split a shard in two at a new boundary account.
+    ///
+    /// Accounts below the boundary stay on shard `index`, the others move to
+    /// the new shard `index + 1`. All shards above move up by one index, like
+    /// in a real resharding where the parent is replaced by two children.
+    pub fn split_shard(&mut self, index: usize, boundary: String) {
+        assert!(index < self.num_shards(), "shard {index} does not exist");
+        assert_eq!(
+            self.shard_index(&boundary),
+            index,
+            "boundary {boundary} is outside of shard {index}"
+        );
+        let lower = if index == 0 { "" } else { self.boundaries[index - 1].as_str() };
+        assert_ne!(boundary, lower, "boundary {boundary} would leave shard {index} empty");
+        self.boundaries.insert(index, boundary);
+    }
+}
+
+/// Account ID for the account with the given index in a seeded account space.
+///
+/// The IDs are hashes, so consecutive indices spread evenly over the account
+/// space instead of all landing on the same shard. The same seed and index
+/// always give the same ID.
+pub fn hashed_account_id(seed: u64, index: usize) -> String {
+    // splitmix64 finalizer, good enough to spread indices over the key space
+    let mut x = seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    format!("{x:016x}.near")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(boundaries: &[&str]) -> AccountShardMap {
+        AccountShardMap::new(boundaries.iter().map(|b| b.to_string()).collect())
+    }
+
+    #[test]
+    fn boundary_accounts_belong_to_the_upper_shard() {
+        let map = map(&["b", "d"]);
+        assert_eq!(map.num_shards(), 3);
+        assert_eq!(map.shard_index(""), 0);
+        assert_eq!(map.shard_index("a"), 0);
+        assert_eq!(map.shard_index("az"), 0);
+        assert_eq!(map.shard_index("b"), 1);
+        assert_eq!(map.shard_index("b.near"), 1);
+        assert_eq!(map.shard_index("czzz"), 1);
+        assert_eq!(map.shard_index("d"), 2);
+        assert_eq!(map.shard_index("zzz"), 2);
+    }
+
+    #[test]
+    fn split_moves_only_the_upper_half() {
+        let mut map = map(&["b", "d"]);
+        map.split_shard(1, "c".to_string());
+        assert_eq!(map.num_shards(), 4);
+        assert_eq!(map.shard_index("a"), 0);
+        assert_eq!(map.shard_index("bz"), 1);
+        assert_eq!(map.shard_index("c"), 2);
+        assert_eq!(map.shard_index("d"), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of shard")]
+    fn split_rejects_foreign_boundary() {
+        map(&["b", "d"]).split_shard(0, "c".to_string());
+    }
+
+    #[test]
+    fn hashed_accounts_are_stable_and_spread() {
+        let map = AccountShardMap::uniform(4);
+        assert_eq!(hashed_account_id(7, 3), hashed_account_id(7, 3));
+        assert_ne!(hashed_account_id(7, 3), hashed_account_id(8, 3));
+
+        let mut per_shard = [0; 4];
+        for index in 0..4000 {
+            per_shard[map.shard_index(&hashed_account_id(7, index))] += 1;
+        }
+        assert!(per_shard.iter().all(|&n| n > 800), "{per_shard:?}");
+    }
+}
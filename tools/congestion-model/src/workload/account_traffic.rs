@@ -0,0 +1,178 @@
+use super::account_shard_map::{hashed_account_id, AccountShardMap};
+use super::Producer;
+use crate::{GGas, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+use rand::{Rng, RngCore};
+
+/// Transactions between random accounts, routed through an
+/// [`AccountShardMap`].
+///
+/// The model knows only shards, this workload adds accounts on top. Each
+/// transaction picks a random sender and receiver from a fixed set of hashed
+/// accounts. It is converted on the shard of the sender, executes a single
+/// receipt on the shard of the receiver and sends a refund back to the
+/// sender. Both receipts are addressed to their account, see
+/// [`crate::Receipt::receiver_account`].
+///
+/// Changing the boundaries of the map moves accounts between shards, which is
+/// how a resharding looks to the traffic. With
+/// [`AccountTrafficProducer::with_split`], the producer splits a shard of the
+/// map during the run and the model moves in-flight receipts along.
+pub struct AccountTrafficProducer {
+    /// Assignment of accounts to shards. Must have as many shards as the
+    /// model, one less with a split. If not set, the account space is split
+    /// evenly on init.
+    pub map: Option<AccountShardMap>,
+    pub accounts: Vec<String>,
+    pub tx_per_round: usize,
+    pub receipt_size: u64,
+    pub attached_gas: GGas,
+    pub execution_gas: GGas,
+    pub conversion_gas: GGas,
+    /// Set on init, from `map` or the even split.
+    active_map: AccountShardMap,
+    split: Option<Split>,
+}
+
+/// A shard split, see [`AccountTrafficProducer::with_split`].
+struct Split {
+    round: Round,
+    index: usize,
+    boundary: String,
+    /// The active map after the split, set on init.
+    map: AccountShardMap,
+}
+
+impl Producer for AccountTrafficProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        // the child of a split has no range before the split
+        let ranges = shards.len() - self.split.is_some() as usize;
+        self.active_map = self.map.clone().unwrap_or_else(|| AccountShardMap::uniform(ranges));
+        assert_eq!(
+            self.active_map.num_shards(),
+            ranges,
+            "account map must have one range per shard"
+        );
+        if let Some(split) = &mut self.split {
+            split.map = self.active_map.clone();
+            split.map.split_shard(split.index, split.boundary.clone());
+        }
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = vec![];
+        for _ in 0..self.tx_per_round {
+            let sender = &self.accounts[rng.gen_range(0..self.accounts.len())];
+            let receiver = &self.accounts[rng.gen_range(0..self.accounts.len())];
+            let mut tx = tx_factory(self.shard_of(shards, round, sender));
+            let receipt = ReceiptDefinition {
+                receiver: self.shard_of(shards, round, receiver),
+                size: self.receipt_size,
+                attached_gas: self.attached_gas,
+                execution_gas: self.execution_gas,
+            };
+            let main = tx.add_first_receipt(receipt, self.conversion_gas);
+            tx.set_receiver_account(main, receiver.clone());
+            let refund = tx.new_refund_receipt(main);
+            tx.set_receiver_account(refund, sender.clone());
+            transactions.push(tx);
+        }
+        transactions
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.split.as_ref().filter(|split| split.round == round).map(|split| &split.map)
+    }
+}
+
+impl AccountTrafficProducer {
+    /// Traffic between `num_accounts` accounts derived from `account_seed`.
+    pub fn new(num_accounts: usize, account_seed: u64, tx_per_round: usize) -> Self {
+        assert!(num_accounts > 0, "must have at least one account");
+        Self {
+            map: None,
+            accounts: (0..num_accounts).map(|i| hashed_account_id(account_seed, i)).collect(),
+            tx_per_round,
+            receipt_size: 1024,
+            attached_gas: 300 * TGAS,
+            execution_gas: 100 * TGAS,
+            conversion_gas: 5 * TGAS,
+            active_map: AccountShardMap::uniform(1),
+            split: None,
+        }
+    }
+
+    pub fn with_map(mut self, map: AccountShardMap) -> Self {
+        self.map = Some(map);
+        self
+    }
+
+    /// Split shard `index` of the map at `boundary` at the start of `round`.
+    ///
+    /// The model needs one shard more than the map has before the split. The
+    /// new upper half is shard `index + 1` and receives no traffic before the
+    /// split, the shards above it get the ranges above the split shard.
+    pub fn with_split(mut self, round: Round, index: usize, boundary: String) -> Self {
+        let map = AccountShardMap::uniform(1);
+        self.split = Some(Split { round, index, boundary, map });
+        self
+    }
+
+    fn shard_of(&self, shards: &[ShardId], round: Round, account: &str) -> ShardId {
+        match &self.split {
+            Some(split) if round >= split.round => shards[split.map.shard_index(account)],
+            Some(split) => {
+                let index = self.active_map.shard_index(account);
+                shards[if index > split.index { index + 1 } else { index }]
+            }
+            None => shards[self.active_map.shard_index(account)],
+        }
+    }
+}
+
+impl Default for AccountTrafficProducer {
+    fn default() -> Self {
+        // as many transactions per round as the default balanced workload
+        // submits with 4 shards
+        Self::new(1000, 0, 96)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NoQueueShard;
+    use crate::{CongestionStrategy, Model};
+
+    #[test]
+    fn receipts_go_to_the_shard_of_their_account() {
+        let map = AccountShardMap::uniform(4);
+        let producer = AccountTrafficProducer::new(50, 3, 20).with_map(map.clone());
+        let strategies: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::new(NoQueueShard {}) as _).collect();
+        let mut model = Model::new(strategies, Box::new(producer));
+        for _ in 0..5 {
+            model.step();
+        }
+
+        let mut checked = 0;
+        for tx in model.transactions.all_transactions() {
+            let sender = tx.sender_shard;
+            let stored = tx.future_receipts.values().chain(tx.executed_receipts.values());
+            for receipt in stored {
+                let account = receipt.receiver_account.as_deref().expect("account is set");
+                assert_eq!(receipt.receiver, model.shard_ids[map.shard_index(account)]);
+                if receipt.refund {
+                    assert_eq!(receipt.receiver, sender);
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 100, "only {checked} receipts checked");
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{GGas, ReceiptDefinition, ShardId, TransactionBuilder, TGAS};
+use rand::RngCore;
 
-use super::{utils, Producer};
+use super::Producer;
 
 /// Transaction producer that sends receipts from all shards to shard 0.
 ///
@@ -50,6 +51,7 @@ impl Producer for AllForOneProducer {
         _round: crate::Round,
         shards: &[ShardId],
         tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
     ) -> Vec<TransactionBuilder> {
         let mut hops_enabled = vec![];
         if self.enable_one_hop {
@@ -84,7 +86,7 @@ impl AllForOneProducer {
     fn produce_one_hop_tx(&self, shards: &[ShardId], tx: &mut TransactionBuilder) {
         let heavy_receipt = self.receipt_to_shard_0(shards, 1);
         let first = tx.add_first_receipt(heavy_receipt, self.conversion_gas);
-        tx.new_outgoing_receipt(first, utils::refund_receipt(tx.sender_shard()));
+        tx.new_refund_receipt(first);
     }
 
     /// Transaction with two receipts, a one executing locally with small
@@ -95,8 +97,8 @@ impl AllForOneProducer {
         let first = tx.add_first_receipt(light_receipt, self.conversion_gas);
         let second = tx.new_outgoing_receipt(first, heavy_receipt);
 
-        tx.new_outgoing_receipt(first, utils::refund_receipt(tx.sender_shard()));
-        tx.new_outgoing_receipt(second, utils::refund_receipt(tx.sender_shard()));
+        tx.new_refund_receipt(first);
+        tx.new_refund_receipt(second);
     }
 
     /// Transaction with a chain of three receipts, the last one spending the
@@ -117,9 +119,9 @@ impl AllForOneProducer {
         let second = tx.new_outgoing_receipt(first, light_receipt_2);
         let third = tx.new_outgoing_receipt(second, heavy_receipt);
 
-        tx.new_outgoing_receipt(first, utils::refund_receipt(tx.sender_shard()));
-        tx.new_outgoing_receipt(second, utils::refund_receipt(tx.sender_shard()));
-        tx.new_outgoing_receipt(third, utils::refund_receipt(tx.sender_shard()));
+        tx.new_refund_receipt(first);
+        tx.new_refund_receipt(second);
+        tx.new_refund_receipt(third);
     }
 
     fn receipt_to_shard_0(&self, shards: &[ShardId], prior_hops: u64) -> ReceiptDefinition {
@@ -1,5 +1,6 @@
-use super::{utils, Producer};
+use super::Producer;
 use crate::{GGas, ReceiptDefinition, ReceiptId, ShardId, TransactionBuilder, TGAS};
+use rand::RngCore;
 
 /// Transaction producer that sends N receipts from each shard to all other
 /// shards every round.
@@ -38,6 +39,7 @@ impl Producer for BalancedProducer {
         _round: crate::Round,
         shards: &[ShardId],
         tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
     ) -> Vec<TransactionBuilder> {
         let mut out = vec![];
         for _ in 0..self.num_tx_per_shard_pair {
@@ -132,7 +134,7 @@ impl BalancedProducer {
 
         let receipt = self.receipt(first_receiver, 1);
         let first = tx.add_first_receipt(receipt, self.conversion_gas);
-        tx.new_outgoing_receipt(first, utils::refund_receipt(tx.sender_shard()));
+        tx.new_refund_receipt(first);
         first
     }
 
@@ -145,7 +147,7 @@ impl BalancedProducer {
     ) -> ReceiptId {
         let next_receiver = if self.depth == depth { receiver } else { tx.sender_shard() };
         let next_receipt = tx.new_outgoing_receipt(prev, self.receipt(next_receiver, depth));
-        tx.new_outgoing_receipt(prev, utils::refund_receipt(tx.sender_shard()));
+        tx.new_refund_receipt(prev);
         next_receipt
     }
 
@@ -0,0 +1,178 @@
+use super::Producer;
+use crate::{GGas, ReceiptDefinition, Round, ShardId, TransactionBuilder, TransactionId, TGAS};
+use rand::{Rng, RngCore};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Users that keep a fixed number of transactions outstanding.
+///
+/// Unlike the other producers, which submit at a fixed rate no matter how the
+/// chain keeps up, each of the `users` submits a new transaction only when one
+/// of its previous transactions completes. The offered load therefore adapts
+/// to the latency. Users are spread evenly over the shards, each transaction
+/// calls a random shard and sends a refund back.
+///
+/// The producer learns about completions through
+/// [`crate::Model::on_transaction_complete`]. Register the callback returned
+/// by [`ClosedLoopProducer::completion_callback`], otherwise every user stops
+/// after its first transactions:
+///
+/// ```rust,ignore
+/// let producer = ClosedLoopProducer::new(100, 2);
+/// let completions = producer.completion_callback();
+/// let mut model = Model::new(strategies, Box::new(producer));
+/// model.on_transaction_complete(completions);
+/// ```
+///
+/// Completions are handled at the start of the next round, in the order the
+/// model reports them, and users refill in the order of their index. With
+/// all randomness drawn from the model, runs are reproducible.
+pub struct ClosedLoopProducer {
+    pub users: usize,
+    /// Transactions each user keeps outstanding, from submission until
+    /// completion.
+    pub outstanding_per_user: usize,
+    pub receipt_size: u64,
+    pub attached_gas: GGas,
+    pub execution_gas: GGas,
+    pub conversion_gas: GGas,
+    /// Transactions that completed since the last round, filled by the
+    /// completion callback.
+    completed: Rc<RefCell<Vec<TransactionId>>>,
+    /// User of each outstanding transaction.
+    owners: HashMap<TransactionId, usize>,
+    /// Number of outstanding transactions per user.
+    outstanding: Vec<usize>,
+}
+
+impl Producer for ClosedLoopProducer {
+    fn init(&mut self, _shards: &[ShardId]) {
+        self.completed.borrow_mut().clear();
+        self.owners.clear();
+        self.outstanding = vec![0; self.users];
+    }
+
+    fn produce_transactions(
+        &mut self,
+        _round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        for tx in self.completed.borrow_mut().drain(..) {
+            // transactions of other producers are not ours to refill
+            if let Some(user) = self.owners.remove(&tx) {
+                self.outstanding[user] -= 1;
+            }
+        }
+
+        let mut transactions = vec![];
+        for user in 0..self.users {
+            let sender = shards[user % shards.len()];
+            while self.outstanding[user] < self.outstanding_per_user {
+                let mut tx = tx_factory(sender);
+                let receipt = ReceiptDefinition {
+                    receiver: shards[rng.gen_range(0..shards.len())],
+                    size: self.receipt_size,
+                    attached_gas: self.attached_gas,
+                    execution_gas: self.execution_gas,
+                };
+                let main = tx.add_first_receipt(receipt, self.conversion_gas);
+                tx.new_refund_receipt(main);
+                self.owners.insert(tx.id(), user);
+                self.outstanding[user] += 1;
+                transactions.push(tx);
+            }
+        }
+        transactions
+    }
+}
+
+impl ClosedLoopProducer {
+    pub fn new(users: usize, outstanding_per_user: usize) -> Self {
+        Self {
+            users,
+            outstanding_per_user,
+            receipt_size: 1024,
+            attached_gas: 300 * TGAS,
+            execution_gas: 100 * TGAS,
+            conversion_gas: 5 * TGAS,
+            completed: Rc::default(),
+            owners: HashMap::new(),
+            outstanding: vec![0; users],
+        }
+    }
+
+    /// Callback to register with [`crate::Model::on_transaction_complete`].
+    pub fn completion_callback(&self) -> impl FnMut(TransactionId, Round) + 'static {
+        let completed = self.completed.clone();
+        move |tx, _round| completed.borrow_mut().push(tx)
+    }
+}
+
+impl Default for ClosedLoopProducer {
+    fn default() -> Self {
+        // 4 shards with the default gas limit execute about 40 of these
+        // transactions per round, 200 outstanding keep them busy
+        Self::new(100, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::{CongestionStrategy, Model};
+
+    /// Run the closed loop and return the model with the number of
+    /// transactions that completed in each round.
+    fn run(users: usize, outstanding_per_user: usize, rounds: usize) -> (Model, Vec<usize>) {
+        let producer = ClosedLoopProducer::new(users, outstanding_per_user);
+        let completions = producer.completion_callback();
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::new(producer)).with_seed(5);
+        model.on_transaction_complete(completions);
+
+        let completed = Rc::new(RefCell::new(vec![0; rounds + 1]));
+        let sink = completed.clone();
+        model.on_transaction_complete(move |_, round| sink.borrow_mut()[round as usize] += 1);
+        for _ in 0..rounds {
+            model.step();
+        }
+        let completed = completed.borrow().clone();
+        (model, completed)
+    }
+
+    #[test]
+    fn completions_are_replaced_in_the_next_round() {
+        let (model, completed) = run(30, 3, 50);
+        let mut submitted = vec![0; 51];
+        for view in model.transaction_views() {
+            submitted[view.submitted_at() as usize] += 1;
+        }
+        assert_eq!(submitted[1], 90);
+        for round in 2..=50 {
+            assert_eq!(submitted[round], completed[round - 1], "round {round}");
+        }
+        let outstanding = 90 - completed[50];
+        let progress = model.progress();
+        assert_eq!(progress.pending_transactions + progress.waiting_transactions, outstanding);
+        assert!(completed.iter().sum::<usize>() > 90);
+    }
+
+    #[test]
+    fn closed_loop_is_deterministic() {
+        let submissions = |model: &Model| -> Vec<(Round, ShardId)> {
+            model
+                .transaction_views()
+                .map(|view| (view.submitted_at(), view.sender_shard()))
+                .collect()
+        };
+        let (first, _) = run(40, 2, 30);
+        let (second, _) = run(40, 2, 30);
+        assert_eq!(submissions(&first), submissions(&second));
+        assert_eq!(first.latency_stats(), second.latency_stats());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{GGas, ReceiptDefinition, ShardId, TransactionBuilder, GAS_LIMIT, TGAS};
+use rand::RngCore;
 
 use super::Producer;
 
@@ -25,11 +26,16 @@ impl Producer for FairnessBenchmarkProducer {
         _round: crate::Round,
         shards: &[ShardId],
         tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
     ) -> Vec<TransactionBuilder> {
         let mut out = vec![];
 
         let busy_shard = shards[0];
         let other_shards = &shards[1..];
+        if other_shards.is_empty() {
+            // nobody to be fair to, and the loops below would never end
+            return out;
+        }
 
         let mut gas_to_busy = 0;
         let mut gas_to_non_busy = 0;
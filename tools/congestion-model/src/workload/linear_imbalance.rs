@@ -1,6 +1,7 @@
 use crate::{GGas, ReceiptDefinition, ShardId, TransactionBuilder, GAS_LIMIT, TGAS};
+use rand::RngCore;
 
-use super::{utils, Producer};
+use super::Producer;
 
 /// Shards X sends transactions to all shards >= X.
 ///
@@ -44,6 +45,7 @@ impl Producer for LinearImbalanceProducer {
         _round: crate::Round,
         shards: &[ShardId],
         tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
     ) -> Vec<TransactionBuilder> {
         let mut all_tx = vec![];
         for (sender_index, &sender_id) in shards.iter().enumerate() {
@@ -59,7 +61,7 @@ impl Producer for LinearImbalanceProducer {
                     let mut tx = tx_factory(sender_id);
                     let main_receipt_id = tx.add_first_receipt(main_receipt, self.conversion_gas);
                     // also add a refund receipt going back
-                    tx.new_outgoing_receipt(main_receipt_id, utils::refund_receipt(sender_id));
+                    tx.new_refund_receipt(main_receipt_id);
                     all_tx.push(tx);
                 }
             }
@@ -1,17 +1,38 @@
+mod account_shard_map;
+mod account_traffic;
 mod all_for_one;
 mod balanced;
+mod closed_loop;
 mod fairness_benchmark;
 mod linear_imbalance;
+mod oversized;
+mod read_traffic;
+mod refund_callbacks;
+mod rng_producer;
+mod scaled;
 mod transaction_builder;
 mod utils;
+mod whales;
+mod yield_resume;
 
+pub use account_shard_map::{hashed_account_id, AccountShardMap};
+pub use account_traffic::AccountTrafficProducer;
 pub use all_for_one::AllForOneProducer;
 pub use balanced::BalancedProducer;
+pub use closed_loop::ClosedLoopProducer;
 pub use fairness_benchmark::FairnessBenchmarkProducer;
 pub use linear_imbalance::LinearImbalanceProducer;
+pub use oversized::OversizedTxProducer;
+pub use read_traffic::ReadTrafficProducer;
+pub use refund_callbacks::RefundCallbackProducer;
+pub use rng_producer::RngProducer;
+pub use scaled::ScaledProducer;
 pub use transaction_builder::{ReceiptDefinition, ReceiptId, TransactionBuilder};
+pub use whales::WhaleProducer;
+pub use yield_resume::YieldResumeProducer;
 
 use crate::{Round, ShardId};
+use rand::RngCore;
 
 /// Produces workload in the form of transactions.
 ///
@@ -19,13 +40,41 @@ use crate::{Round, ShardId};
 /// execution.
 pub trait Producer {
     /// Set up initial state of the producer if necessary.
+    ///
+    /// This is called again when the model is reset and must reset all state
+    /// from previous rounds.
     fn init(&mut self, shards: &[ShardId]);
 
     /// Create transactions for a round.
+    ///
+    /// The random number generator is owned and seeded by the model. Producers
+    /// should draw all their randomness from it to keep model executions
+    /// reproducible.
     fn produce_transactions(
         &mut self,
         round: Round,
         shards: &[ShardId],
         tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
     ) -> Vec<TransactionBuilder>;
+
+    /// First round after `round` in which the producer may submit
+    /// transactions, used by [`crate::EventDriven`] to skip idle rounds.
+    ///
+    /// Rounds in between are skipped without calling
+    /// [`Producer::produce_transactions`]. The default is the next round,
+    /// which never skips anything.
+    fn next_submission_round(&self, round: Round) -> Round {
+        round + 1
+    }
+
+    /// Account map that takes effect at the start of `round`, if the producer
+    /// splits a shard in that round.
+    ///
+    /// The model then moves every receipt that has not executed, yet, to the
+    /// shard of its [`crate::Receipt::receiver_account`] under the new map,
+    /// see [`AccountShardMap::split_shard`]. The default never reshards.
+    fn resharding(&self, _round: Round) -> Option<&AccountShardMap> {
+        None
+    }
 }
@@ -0,0 +1,63 @@
+use super::{AccountShardMap, Producer};
+use crate::{Round, ShardId, TransactionBuilder};
+use rand::{Rng, RngCore};
+
+/// Wraps another producer and occasionally declares one of its transactions
+/// larger than it is.
+///
+/// Each transaction of the inner workload gets the declared size `tx_size`
+/// with `oversized_probability`, see [`TransactionBuilder::with_size`]. Its
+/// receipts stay the same. With [`crate::ModelConfig::max_tx_size`] below
+/// `tx_size`, sender shards reject these transactions, see
+/// [`crate::Model::tx_rejection_rate`].
+pub struct OversizedTxProducer {
+    pub inner: Box<dyn Producer>,
+    /// Probability for each produced transaction to be oversized.
+    pub oversized_probability: f64,
+    /// Declared size in bytes of oversized transactions.
+    pub tx_size: u64,
+}
+
+impl Producer for OversizedTxProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        self.inner
+            .produce_transactions(round, shards, tx_factory, rng)
+            .into_iter()
+            .map(|tx| {
+                if rng.gen_bool(self.oversized_probability) {
+                    tx.with_size(self.tx_size)
+                } else {
+                    tx
+                }
+            })
+            .collect()
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        self.inner.next_submission_round(round)
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl OversizedTxProducer {
+    pub fn new(inner: Box<dyn Producer>, oversized_probability: f64, tx_size: u64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&oversized_probability),
+            "oversized probability must be between 0 and 1"
+        );
+        Self { inner, oversized_probability, tx_size }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{AccountShardMap, Producer};
+use crate::{GGas, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+use rand::RngCore;
+
+/// Gas for converting a read transaction, the same as for
+/// [`super::BalancedProducer`] transactions.
+const READ_CONVERSION_GAS: GGas = 5 * TGAS;
+const READ_RECEIPT_SIZE: u64 = 200;
+
+/// Wraps another producer and adds pure read transactions on top of its
+/// workload.
+///
+/// Every round, each shard gets `reads_per_shard` transactions that are
+/// converted to a single read-only receipt executing on the same shard. These
+/// burn gas but produce nothing, which shows how much read traffic displaces
+/// the writes of the inner workload.
+pub struct ReadTrafficProducer {
+    pub inner: Box<dyn Producer>,
+    pub reads_per_shard: usize,
+    /// Execution gas of each read-only receipt.
+    pub read_gas: GGas,
+}
+
+impl Producer for ReadTrafficProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = self.inner.produce_transactions(round, shards, tx_factory, rng);
+        for &shard in shards {
+            for _ in 0..self.reads_per_shard {
+                let mut tx = tx_factory(shard);
+                let receipt = ReceiptDefinition {
+                    receiver: shard,
+                    size: READ_RECEIPT_SIZE,
+                    attached_gas: self.read_gas,
+                    execution_gas: self.read_gas,
+                };
+                let read = tx.add_first_receipt(receipt, READ_CONVERSION_GAS);
+                tx.mark_read_only(read);
+                transactions.push(tx);
+            }
+        }
+        transactions
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        self.inner.next_submission_round(round)
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl ReadTrafficProducer {
+    pub fn new(inner: Box<dyn Producer>, reads_per_shard: usize, read_gas: GGas) -> Self {
+        Self { inner, reads_per_shard, read_gas }
+    }
+}
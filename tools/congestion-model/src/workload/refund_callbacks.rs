@@ -0,0 +1,68 @@
+use super::{AccountShardMap, Producer};
+use crate::{GGas, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+use rand::{Rng, RngCore};
+
+const CALLBACK_RECEIPT_SIZE: u64 = 512;
+
+/// Wraps another producer and lets some of its refunds trigger a callback.
+///
+/// Each refund of the inner workload gets a callback with
+/// `callback_probability`. The callback executes on the shard of the refund,
+/// which is the sender shard, once the refund executed. It burns
+/// `callback_gas` on top of the inner workload, see
+/// [`crate::Model::refund_callback_load`].
+pub struct RefundCallbackProducer {
+    pub inner: Box<dyn Producer>,
+    /// Probability for each refund to trigger a callback.
+    pub callback_probability: f64,
+    /// Execution gas of each callback.
+    pub callback_gas: GGas,
+}
+
+impl Producer for RefundCallbackProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = self.inner.produce_transactions(round, shards, tx_factory, rng);
+        for tx in &mut transactions {
+            for refund in tx.refunds() {
+                if rng.gen_bool(self.callback_probability) {
+                    let callback = ReceiptDefinition {
+                        receiver: tx.sender_shard(),
+                        size: CALLBACK_RECEIPT_SIZE,
+                        attached_gas: self.callback_gas,
+                        execution_gas: self.callback_gas,
+                    };
+                    tx.new_refund_callback(refund, callback);
+                }
+            }
+        }
+        transactions
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        self.inner.next_submission_round(round)
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl RefundCallbackProducer {
+    pub fn new(inner: Box<dyn Producer>, callback_probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&callback_probability),
+            "callback probability must be between 0 and 1"
+        );
+        Self { inner, callback_probability, callback_gas: 20 * TGAS }
+    }
+}
@@ -0,0 +1,330 @@
+use super::{AccountShardMap, Producer};
+use crate::{Round, ShardId, TransactionBuilder};
+use rand::RngCore;
+use std::io::{self, BufRead, Write};
+
+/// Wraps another producer to record the random numbers it draws, or to feed
+/// it previously recorded numbers instead of the model's random stream.
+///
+/// A recording only holds the draws, one line per round, which is much
+/// smaller than a trace of all transactions. Replaying it reproduces the
+/// workload of the recorded run exactly, even after changes to the inner
+/// producer, as long as it draws the same kinds of numbers in the same order
+/// in every round. A replay that notices a different draw order panics, but
+/// changes that only reinterpret the drawn numbers go unnoticed.
+///
+/// Resetting the model starts a replay from the first round again. A
+/// recording continues in the same output, so only record one run per
+/// output.
+pub struct RngProducer {
+    pub inner: Box<dyn Producer>,
+    mode: Mode,
+}
+
+enum Mode {
+    Record(Box<dyn Write>),
+    Replay {
+        /// Recorded draws, by round.
+        rounds: Vec<(Round, Vec<Draw>)>,
+        /// Index of the next round to replay.
+        next: usize,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Draw {
+    U32(u32),
+    U64(u64),
+    Bytes(Vec<u8>),
+}
+
+impl Producer for RngProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        if let Mode::Replay { next, .. } = &mut self.mode {
+            *next = 0;
+        }
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        match &mut self.mode {
+            Mode::Record(out) => {
+                let mut recorder = Recorder { rng, draws: vec![] };
+                let transactions =
+                    self.inner.produce_transactions(round, shards, tx_factory, &mut recorder);
+                write_round(out, round, &recorder.draws).expect("failed to record RNG draws");
+                transactions
+            }
+            Mode::Replay { rounds, next } => {
+                let draws = match rounds.get(*next) {
+                    Some((recorded, draws)) if *recorded == round => {
+                        *next += 1;
+                        draws.as_slice()
+                    }
+                    // rounds without draws are not recorded
+                    _ => &[],
+                };
+                let mut replay = Replay { round, draws, pos: 0 };
+                let transactions =
+                    self.inner.produce_transactions(round, shards, tx_factory, &mut replay);
+                assert_eq!(
+                    replay.pos,
+                    draws.len(),
+                    "round {round} took fewer draws than recorded, the draw order changed"
+                );
+                transactions
+            }
+        }
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        self.inner.next_submission_round(round)
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl RngProducer {
+    /// Record the draws of the inner producer to `out`, e.g. a file.
+    ///
+    /// Panics during the run if writing fails.
+    pub fn record_draws(inner: Box<dyn Producer>, out: impl Write + 'static) -> Self {
+        Self { inner, mode: Mode::Record(Box::new(out)) }
+    }
+
+    /// Replay draws recorded with [`RngProducer::record_draws`] to the inner
+    /// producer. The model's random stream is not used.
+    ///
+    /// Fails if the recording is malformed, naming the line.
+    pub fn replay_draws(inner: Box<dyn Producer>, input: impl BufRead) -> io::Result<Self> {
+        let mut rounds = vec![];
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let parsed = parse_round(&line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: malformed RNG draws: {line}", index + 1),
+                )
+            })?;
+            rounds.push(parsed);
+        }
+        Ok(Self { inner, mode: Mode::Replay { rounds, next: 0 } })
+    }
+}
+
+/// Format: the round, then each draw as `u32:HEX`, `u64:HEX` or `bytes:HEX`.
+fn write_round(out: &mut dyn Write, round: Round, draws: &[Draw]) -> io::Result<()> {
+    if draws.is_empty() {
+        return Ok(());
+    }
+    write!(out, "{round}")?;
+    for draw in draws {
+        match draw {
+            Draw::U32(value) => write!(out, " u32:{value:x}")?,
+            Draw::U64(value) => write!(out, " u64:{value:x}")?,
+            Draw::Bytes(bytes) => {
+                write!(out, " bytes:")?;
+                for byte in bytes {
+                    write!(out, "{byte:02x}")?;
+                }
+            }
+        }
+    }
+    writeln!(out)?;
+    out.flush()
+}
+
+fn parse_round(line: &str) -> Option<(Round, Vec<Draw>)> {
+    let mut fields = line.split_whitespace();
+    let round = fields.next()?.parse().ok()?;
+    let draws = fields
+        .map(|field| match field.split_once(':')? {
+            ("u32", hex) => u32::from_str_radix(hex, 16).ok().map(Draw::U32),
+            ("u64", hex) => u64::from_str_radix(hex, 16).ok().map(Draw::U64),
+            ("bytes", hex) if hex.len() % 2 == 0 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect::<Option<_>>()
+                .map(Draw::Bytes),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    Some((round, draws))
+}
+
+/// Passes draws through from the model's random stream and remembers them.
+struct Recorder<'a> {
+    rng: &'a mut dyn RngCore,
+    draws: Vec<Draw>,
+}
+
+impl RngCore for Recorder<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.rng.next_u32();
+        self.draws.push(Draw::U32(value));
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = self.rng.next_u64();
+        self.draws.push(Draw::U64(value));
+        value
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+        self.draws.push(Draw::Bytes(dest.to_vec()));
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Hands out the draws recorded for one round.
+struct Replay<'a> {
+    round: Round,
+    draws: &'a [Draw],
+    pos: usize,
+}
+
+impl Replay<'_> {
+    fn next_draw(&mut self, kind: &str) -> &Draw {
+        let Some(draw) = self.draws.get(self.pos) else {
+            panic!("round {} took more draws than recorded, the draw order changed", self.round);
+        };
+        self.pos += 1;
+        let matches = matches!(
+            (draw, kind),
+            (Draw::U32(_), "u32") | (Draw::U64(_), "u64") | (Draw::Bytes(_), "bytes")
+        );
+        assert!(matches, "round {} drew {kind} where {draw:?} was recorded", self.round);
+        draw
+    }
+}
+
+impl RngCore for Replay<'_> {
+    fn next_u32(&mut self) -> u32 {
+        match self.next_draw("u32") {
+            Draw::U32(value) => *value,
+            _ => unreachable!(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.next_draw("u64") {
+            Draw::U64(value) => *value,
+            _ => unreachable!(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let round = self.round;
+        match self.next_draw("bytes") {
+            Draw::Bytes(bytes) if bytes.len() == dest.len() => dest.copy_from_slice(bytes),
+            Draw::Bytes(bytes) => {
+                panic!(
+                    "round {round} filled {} bytes where {} were recorded",
+                    dest.len(),
+                    bytes.len()
+                )
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::NepStrategy;
+    use crate::workload::AccountTrafficProducer;
+    use crate::{CongestionStrategy, Model};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Shared buffer, to read a recording while the model owns the writer.
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(producer: RngProducer, seed: u64) -> Model {
+        let shards: Vec<Box<dyn CongestionStrategy>> =
+            (0..4).map(|_| Box::<NepStrategy>::default() as _).collect();
+        let mut model = Model::new(shards, Box::new(producer)).with_seed(seed);
+        for _ in 0..20 {
+            model.step();
+        }
+        model
+    }
+
+    fn workload() -> Box<dyn Producer> {
+        Box::new(AccountTrafficProducer::new(100, 1, 30))
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_run() {
+        let recording = Buffer::default();
+        let recorded = run(RngProducer::record_draws(workload(), recording.clone()), 7);
+        let lines = recording.0.borrow().clone();
+        assert_eq!(lines.split(|&b| b == b'\n').filter(|line| !line.is_empty()).count(), 20);
+
+        // a different seed does not matter, all draws come from the recording
+        let replayed = run(RngProducer::replay_draws(workload(), &lines[..]).unwrap(), 8);
+        assert_eq!(replayed.gas_throughput().total, recorded.gas_throughput().total);
+        assert_eq!(replayed.latency_stats(), recorded.latency_stats());
+        let submissions = |model: &Model| -> Vec<_> {
+            model.transaction_views().map(|view| (view.sender_shard(), view.receipts())).collect()
+        };
+        assert_eq!(submissions(&replayed), submissions(&recorded));
+
+        // without the recording, the other seed gives another workload
+        let recording = Buffer::default();
+        let other = run(RngProducer::record_draws(workload(), recording.clone()), 8);
+        assert_ne!(recording.0.borrow().as_slice(), lines.as_slice());
+        assert_ne!(submissions(&other), submissions(&recorded));
+    }
+
+    #[test]
+    #[should_panic(expected = "more draws than recorded")]
+    fn replay_detects_changed_draw_order() {
+        let recording = Buffer::default();
+        run(RngProducer::record_draws(workload(), recording.clone()), 7);
+        let lines = recording.0.borrow().clone();
+        // the same workload with more transactions per round draws more
+        let more = Box::new(AccountTrafficProducer::new(100, 1, 31));
+        run(RngProducer::replay_draws(more, &lines[..]).unwrap(), 7);
+    }
+
+    #[test]
+    fn malformed_recording_names_the_line() {
+        let input = "1 u64:ff\n2 u64:zz\n";
+        let Err(err) = RngProducer::replay_draws(workload(), input.as_bytes()) else {
+            panic!("malformed recording accepted");
+        };
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+    }
+}
@@ -0,0 +1,75 @@
+use super::{AccountShardMap, Producer};
+use crate::{Round, ShardId, TransactionBuilder};
+use rand::RngCore;
+
+/// Wraps another producer and scales how often it submits transactions.
+///
+/// The inner producer is asked for a batch of transactions `multiplier` times
+/// per round on average. With a multiplier of 3, each round gets three
+/// batches. With 0.5, every other round gets one. Fractions carry over
+/// between rounds, so the long-run rate is exact. The shape of each batch is
+/// whatever the inner producer makes it, only the rate changes.
+pub struct ScaledProducer {
+    pub inner: Box<dyn Producer>,
+    pub multiplier: f64,
+    /// Batches owed to previous rounds, always in `[0, 1)` between rounds.
+    credit: f64,
+    /// Round of the last call to `produce_transactions`, 0 before the first.
+    last_round: Round,
+}
+
+impl Producer for ScaledProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.credit = 0.0;
+        self.last_round = 0;
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = vec![];
+        // Skipped rounds add their credit, too. Adding round by round keeps
+        // the same rounding as without skipping.
+        for _ in self.last_round..round {
+            self.credit += self.multiplier;
+        }
+        self.last_round = round;
+        while self.credit >= 1.0 {
+            self.credit -= 1.0;
+            transactions.extend(self.inner.produce_transactions(round, shards, tx_factory, rng));
+        }
+        transactions
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        if self.multiplier == 0.0 {
+            return Round::MAX;
+        }
+        let mut credit = self.credit;
+        let mut next = self.last_round;
+        while credit < 1.0 || next <= round {
+            credit += self.multiplier;
+            next += 1;
+        }
+        next
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl ScaledProducer {
+    pub fn new(inner: Box<dyn Producer>, multiplier: f64) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier >= 0.0,
+            "load multiplier must be a non-negative number"
+        );
+        Self { inner, multiplier, credit: 0.0, last_round: 0 }
+    }
+}
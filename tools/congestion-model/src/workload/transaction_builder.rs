@@ -1,6 +1,11 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
 
-use crate::{GGas, Receipt, Round, ShardId, Transaction, TransactionId};
+use super::utils;
+use crate::{
+    ExecutionMode, GGas, Receipt, ReceiptPriority, Round, ShardId, Transaction, TransactionId,
+};
+use rand::RngCore;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct ReceiptId(TransactionId, usize);
@@ -35,6 +40,39 @@ pub struct TransactionBuilder {
     outgoing: Vec<Vec<ReceiptId>>,
     /// Reverse edge index for quick access.
     dependencies: Vec<Vec<ReceiptId>>,
+    /// If set, outgoing receipts are sized relative to their predecessor.
+    size_growth: Option<f64>,
+    /// Upper bound for the size of each receipt.
+    max_receipt_size: Option<u64>,
+    /// Upper bound for the number of receipts, applied when building.
+    max_receipts: Option<usize>,
+    /// Correlation of size and gas in [`TransactionBuilder::random_receipt`].
+    size_gas_correlation: f64,
+    /// Priority of the first receipt, inherited by all others.
+    priority: ReceiptPriority,
+    /// Fee offered for inclusion.
+    fee: u64,
+    /// Priority gas attached to each receipt.
+    priority_gas: GGas,
+    /// Declared size, `None` for the size of the first receipt.
+    size: Option<u64>,
+    execution_mode: ExecutionMode,
+    /// Indices of receipts marked with [`TransactionBuilder::mark_read_only`].
+    read_only: HashSet<usize>,
+    /// Indices of receipts added with [`TransactionBuilder::new_refund_receipt`].
+    refunds: HashSet<usize>,
+    /// Indices of receipts added with
+    /// [`TransactionBuilder::new_refund_callback`].
+    refund_callbacks: HashSet<usize>,
+    /// Accounts set with [`TransactionBuilder::set_receiver_account`].
+    receiver_accounts: HashMap<usize, String>,
+    /// Costs set with [`TransactionBuilder::set_send_cost`].
+    send_costs: HashMap<usize, GGas>,
+    /// Costs set with [`TransactionBuilder::set_witness_cost`].
+    witness_costs: HashMap<usize, u64>,
+    /// Indices of receipts marked with [`TransactionBuilder::mark_yield`],
+    /// with their timeout receipts.
+    yields: HashMap<usize, Vec<ReceiptId>>,
 }
 
 impl TransactionBuilder {
@@ -47,6 +85,149 @@ impl TransactionBuilder {
             receipts: vec![],
             outgoing: vec![],
             dependencies: vec![],
+            size_growth: None,
+            max_receipt_size: None,
+            max_receipts: None,
+            size_gas_correlation: 0.0,
+            priority: ReceiptPriority::Normal,
+            fee: 0,
+            priority_gas: 0,
+            size: None,
+            execution_mode: ExecutionMode::Parallel,
+            read_only: HashSet::new(),
+            refunds: HashSet::new(),
+            refund_callbacks: HashSet::new(),
+            receiver_accounts: HashMap::new(),
+            send_costs: HashMap::new(),
+            witness_costs: HashMap::new(),
+            yields: HashMap::new(),
+        }
+    }
+
+    /// Submit the transaction with the given priority.
+    ///
+    /// The first receipt gets this priority and each receipt created by an
+    /// execution inherits the priority of the executed receipt.
+    pub fn with_priority(mut self, priority: ReceiptPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Choose whether receipts of the transaction can execute concurrently,
+    /// see [`ExecutionMode`]. Parallel by default.
+    pub fn with_execution_mode(mut self, execution_mode: ExecutionMode) -> Self {
+        self.execution_mode = execution_mode;
+        self
+    }
+
+    /// Offer a fee for including the transaction, 0 by default.
+    ///
+    /// Fees are not burnt or refunded, they only decide which transactions a
+    /// full mempool evicts with [`crate::MempoolEviction::LowestFee`].
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Attach `gas` to every receipt of the transaction on top of its own
+    /// attached gas, 0 by default.
+    ///
+    /// Strategies like [`crate::strategy::PriorityGas`] execute
+    /// receipts with more priority gas first. Each receipt burns all of it
+    /// when it executes and none of it is refunded, but it does not count
+    /// against the gas limit of the chunk.
+    pub fn with_priority_gas(mut self, gas: GGas) -> Self {
+        self.priority_gas = gas;
+        self
+    }
+
+    /// Declare the size of the transaction in bytes, which the sender shard
+    /// checks against [`crate::ModelConfig::max_tx_size`] before conversion.
+    /// Defaults to the size of the first receipt.
+    ///
+    /// Unlike receipt sizes, the declared size is not checked when the
+    /// transaction is built, so producers can submit oversized transactions.
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Size outgoing receipts as `factor` times the size of their predecessor,
+    /// ignoring the size given in their [`ReceiptDefinition`].
+    ///
+    /// Models contract calls that produce larger (or smaller) receipts than
+    /// their input, e.g. by returning big data. Applies to all receipts added
+    /// with [`TransactionBuilder::new_outgoing_receipt`], including refunds.
+    /// Grown sizes saturate at the max receipt size, or at `u64::MAX` if
+    /// there is none.
+    pub fn with_size_growth(mut self, factor: f64) -> Self {
+        assert!(factor.is_finite() && factor >= 0.0, "invalid size growth factor {factor}");
+        self.size_growth = Some(factor);
+        self
+    }
+
+    /// Limit the size of each receipt in the transaction.
+    ///
+    /// Adding a receipt with a larger explicit size panics. Sizes computed
+    /// from [`TransactionBuilder::with_size_growth`] are capped instead.
+    ///
+    /// The model applies [`crate::ModelConfig::max_receipt_size`] to all
+    /// builders it hands out to producers.
+    pub fn with_max_receipt_size(mut self, max_receipt_size: u64) -> Self {
+        self.max_receipt_size = Some(max_receipt_size);
+        self
+    }
+
+    /// Keep at most `max_receipts` receipts when the transaction is built and
+    /// drop the rest of its DAG.
+    ///
+    /// Receipts are kept in breadth-first order from the first receipt, so
+    /// the kept receipts form a connected DAG. The timeout receipts of a yield
+    /// count as its successors. See [`crate::TransactionView::truncated_receipt_count`].
+    ///
+    /// The model applies [`crate::ModelConfig::max_outstanding_receipts_per_tx`]
+    /// this way with [`crate::ReceiptCapPolicy::Truncate`].
+    pub fn with_max_receipts(mut self, max_receipts: usize) -> Self {
+        assert!(max_receipts >= 1, "a transaction needs at least its first receipt");
+        self.max_receipts = Some(max_receipts);
+        self
+    }
+
+    /// Correlate size and execution gas of the receipts drawn with
+    /// [`TransactionBuilder::random_receipt`], with a Pearson correlation of
+    /// `rho` in `[-1, 1]`.
+    ///
+    /// Real receipts with bigger payloads usually cost more. Without
+    /// correlation, which is the default, size and gas are drawn
+    /// independently and tiny receipts are as likely to be expensive as big
+    /// ones.
+    pub fn with_size_gas_correlation(mut self, rho: f64) -> Self {
+        assert!((-1.0..=1.0).contains(&rho), "size gas correlation {rho} is not in [-1, 1]");
+        self.size_gas_correlation = rho;
+        self
+    }
+
+    /// Definition of a receipt with random size and execution gas, each
+    /// uniformly distributed over its range and correlated as configured with
+    /// [`TransactionBuilder::with_size_gas_correlation`].
+    ///
+    /// The receipt attaches the largest gas of the range, so it never runs out
+    /// of gas. Add it with [`TransactionBuilder::add_first_receipt`] or
+    /// [`TransactionBuilder::new_outgoing_receipt`].
+    pub fn random_receipt(
+        &self,
+        receiver: ShardId,
+        size: RangeInclusive<u64>,
+        gas: RangeInclusive<GGas>,
+        rng: &mut dyn RngCore,
+    ) -> ReceiptDefinition {
+        let (size_quantile, gas_quantile) =
+            utils::correlated_uniforms(self.size_gas_correlation, rng);
+        ReceiptDefinition {
+            receiver,
+            size: quantile(&size, size_quantile),
+            attached_gas: *gas.end(),
+            execution_gas: quantile(&gas, gas_quantile),
         }
     }
 
@@ -69,15 +250,50 @@ impl TransactionBuilder {
     pub fn new_outgoing_receipt(
         &mut self,
         predecessor: ReceiptId,
-        def: ReceiptDefinition,
+        mut def: ReceiptDefinition,
     ) -> ReceiptId {
         self.assert_transaction_id(predecessor);
+        if let Some(factor) = self.size_growth {
+            // `as` saturates at `u64::MAX` for sizes too large to represent
+            let grown = (self.receipts[predecessor.1].size as f64 * factor) as u64;
+            def.size = grown.min(self.max_receipt_size.unwrap_or(u64::MAX));
+        }
         let successor = self.add_receipt(def);
         self.outgoing[predecessor.1].push(successor);
         self.dependencies[successor.1].push(predecessor);
         successor
     }
 
+    /// Create a refund receipt back to the sender shard after an existing
+    /// receipt finishes.
+    ///
+    /// Refunds are ordinary receipts for the model and the strategies, they
+    /// queue and compete for gas like any other receipt. They are only
+    /// marked, see [`Receipt::refund`], to measure them separately.
+    pub fn new_refund_receipt(&mut self, predecessor: ReceiptId) -> ReceiptId {
+        let refund =
+            self.new_outgoing_receipt(predecessor, utils::refund_receipt(self.sender_shard));
+        self.refunds.insert(refund.1);
+        refund
+    }
+
+    /// Create a receipt that a refund triggers when it executes, like a
+    /// callback on the signer's account reacting to the returned balance.
+    ///
+    /// The refund is no longer a sink of the DAG, the callback adds load to
+    /// the sender shard after the main work is done. Like every outgoing
+    /// receipt, the callback is only created once the refund executed, not
+    /// when the refund itself is created. Callbacks are ordinary receipts
+    /// marked with [`Receipt::refund_callback`] and can have outgoing
+    /// receipts of their own.
+    pub fn new_refund_callback(&mut self, refund: ReceiptId, def: ReceiptDefinition) -> ReceiptId {
+        self.assert_transaction_id(refund);
+        assert!(self.refunds.contains(&refund.1), "receipt {} is not a refund", refund.1);
+        let callback = self.new_outgoing_receipt(refund, def);
+        self.refund_callbacks.insert(callback.1);
+        callback
+    }
+
     /// Connect two existing receipts that depend on each other.
     ///
     /// Use this to model promise dependencies.
@@ -110,6 +326,81 @@ impl TransactionBuilder {
         self.dependencies[successor.1].push(predecessor);
     }
 
+    /// Turn a receipt into a read-only receipt, like a view call.
+    ///
+    /// Read-only receipts burn gas but change no state. They must be leaves of
+    /// the DAG and execute on the shard that created them, i.e. the receiver
+    /// of their predecessors, or the sender shard for the first receipt.
+    /// Nothing is refunded, their attached gas is set to their execution gas.
+    ///
+    /// The conditions are checked when the transaction is built, so the
+    /// receipt can be marked before or after adding its predecessors.
+    pub fn mark_read_only(&mut self, receipt: ReceiptId) {
+        self.assert_transaction_id(receipt);
+        self.read_only.insert(receipt.1);
+    }
+
+    /// Turn a receipt into a yield, like a contract calling `yield_create`.
+    ///
+    /// When it executes, the receipt burns its gas but creates none of its
+    /// outgoing receipts. It waits outside of all queues until it is resumed
+    /// or times out, see [`crate::YieldConfig`]. On resume, its outgoing
+    /// receipts are created. On timeout, the receipts added with
+    /// [`TransactionBuilder::new_timeout_receipt`] are created instead. The
+    /// other branch is never created.
+    ///
+    /// Like in nearcore, both branches continue on the shard of the yield, so
+    /// their first receipts must have the same receiver as the yield. This is
+    /// checked when the transaction is built.
+    pub fn mark_yield(&mut self, receipt: ReceiptId) {
+        self.assert_transaction_id(receipt);
+        self.yields.entry(receipt.1).or_default();
+    }
+
+    /// Create a receipt that a yield creates when it times out, instead of its
+    /// outgoing receipts. Marks the predecessor as a yield.
+    pub fn new_timeout_receipt(&mut self, yielded: ReceiptId, def: ReceiptDefinition) -> ReceiptId {
+        self.mark_yield(yielded);
+        let successor = self.add_receipt(def);
+        self.dependencies[successor.1].push(yielded);
+        self.yields.get_mut(&yielded.1).unwrap().push(successor);
+        successor
+    }
+
+    /// Address a receipt to an account.
+    ///
+    /// The model only routes by shard, so the receiver shard of the receipt
+    /// must already be the shard of the account. Workloads that map accounts
+    /// to shards, like [`super::AccountTrafficProducer`], set it to make the
+    /// account visible to strategies in [`Receipt::receiver_account`].
+    pub fn set_receiver_account(&mut self, receipt: ReceiptId, account: String) {
+        self.assert_transaction_id(receipt);
+        self.receiver_accounts.insert(receipt.1, account);
+    }
+
+    /// Let the sender of a receipt burn `send_cost` when it forwards the
+    /// receipt to another shard, see [`Receipt::send_cost`]. 0 by default.
+    ///
+    /// Sending and executing a receipt cost different amounts. Set a higher
+    /// send cost for receipts that are expensive to serialize, like big or
+    /// deeply nested payloads.
+    pub fn set_send_cost(&mut self, receipt: ReceiptId, send_cost: GGas) {
+        self.assert_transaction_id(receipt);
+        self.send_costs.insert(receipt.1, send_cost);
+    }
+
+    /// Let a receipt add `witness_cost` bytes to the state witness of the
+    /// chunk that executes it, see [`Receipt::witness_cost`]. Its size by
+    /// default.
+    ///
+    /// The witness holds the state a receipt touches, which can be much more
+    /// than the receipt itself, like for a cheap call that reads a big
+    /// contract.
+    pub fn set_witness_cost(&mut self, receipt: ReceiptId, witness_cost: u64) {
+        self.assert_transaction_id(receipt);
+        self.witness_costs.insert(receipt.1, witness_cost);
+    }
+
     /// Unique ID of the transaction within the model execution.
     pub fn id(&self) -> TransactionId {
         self.id
@@ -121,9 +412,78 @@ impl TransactionBuilder {
         self.sender_shard
     }
 
-    pub(crate) fn build(self) -> Transaction {
+    /// Refund receipts added so far, in the order they were added.
+    pub fn refunds(&self) -> Vec<ReceiptId> {
+        let mut refunds: Vec<usize> = self.refunds.iter().copied().collect();
+        refunds.sort_unstable();
+        refunds.into_iter().map(|index| ReceiptId(self.id, index)).collect()
+    }
+
+    /// Change the round in which the transaction is submitted.
+    pub(crate) fn set_round(&mut self, round: Round) {
+        self.round = round;
+    }
+
+    /// Check that the transaction is a DAG of receipts between the given
+    /// shards. Producers are trusted to build valid transactions, this is for
+    /// transactions built by hand.
+    pub(crate) fn validate(&self, shards: &[ShardId]) {
+        assert!(!self.receipts.is_empty(), "transaction must have a first receipt");
+        assert!(shards.contains(&self.sender_shard), "unknown sender shard {}", self.sender_shard);
+        for (index, def) in self.receipts.iter().enumerate() {
+            assert!(
+                shards.contains(&def.receiver),
+                "receipt {index} has unknown receiver shard {}",
+                def.receiver
+            );
+        }
+        // Kahn's algorithm, receipts left with dependencies are on a cycle
+        let mut missing: Vec<usize> = self.dependencies.iter().map(Vec::len).collect();
+        let mut ready: Vec<usize> = (0..missing.len()).filter(|&i| missing[i] == 0).collect();
+        let mut visited = 0;
+        while let Some(index) = ready.pop() {
+            visited += 1;
+            for next in &self.outgoing[index] {
+                missing[next.1] -= 1;
+                if missing[next.1] == 0 {
+                    ready.push(next.1);
+                }
+            }
+        }
+        assert_eq!(visited, self.receipts.len(), "receipt dependencies contain a cycle");
+    }
+
+    /// Mutable access to the definitions of all receipts added so far.
+    ///
+    /// Allows wrapping producers to modify receipts after the inner producer
+    /// has built the DAG.
+    pub(crate) fn receipts_mut(&mut self) -> &mut [ReceiptDefinition] {
+        &mut self.receipts
+    }
+
+    pub(crate) fn build(mut self) -> Transaction {
+        let truncated_receipts = match self.max_receipts {
+            Some(max) => self.truncate(max),
+            None => 0,
+        };
+        for &index in &self.read_only {
+            self.check_read_only(index);
+            let def = &mut self.receipts[index];
+            def.attached_gas = def.execution_gas;
+        }
+        for (&index, timeouts) in &self.yields {
+            self.check_yield(index, timeouts);
+        }
         let transaction_id = self.id();
         let initial_receipt = self.first_receipt();
+        let total_attached_gas = self
+            .receipts
+            .iter()
+            .map(|def| def.attached_gas)
+            .chain(self.send_costs.values().copied())
+            .chain(self.receipts.iter().map(|_| self.priority_gas))
+            .try_fold(self.tx_conversion_cost, |sum, gas| sum.checked_add(gas))
+            .expect("total gas attached to a transaction overflows u64");
 
         // Convert ReceiptDefinition to Receipt
         let receipts: HashMap<ReceiptId, Receipt> = self
@@ -132,16 +492,24 @@ impl TransactionBuilder {
             .enumerate()
             .map(|(index, receipt_def)| {
                 let id = ReceiptId(transaction_id, index);
-                (
-                    id,
-                    Receipt::new_future_receipt(
-                        ReceiptId(transaction_id, index),
-                        receipt_def.receiver,
-                        receipt_def.size,
-                        receipt_def.attached_gas,
-                        receipt_def.execution_gas,
-                    ),
-                )
+                let mut receipt = Receipt::new_future_receipt(
+                    ReceiptId(transaction_id, index),
+                    receipt_def.receiver,
+                    self.sender_shard,
+                    receipt_def.size,
+                    receipt_def.attached_gas,
+                    receipt_def.execution_gas,
+                );
+                receipt.read_only = self.read_only.contains(&index);
+                receipt.refund = self.refunds.contains(&index);
+                receipt.refund_callback = self.refund_callbacks.contains(&index);
+                receipt.receiver_account = self.receiver_accounts.remove(&index);
+                receipt.send_cost = self.send_costs.get(&index).copied().unwrap_or(0);
+                if let Some(&witness_cost) = self.witness_costs.get(&index) {
+                    receipt.witness_cost = witness_cost;
+                }
+                receipt.attached_priority_gas = self.priority_gas;
+                (id, receipt)
             })
             .collect();
 
@@ -160,6 +528,11 @@ impl TransactionBuilder {
                 (receipt_id, outgoing)
             })
             .collect();
+        let yield_timeouts = self
+            .yields
+            .into_iter()
+            .map(|(index, timeouts)| (ReceiptId(transaction_id, index), timeouts))
+            .collect();
         let dependencies = self
             .dependencies
             .into_iter()
@@ -176,17 +549,90 @@ impl TransactionBuilder {
             sender_shard: self.sender_shard,
             initial_receipt_receiver: receipts[&initial_receipt].receiver,
             initial_receipt_gas: receipts[&initial_receipt].attached_gas,
+            priority: self.priority,
+            fee: self.fee,
+            evicted_at: None,
+            size: self.size.unwrap_or(receipts[&initial_receipt].size),
+            rejection: None,
+            truncated_receipts,
+            execution_mode: self.execution_mode,
+            sequential_backlog: VecDeque::new(),
             initial_receipt,
             tx_conversion_cost: self.tx_conversion_cost,
+            total_attached_gas,
+            send_gas_burnt: 0,
+            priority_gas: self.priority_gas,
             outgoing,
             dependencies,
             future_receipts: receipts,
             pending_receipts: HashSet::new(),
             dropped_receipts: HashMap::new(),
             executed_receipts: HashMap::new(),
+            yield_timeouts,
         }
     }
 
+    /// Remove all but the first `max_receipts` receipts in breadth-first
+    /// order and renumber the rest. Returns how many were removed.
+    ///
+    /// Each kept receipt except the first was discovered from a kept
+    /// predecessor, so the result is connected. Removing receipts and edges
+    /// cannot create a cycle.
+    fn truncate(&mut self, max_receipts: usize) -> usize {
+        let num_receipts = self.receipts.len();
+        if num_receipts <= max_receipts {
+            return 0;
+        }
+        let mut new_index: Vec<Option<usize>> = vec![None; num_receipts];
+        new_index[0] = Some(0);
+        let mut kept = vec![0];
+        let mut next = 0;
+        while next < kept.len() && kept.len() < max_receipts {
+            let index = kept[next];
+            next += 1;
+            let timeouts = self.yields.get(&index).into_iter().flatten();
+            for successor in self.outgoing[index].iter().chain(timeouts) {
+                if kept.len() == max_receipts {
+                    break;
+                }
+                if new_index[successor.1].is_none() {
+                    new_index[successor.1] = Some(kept.len());
+                    kept.push(successor.1);
+                }
+            }
+        }
+
+        let id = self.id;
+        let remap = |ids: &[ReceiptId]| -> Vec<ReceiptId> {
+            ids.iter().filter_map(|old| new_index[old.1].map(|new| ReceiptId(id, new))).collect()
+        };
+        self.outgoing = kept.iter().map(|&old| remap(&self.outgoing[old])).collect();
+        self.dependencies = kept.iter().map(|&old| remap(&self.dependencies[old])).collect();
+        self.receipts = kept.iter().map(|&old| self.receipts[old].clone()).collect();
+        let remap_set =
+            |set: &HashSet<usize>| set.iter().filter_map(|&old| new_index[old]).collect();
+        self.read_only = remap_set(&self.read_only);
+        self.refunds = remap_set(&self.refunds);
+        self.refund_callbacks = remap_set(&self.refund_callbacks);
+        self.receiver_accounts = std::mem::take(&mut self.receiver_accounts)
+            .into_iter()
+            .filter_map(|(old, account)| new_index[old].map(|new| (new, account)))
+            .collect();
+        self.send_costs = std::mem::take(&mut self.send_costs)
+            .into_iter()
+            .filter_map(|(old, cost)| new_index[old].map(|new| (new, cost)))
+            .collect();
+        self.witness_costs = std::mem::take(&mut self.witness_costs)
+            .into_iter()
+            .filter_map(|(old, cost)| new_index[old].map(|new| (new, cost)))
+            .collect();
+        self.yields = std::mem::take(&mut self.yields)
+            .into_iter()
+            .filter_map(|(old, timeouts)| new_index[old].map(|new| (new, remap(&timeouts))))
+            .collect();
+        num_receipts - kept.len()
+    }
+
     /// Returns the ID of the initial receipt from converting the transaction to a receipt.
     ///
     /// Because [`TransactionBuilder::add_first_receipt`] only works on empty
@@ -197,6 +643,9 @@ impl TransactionBuilder {
 
     /// Add a receipt to the transaction and assign a [`ReceiptId`] to it.
     fn add_receipt(&mut self, def: ReceiptDefinition) -> ReceiptId {
+        if let Some(max) = self.max_receipt_size {
+            assert!(def.size <= max, "receipt size {} exceeds max receipt size {max}", def.size);
+        }
         let id = ReceiptId(self.id, self.receipts.len());
         self.receipts.push(def);
         self.outgoing.push(vec![]);
@@ -204,14 +653,133 @@ impl TransactionBuilder {
         id
     }
 
+    fn check_read_only(&self, index: usize) {
+        assert!(
+            self.outgoing[index].is_empty(),
+            "read-only receipt {index} must not have outgoing receipts"
+        );
+        let receiver = self.receipts[index].receiver;
+        let creators: Vec<ShardId> = if index == 0 {
+            vec![self.sender_shard]
+        } else {
+            self.dependencies[index].iter().map(|pred| self.receipts[pred.1].receiver).collect()
+        };
+        assert!(
+            creators.iter().all(|&creator| creator == receiver),
+            "read-only receipt {index} must execute on the shard that creates it"
+        );
+    }
+
+    fn check_yield(&self, index: usize, timeouts: &[ReceiptId]) {
+        assert!(!self.read_only.contains(&index), "read-only receipt {index} must not yield");
+        let receiver = self.receipts[index].receiver;
+        assert!(
+            self.outgoing[index]
+                .iter()
+                .chain(timeouts)
+                .all(|next| self.receipts[next.1].receiver == receiver),
+            "receipts after yield {index} must execute on the shard of the yield"
+        );
+    }
+
     #[track_caller]
     fn assert_transaction_id(&self, id: ReceiptId) {
         assert_eq!(id.0, self.id, "receipt belongs to a different transaction");
     }
 }
 
+/// Value at the quantile `q` in `[0, 1)` of a uniform distribution over the
+/// range.
+fn quantile(range: &RangeInclusive<u64>, q: f64) -> u64 {
+    let (start, end) = (*range.start(), *range.end());
+    assert!(start <= end, "empty range {range:?}");
+    let offset = ((end - start) as f64 + 1.0) * q;
+    start.saturating_add(offset as u64).min(end)
+}
+
 impl ReceiptId {
     pub fn transaction_id(&self) -> TransactionId {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::NoQueueShard;
+    use crate::workload::BalancedProducer;
+    use crate::{Model, ReceiptDefinition, TGAS};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Pearson correlation of size and execution gas over many random
+    /// receipts.
+    fn sample_correlation(rho: f64) -> f64 {
+        let mut model =
+            Model::new(vec![Box::new(NoQueueShard {})], Box::<BalancedProducer>::default());
+        let shard = model.shard_ids()[0];
+        let builder =
+            model.transactions.new_transaction_builder(shard, 1).with_size_gas_correlation(rho);
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let samples: Vec<(f64, f64)> = (0..20_000)
+            .map(|_| {
+                let def = builder.random_receipt(shard, 100..=10_000, TGAS..=300 * TGAS, &mut rng);
+                assert!((100..=10_000).contains(&def.size));
+                assert!((TGAS..=300 * TGAS).contains(&def.execution_gas));
+                assert_eq!(def.attached_gas, 300 * TGAS);
+                (def.size as f64, def.execution_gas as f64)
+            })
+            .collect();
+
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|s| s.1).sum::<f64>() / n;
+        let cov = samples.iter().map(|s| (s.0 - mean_x) * (s.1 - mean_y)).sum::<f64>();
+        let var_x = samples.iter().map(|s| (s.0 - mean_x).powi(2)).sum::<f64>();
+        let var_y = samples.iter().map(|s| (s.1 - mean_y).powi(2)).sum::<f64>();
+        cov / (var_x * var_y).sqrt()
+    }
+
+    #[test]
+    fn random_receipts_have_requested_correlation() {
+        for rho in [-0.8, 0.0, 0.3, 0.9, 1.0] {
+            let measured = sample_correlation(rho);
+            assert!((measured - rho).abs() < 0.03, "requested {rho}, measured {measured}");
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_a_connected_dag() {
+        let mut model =
+            Model::new(vec![Box::new(NoQueueShard {})], Box::<BalancedProducer>::default());
+        let shard = model.shard_ids()[0];
+        let def = ReceiptDefinition {
+            receiver: shard,
+            size: 100,
+            attached_gas: TGAS,
+            execution_gas: TGAS,
+        };
+        // a -> b -> d -> e and a -> c -> d, plus a refund after e
+        let mut tx = model.transactions.new_transaction_builder(shard, 1);
+        let a = tx.add_first_receipt(def.clone(), 0);
+        let b = tx.new_outgoing_receipt(a, def.clone());
+        let c = tx.new_outgoing_receipt(a, def.clone());
+        let d = tx.new_outgoing_receipt(b, def.clone());
+        tx.new_dependency(c, d);
+        let e = tx.new_outgoing_receipt(d, def);
+        tx.new_refund_receipt(e);
+
+        assert_eq!(tx.truncate(4), 2);
+        tx.validate(&[shard]);
+        assert_eq!(tx.receipts.len(), 4);
+        assert_eq!(tx.outgoing, [vec![b, c], vec![d], vec![d], vec![]]);
+        assert_eq!(tx.dependencies, [vec![], vec![a], vec![a], vec![b, c]]);
+        assert!(tx.refunds.is_empty());
+        assert_eq!(tx.truncate(4), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not in [-1, 1]")]
+    fn size_gas_correlation_out_of_range_panics() {
+        sample_correlation(1.5);
+    }
+}
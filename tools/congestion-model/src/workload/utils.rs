@@ -1,4 +1,6 @@
 use crate::{GGas, ReceiptDefinition, ShardId, GGAS};
+use rand::{Rng, RngCore};
+use std::f64::consts::PI;
 
 // The refund is just a Transfer. I think this it is fairly small but I didn't
 // check the exact number.
@@ -14,3 +16,37 @@ pub fn refund_receipt(receiver: ShardId) -> ReceiptDefinition {
         execution_gas: REFUND_GAS,
     }
 }
+
+/// Two uniform samples from `[0, 1)` with the given Pearson correlation.
+///
+/// Uses a Gaussian copula: two standard normal samples are correlated and then
+/// mapped back to uniform through the normal CDF. That mapping weakens the
+/// correlation, so the normal samples use `2 sin(pi * rho / 6)` instead of
+/// `rho`, which is exact for uniform marginals.
+pub(crate) fn correlated_uniforms(rho: f64, rng: &mut dyn RngCore) -> (f64, f64) {
+    let normal_rho = 2.0 * (PI * rho / 6.0).sin();
+    // Box-Muller, `1 - u` avoids ln(0)
+    let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+    let angle = 2.0 * PI * rng.gen::<f64>();
+    let (z1, z2) = (radius * angle.cos(), radius * angle.sin());
+    let z2 = normal_rho * z1 + (1.0 - normal_rho * normal_rho).max(0.0).sqrt() * z2;
+    (normal_cdf(z1).min(MAX_UNIFORM), normal_cdf(z2).min(MAX_UNIFORM))
+}
+
+/// Largest f64 below 1.
+const MAX_UNIFORM: f64 = 1.0 - f64::EPSILON / 2.0;
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / 2f64.sqrt()))
+}
+
+/// Abramowitz and Stegun 7.1.26, absolute error below 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
@@ -0,0 +1,76 @@
+use super::{AccountShardMap, Producer};
+use crate::{Round, ShardId, TransactionBuilder};
+use rand::{Rng, RngCore};
+
+/// Wraps another producer and occasionally turns one of its transactions into
+/// a whale.
+///
+/// A whale is a transaction whose heaviest receipt has its execution gas,
+/// attached gas and size multiplied by `whale_gas_multiplier`. With a large
+/// enough multiplier, a single whale receipt takes up most of a chunk and
+/// everything queued behind it on the receiving shard has to wait. This is
+/// useful to study head-of-line blocking.
+///
+/// Note that the chunk gas limit is a soft limit in the model. A whale asking
+/// for more than the gas limit still executes in a single chunk.
+pub struct WhaleProducer {
+    pub inner: Box<dyn Producer>,
+    /// Probability for each produced transaction to become a whale.
+    pub whale_probability: f64,
+    /// Factor applied to gas and size of the heaviest receipt of a whale.
+    pub whale_gas_multiplier: u64,
+}
+
+impl Producer for WhaleProducer {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.inner.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = self.inner.produce_transactions(round, shards, tx_factory, rng);
+        for tx in &mut transactions {
+            if rng.gen_bool(self.whale_probability) {
+                self.make_whale(tx);
+            }
+        }
+        transactions
+    }
+
+    fn next_submission_round(&self, round: Round) -> Round {
+        self.inner.next_submission_round(round)
+    }
+
+    fn resharding(&self, round: Round) -> Option<&AccountShardMap> {
+        self.inner.resharding(round)
+    }
+}
+
+impl WhaleProducer {
+    pub fn new(
+        inner: Box<dyn Producer>,
+        whale_probability: f64,
+        whale_gas_multiplier: u64,
+    ) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&whale_probability),
+            "whale probability must be between 0 and 1"
+        );
+        Self { inner, whale_probability, whale_gas_multiplier }
+    }
+
+    fn make_whale(&self, tx: &mut TransactionBuilder) {
+        let Some(heaviest) = tx.receipts_mut().iter_mut().max_by_key(|def| def.execution_gas)
+        else {
+            return;
+        };
+        heaviest.execution_gas = heaviest.execution_gas.saturating_mul(self.whale_gas_multiplier);
+        heaviest.attached_gas = heaviest.attached_gas.saturating_mul(self.whale_gas_multiplier);
+        heaviest.size = heaviest.size.saturating_mul(self.whale_gas_multiplier);
+    }
+}
@@ -0,0 +1,62 @@
+use super::Producer;
+use crate::{GGas, ReceiptDefinition, Round, ShardId, TransactionBuilder, TGAS};
+use rand::RngCore;
+
+/// Transactions that yield and wait for an external resume, like chain
+/// signature requests.
+///
+/// Each round, every shard sends `tx_per_shard` calls to the next shard. The
+/// call yields, see [`TransactionBuilder::mark_yield`]. When resumed, it
+/// continues with `callback_gas`, when timed out with the cheaper
+/// `timeout_gas`. Either way, a refund goes back to the sender.
+pub struct YieldResumeProducer {
+    pub tx_per_shard: usize,
+    /// Execution gas of the yielding call.
+    pub yield_gas: GGas,
+    /// Execution gas of the callback after a resume.
+    pub callback_gas: GGas,
+    /// Execution gas of the callback after a timeout.
+    pub timeout_gas: GGas,
+}
+
+impl Producer for YieldResumeProducer {
+    fn init(&mut self, _shards: &[ShardId]) {}
+
+    fn produce_transactions(
+        &mut self,
+        _round: Round,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        let mut transactions = vec![];
+        for (i, &sender) in shards.iter().enumerate() {
+            let receiver = shards[(i + 1) % shards.len()];
+            for _ in 0..self.tx_per_shard {
+                let mut tx = tx_factory(sender);
+                let call = tx.add_first_receipt(receipt(receiver, self.yield_gas), 5 * TGAS);
+                let callback = tx.new_outgoing_receipt(call, receipt(receiver, self.callback_gas));
+                tx.new_refund_receipt(callback);
+                let timeout = tx.new_timeout_receipt(call, receipt(receiver, self.timeout_gas));
+                tx.new_refund_receipt(timeout);
+                transactions.push(tx);
+            }
+        }
+        transactions
+    }
+}
+
+fn receipt(receiver: ShardId, execution_gas: GGas) -> ReceiptDefinition {
+    ReceiptDefinition { receiver, size: 1024, attached_gas: 300 * TGAS, execution_gas }
+}
+
+impl Default for YieldResumeProducer {
+    fn default() -> Self {
+        Self {
+            tx_per_shard: 20,
+            yield_gas: 10 * TGAS,
+            callback_gas: 50 * TGAS,
+            timeout_gas: 5 * TGAS,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Runs each strategy on a fixed scenario and compares a compact summary of
+//! the outcome against a checked-in golden file in `tests/golden/`.
+//!
+//! A refactoring that changes how a strategy behaves shows up as a drift from
+//! its golden file. If the change is intended, regenerate the files with
+//!
+//! ```bash
+//! UPDATE_GOLDEN=1 cargo test -p congestion-model --test golden
+//! ```
+//!
+//! The workload does not use the random number generator and the model
+//! config disables all randomized effects, so the summaries do not depend on
+//! the RNG implementation.
+
+use congestion_model::strategy::{
+    AdaptiveThreshold, FancyGlobalTransactionStop, GlobalNearlyDone, GlobalTxStopShard,
+    NepStrategy, NewTxLast, NoQueueShard, PriorityAging, PriorityGas, SimpleBackpressure,
+    SmoothTrafficLight, TrafficLight,
+};
+use congestion_model::workload::Producer;
+use congestion_model::{
+    CongestionStrategy, Model, ModelConfig, ReceiptDefinition, ShardId, TransactionBuilder, PGAS,
+    TGAS,
+};
+use rand::RngCore;
+use std::path::PathBuf;
+
+const SHARDS: usize = 4;
+/// Rounds during which the workload submits transactions.
+const LOAD_ROUNDS: u64 = 40;
+/// Rounds executed per strategy, including rounds without new load.
+const ROUNDS: u64 = 150;
+const SEED: u64 = 7;
+/// Relative tolerance for floating point values in the summary.
+const TOLERANCE: f64 = 1e-6;
+
+type StrategyFactory = fn() -> Box<dyn CongestionStrategy>;
+
+fn strategies() -> Vec<(&'static str, StrategyFactory)> {
+    vec![
+        ("No queues", || Box::new(NoQueueShard {})),
+        ("Global TX stop", || Box::<GlobalTxStopShard>::default()),
+        ("Simple backpressure", || Box::<SimpleBackpressure>::default()),
+        ("Fancy Stop", || Box::<FancyGlobalTransactionStop>::default()),
+        ("New TX last", || Box::<NewTxLast>::default()),
+        ("Traffic Light", || Box::<TrafficLight>::default()),
+        ("Smooth Traffic Light", || Box::<SmoothTrafficLight>::default()),
+        ("Adaptive Threshold", || Box::<AdaptiveThreshold>::default()),
+        ("Priority Aging", || Box::<PriorityAging>::default()),
+        ("Priority Gas", || Box::<PriorityGas>::default()),
+        ("Global Nearly Done", || Box::<GlobalNearlyDone>::default()),
+        ("NEP", || Box::<NepStrategy>::default()),
+        ("NEP 10/1 Pgas", || Box::new(NepStrategy::default().with_gas_limits(10 * PGAS, PGAS))),
+    ]
+}
+
+/// More cross-shard work than the shards can execute, with extra load on the
+/// first shard, derived from the round and shard index only.
+struct FixedWorkload;
+
+impl Producer for FixedWorkload {
+    fn init(&mut self, _shards: &[ShardId]) {}
+
+    fn produce_transactions(
+        &mut self,
+        round: u64,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        if round > LOAD_ROUNDS {
+            return vec![];
+        }
+        let mut out = vec![];
+        for (i, &sender) in shards.iter().enumerate() {
+            for j in 0..8 {
+                let step = round as usize + i + j;
+                let gas = (50 + 37 * (step % 7) as u64) * TGAS;
+                let def = |receiver, size| ReceiptDefinition {
+                    receiver,
+                    size,
+                    attached_gas: gas,
+                    execution_gas: gas,
+                };
+                let first_receiver =
+                    if j % 2 == 0 { shards[0] } else { shards[(i + j) % shards.len()] };
+                let mut tx = tx_factory(sender);
+                let first = tx.add_first_receipt(def(first_receiver, 1000 * (j as u64 + 1)), TGAS);
+                tx.new_outgoing_receipt(first, def(shards[(i + j + 1) % shards.len()], 500));
+                out.push(tx);
+            }
+        }
+        out
+    }
+}
+
+/// Stable, line-based summary of a run.
+fn summary(strategy: StrategyFactory) -> String {
+    let shards = (0..SHARDS).map(|_| strategy()).collect();
+    let mut model =
+        Model::new(shards, Box::new(FixedWorkload)).with_seed(SEED).with_config(ModelConfig {
+            drop_probability: 0.0,
+            gas_limit_jitter: 0.0,
+            ..ModelConfig::default()
+        });
+    let mut queued_gas_integral = 0;
+    for _ in 0..ROUNDS {
+        model.step();
+        queued_gas_integral += model.gas_ledger().queued;
+    }
+    let progress = model.progress();
+    let latency = model.latency_stats();
+    let dropped: usize = model.transaction_views().map(|view| view.dropped_receipt_count()).sum();
+    [
+        format!("finished = {}", progress.finished_transactions),
+        format!("failed = {}", progress.failed_transactions),
+        format!("unresolved = {}", latency.unresolved),
+        format!("latency_p50 = {}", latency.p50),
+        format!("latency_p99 = {}", latency.p99),
+        format!("dropped_receipts = {dropped}"),
+        format!("queued_pgas_rounds = {:.6}", queued_gas_integral as f64 / PGAS as f64),
+        format!("congestion_index = {:.6}", model.congestion_index()),
+    ]
+    .join("\n")
+        + "\n"
+}
+
+fn golden_path(strategy_name: &str) -> PathBuf {
+    let file_name: String = strategy_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(file_name + ".txt")
+}
+
+fn parse(summary: &str) -> Vec<(&str, &str)> {
+    summary
+        .lines()
+        .map(|line| line.split_once(" = ").unwrap_or_else(|| panic!("malformed line {line:?}")))
+        .collect()
+}
+
+/// Lines of `actual` that differ from `golden`. Integers must match exactly,
+/// floating point values within [`TOLERANCE`].
+fn drift(golden: &str, actual: &str) -> Vec<String> {
+    let (golden, actual) = (parse(golden), parse(actual));
+    if golden.iter().map(|(key, _)| key).ne(actual.iter().map(|(key, _)| key)) {
+        return vec![format!("keys changed from {golden:?} to {actual:?}")];
+    }
+    let mut out = vec![];
+    for ((key, expected), (_, value)) in golden.into_iter().zip(actual) {
+        let matches = if expected.contains('.') {
+            let (expected, value): (f64, f64) = (expected.parse().unwrap(), value.parse().unwrap());
+            (expected - value).abs() <= TOLERANCE * expected.abs().max(1.0)
+        } else {
+            expected == value
+        };
+        if !matches {
+            out.push(format!("{key}: expected {expected}, got {value}"));
+        }
+    }
+    out
+}
+
+#[test]
+fn strategies_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = vec![];
+    for (name, strategy) in strategies() {
+        let actual = summary(strategy);
+        let path = golden_path(name);
+        if update {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(golden) => {
+                for line in drift(&golden, &actual) {
+                    failures.push(format!("{name}: {line}"));
+                }
+            }
+            Err(err) => failures.push(format!("{name}: cannot read {}: {err}", path.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "summaries drifted from the golden files, rerun with UPDATE_GOLDEN=1 if this is \
+        intended:\n{}",
+        failures.join("\n")
+    );
+}
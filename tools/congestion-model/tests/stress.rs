@@ -0,0 +1,162 @@
+//! Drives the model across a broad matrix of configurations and checks that
+//! it neither panics nor hangs.
+//!
+//! Strategies are allowed to deadlock or build up unbounded queues. This only
+//! checks that the model itself survives every configuration for a fixed
+//! number of rounds.
+//!
+//! This takes several minutes and is ignored by default. Run it with
+//!
+//! ```bash
+//! cargo test --release -p congestion-model --test stress -- --ignored --nocapture
+//! ```
+
+use congestion_model::strategy::{
+    AdaptiveThreshold, FancyGlobalTransactionStop, GlobalNearlyDone, GlobalTxStopShard,
+    NepStrategy, NewTxLast, NoQueueShard, PriorityAging, SimpleBackpressure, SmoothTrafficLight,
+    TrafficLight,
+};
+use congestion_model::workload::{
+    AllForOneProducer, BalancedProducer, FairnessBenchmarkProducer, LinearImbalanceProducer,
+    Producer, WhaleProducer,
+};
+use congestion_model::{
+    CongestionStrategy, Model, ModelConfig, ShardId, TransactionBuilder, GAS_LIMIT, PGAS,
+};
+use rand::RngCore;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Rounds during which the workload submits transactions.
+const LOAD_ROUNDS: u64 = 50;
+/// Rounds executed per configuration, including rounds without new load.
+const ROUNDS: u64 = 200;
+/// Generous upper bound for the wall-clock time of a single configuration.
+/// Exceeding it means the model hangs, e.g. in an endless loop.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+type StrategyFactory = fn() -> Box<dyn CongestionStrategy>;
+type WorkloadFactory = fn() -> Box<dyn Producer>;
+
+fn strategies() -> Vec<(&'static str, StrategyFactory)> {
+    vec![
+        ("No queues", || Box::new(NoQueueShard {})),
+        ("Global TX stop", || Box::<GlobalTxStopShard>::default()),
+        ("Simple backpressure", || Box::<SimpleBackpressure>::default()),
+        ("Fancy Stop", || Box::<FancyGlobalTransactionStop>::default()),
+        ("New TX last", || Box::<NewTxLast>::default()),
+        ("Traffic Light", || Box::<TrafficLight>::default()),
+        ("Smooth Traffic Light", || Box::<SmoothTrafficLight>::default()),
+        ("Adaptive Threshold", || Box::<AdaptiveThreshold>::default()),
+        ("Priority Aging", || Box::<PriorityAging>::default()),
+        ("Global Nearly Done", || Box::<GlobalNearlyDone>::default()),
+        ("NEP", || Box::<NepStrategy>::default()),
+        ("NEP 10/1 Pgas", || Box::new(NepStrategy::default().with_gas_limits(10 * PGAS, PGAS))),
+    ]
+}
+
+fn workloads() -> Vec<(&'static str, WorkloadFactory)> {
+    vec![
+        ("Balanced", || Box::<BalancedProducer>::default()),
+        ("Shard War", || {
+            Box::new(BalancedProducer::with_sizes_and_fan_out(vec![100, 100, 100, 100_000], 4))
+        }),
+        ("Mixed All To One", || Box::<AllForOneProducer>::default()),
+        ("Relayed Hot", || Box::new(AllForOneProducer::hot_tg())),
+        ("Big Linear Imbalance", || Box::new(LinearImbalanceProducer::big_receipts())),
+        ("Fairness Test", || Box::<FairnessBenchmarkProducer>::default()),
+        ("Balanced Whales", || {
+            Box::new(WhaleProducer::new(Box::<BalancedProducer>::default(), 0.1, 20))
+        }),
+    ]
+}
+
+/// Stops producing transactions after [`LOAD_ROUNDS`].
+struct Bounded(Box<dyn Producer>);
+
+impl Producer for Bounded {
+    fn init(&mut self, shards: &[ShardId]) {
+        self.0.init(shards);
+    }
+
+    fn produce_transactions(
+        &mut self,
+        round: u64,
+        shards: &[ShardId],
+        tx_factory: &mut dyn FnMut(ShardId) -> TransactionBuilder,
+        rng: &mut dyn RngCore,
+    ) -> Vec<TransactionBuilder> {
+        if round > LOAD_ROUNDS {
+            return vec![];
+        }
+        self.0.produce_transactions(round, shards, tx_factory, rng)
+    }
+}
+
+/// Runs a configuration on its own thread. Returns an error if the model
+/// panics or does not finish all rounds within [`TIMEOUT`].
+fn run_with_timeout(
+    num_shards: usize,
+    strategy: StrategyFactory,
+    workload: WorkloadFactory,
+    config: ModelConfig,
+    seed: u64,
+) -> Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let shards = (0..num_shards).map(|_| strategy()).collect();
+        let mut model =
+            Model::new(shards, Box::new(Bounded(workload()))).with_seed(seed).with_config(config);
+        for _ in 0..ROUNDS {
+            model.step();
+        }
+        // make sure evaluation code copes with the final state, too
+        model.progress();
+        model.user_experience();
+        assert!(model.gas_ledger().is_balanced(), "gas ledger is not balanced");
+        sender.send(()).unwrap();
+    });
+    match receiver.recv_timeout(TIMEOUT) {
+        Ok(()) => Ok(()),
+        Err(RecvTimeoutError::Disconnected) => Err("panicked".to_owned()),
+        // The thread keeps running in the background until the test exits.
+        Err(RecvTimeoutError::Timeout) => Err(format!("did not finish within {TIMEOUT:?}")),
+    }
+}
+
+#[test]
+#[ignore = "takes several minutes, run explicitly"]
+fn no_panics_across_parameter_space() {
+    let mut failures = vec![];
+    let mut num_runs = 0;
+    for (strategy_name, strategy) in strategies() {
+        for (workload_name, workload) in workloads() {
+            for num_shards in [1, 2, 4, 7] {
+                for gas_limit in [GAS_LIMIT / 4, GAS_LIMIT, 4 * GAS_LIMIT] {
+                    for seed in [0, 1] {
+                        let config = ModelConfig {
+                            gas_limit_schedule: vec![(0, gas_limit)],
+                            ..ModelConfig::default()
+                        };
+                        num_runs += 1;
+                        if let Err(err) =
+                            run_with_timeout(num_shards, strategy, workload, config, seed)
+                        {
+                            failures.push(format!(
+                                "{strategy_name} / {workload_name} / {num_shards} shards / \
+                                {gas_limit} GGas / seed {seed}: {err}"
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {num_runs} runs failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}